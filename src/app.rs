@@ -1,24 +1,32 @@
 use std::{
-    collections::HashSet,
-    io::{self, Write},
+    collections::{HashMap, HashSet},
+    io,
     path::Path,
+    time::{Duration, Instant},
 };
-use termion::{
-    cursor::Goto, event::Key, input::MouseTerminal, raw::IntoRawMode, screen::AlternateScreen,
-};
+use termion::{input::MouseTerminal, raw::IntoRawMode, screen::AlternateScreen};
 use tui::{
-    backend::TermionBackend,
-    layout::{Constraint, Direction, Layout},
+    backend::{Backend, TermionBackend},
+    layout::{Constraint, Direction, Layout, Rect},
+    terminal::Frame,
     Terminal,
 };
 use unicode_width::UnicodeWidthStr;
 
 use crate::{
     component,
+    git::{
+        Area, BranchStatus, CherryPickState, CherryPickStatus, Commit, Comparison, Hunk, LogFilter,
+        MergeAnalysis, MergeOutcome, MergeStrategy, RebaseState, RebaseStatus, ReflogEntry,
+        RemoteBranch, Signature, WorkingTreeStatus,
+    },
+    issue::{self, BranchTemplate},
     util::{
-        event::{Event, Events},
+        self,
+        event::{BracketedPasteTerminal, Event, Events, Key, Mouse, MouseButton, MouseKind},
         StatefulList,
     },
+    worker::{Request, Response, Worker},
 };
 
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -27,6 +35,144 @@ pub enum InputMode {
     Search,
     Help,
     ShowLog,
+    /// Searching for the branch to use with `source_branch`.
+    SelectTarget(Target),
+    /// A rebase stopped at conflicts.
+    Rebase,
+    /// A merge stopped at conflicts.
+    MergeConflicts,
+    /// A cherry-pick stopped at conflicts.
+    CherryPickConflicts,
+    /// Editing the filter of the log view.
+    LogFilter,
+    /// The commits unique to each of two branches.
+    Compare,
+    /// The diff between the compared branches.
+    Diff,
+    /// Editing the description of the highlighted branch.
+    EditDescription,
+    /// The staged, unstaged, untracked and conflicted files.
+    Status,
+    /// The hunks of the file highlighted in the status, to stage or unstage one at a time.
+    Hunks,
+    /// Typing the message of a commit.
+    CommitMessage,
+    /// Editing the message of the commit highlighted in the log.
+    Reword,
+    /// Where HEAD, or the highlighted branch, pointed before.
+    Reflog,
+    /// Typing the name of a branch to create at the highlighted reflog entry.
+    ReflogBranch,
+    /// Confirming the checkout of the highlighted reflog entry.
+    ReflogCheckout,
+    /// The branches checked out last, to switch to with a number key.
+    Recent,
+}
+
+/// What the branch chosen in `InputMode::SelectTarget` is for.
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum Target {
+    Rebase,
+    Compare,
+}
+
+/// How to go on with a merge that stopped at conflicts.
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum MergeStep {
+    Commit,
+    Abort,
+}
+
+/// The choices of the merge popup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeForm {
+    pub branch: String,
+    pub analysis: MergeAnalysis,
+    pub strategy: MergeStrategy,
+    pub message: String,
+}
+
+impl MergeForm {
+    /// A fast-forward is offered only when it is possible.
+    pub fn strategies(&self) -> Vec<MergeStrategy> {
+        let mut strategies = vec![MergeStrategy::Commit, MergeStrategy::Squash];
+        if self.analysis == MergeAnalysis::FastForward {
+            strategies.insert(0, MergeStrategy::FastForward);
+        }
+        strategies
+    }
+
+    pub fn next_strategy(&mut self) {
+        let strategies = self.strategies();
+        let index = strategies
+            .iter()
+            .position(|s| *s == self.strategy)
+            .map(|i| (i + 1) % strategies.len())
+            .unwrap_or(0);
+        self.strategy = strategies[index];
+    }
+}
+
+/// The commit of the log view whose message is being changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewordForm {
+    pub branch: String,
+    pub oid: git2::Oid,
+    pub id: String,
+    pub message: String,
+    /// The commit is on the upstream of the branch already.
+    pub pushed: bool,
+}
+
+/// How to go on with a cherry-pick that stopped at conflicts.
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum CherryPickStep {
+    Continue,
+    Abort,
+}
+
+/// A change the worker is making to the repository. Actions that change the repository
+/// wait until the worker reports back, not to work on a HEAD or an index that moves.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    /// `<branch> onto <target>`.
+    Rebase(String),
+    /// The summary of the commit, while its hooks run.
+    Commit(String),
+    Push,
+    DeleteBranches,
+    Sync,
+    Merge {
+        branch: String,
+        into: String,
+    },
+    /// Onto the branch.
+    CherryPick(String),
+    /// The abbreviated id of the commit.
+    Reword(String),
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Operation::Rebase(rebasing) => write!(f, "Rebasing {}", rebasing),
+            Operation::Commit(_) => write!(f, "Running the commit hooks"),
+            Operation::Push => write!(f, "Pushing"),
+            Operation::DeleteBranches => write!(f, "Deleting branches"),
+            Operation::Sync => write!(f, "Syncing"),
+            Operation::Merge { branch, into } => write!(f, "Merging {} into {}", branch, into),
+            Operation::CherryPick(onto) => write!(f, "Cherry-picking onto {}", onto),
+            Operation::Reword(id) => write!(f, "Rewording {}", id),
+        }
+    }
+}
+
+/// How to go on with a rebase that stopped at conflicts.
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum RebaseStep {
+    Continue,
+    Skip,
+    Abort,
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -34,67 +180,623 @@ pub enum Command {
     Checkout,
     #[allow(dead_code)]
     DeleteBranch,
+    Push,
+    /// Push with `--force-with-lease`.
+    ForcePush,
+    /// Rebase `source_branch` onto `target_branch`.
+    Rebase,
+    /// Merge the branch of `App::merge` into HEAD.
+    Merge,
+    /// Cherry-pick `App::cherry_pick_commits` onto HEAD.
+    CherryPick,
 }
 
 impl Command {
-    pub fn run(&self, app: &App) -> anyhow::Result<()> {
+    /// Runs the command and returns a message describing what was done.
+    pub fn run(&self, app: &App) -> anyhow::Result<String> {
         match self {
-            Command::Checkout => {
-                Self::run_checkout(app)?;
-            }
-            Command::DeleteBranch => {
-                Self::run_delete_branches(app)?;
-            }
+            Command::Checkout => Self::run_checkout(app),
+            // started by `App::perform`, see e.g. `App::start_rebase`
+            Command::DeleteBranch
+            | Command::Push
+            | Command::ForcePush
+            | Command::Rebase
+            | Command::Merge
+            | Command::CherryPick => Ok(String::new()),
         }
-        Ok(())
     }
 
-    fn run_checkout(app: &App) -> anyhow::Result<()> {
-        if let Some(ref branch) = app.selected_branch() {
-            app.repo.checkout(branch)?;
+    fn run_checkout(app: &App) -> anyhow::Result<String> {
+        match app.selected_branch() {
+            Some(branch) => {
+                app.repo.checkout(branch)?;
+                Ok(format!("Switched to branch '{}'", branch))
+            }
+            None => Ok(String::new()),
         }
-        Ok(())
     }
+}
 
-    fn run_delete_branches(app: &App) -> anyhow::Result<()> {
-        app.selected
-            .iter()
-            .map(|x| app.repo.delete_branch(x))
-            .collect()
+/// Where things were drawn in the last frame, for mouse hit-testing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Areas {
+    pub branch_list: Rect,
+    pub log: Option<Rect>,
+    pub buttons: Option<component::ConfirmButtons>,
+}
+
+/// What the event loop should do after a key press.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    None,
+    /// Run the command waiting for confirmation.
+    RunCommand,
+    /// Switch to a mode that shows something read from the repository, e.g. the hunks of
+    /// a file. Some modes fall back to another one, like a merge to its conflicts.
+    Open(InputMode),
+    /// Show the reflog of the highlighted branch instead of HEAD's, or the other way round.
+    SwitchReflog,
+    /// Stage or unstage the highlighted file of the status, or hunk of the hunks view.
+    Stage,
+    /// Open the issue of the highlighted branch, or of the highlighted commit of the log.
+    OpenIssue,
+    /// Fast-forward the marked or highlighted branches to their upstream.
+    Sync,
+    Rebase(RebaseStep),
+    Merge(MergeStep),
+    CherryPick(CherryPickStep),
+    SaveDescription,
+    /// Commit the staged changes with the typed message.
+    Commit,
+    /// Change the message of a commit of the log view.
+    Reword,
+    /// Check out the highlighted reflog entry, detaching HEAD.
+    CheckoutReflogEntry,
+    /// Create a branch at the highlighted reflog entry and check it out.
+    BranchAtReflogEntry,
+    /// Check out the recent branch at the index, without asking.
+    CheckoutRecent(usize),
+    Quit,
+}
+
+impl Action {
+    /// Whether the action changes the repository, so it has to wait for the worker.
+    fn changes_repository(&self) -> bool {
+        !matches!(
+            self,
+            Action::None
+                | Action::Quit
+                | Action::Open(_)
+                | Action::SwitchReflog
+                | Action::OpenIssue
+        )
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notification {
+    Info(String),
+    Error(String),
+}
+
 pub struct App {
     pub input: String,
+    /// The input the branches were last filtered by.
+    filtered_by: String,
     pub input_mode: InputMode,
     pub selected: HashSet<String>,
     pub repo: crate::git::Repository,
     pub branches: StatefulList<String>,
     pub all_branches: Vec<String>,
+    pub notification: Option<Notification>,
+    pub config: crate::config::Config,
+    /// Set by `git ex pick`: Ctrl+o picks branches instead of checking them out.
+    pub picking: bool,
+    pub picked: Vec<String>,
+    pub areas: Areas,
+    /// The highlighted commit of the log view.
+    pub log_cursor: usize,
+    /// The commits marked in the log view, to be cherry-picked.
+    pub marked_commits: Vec<git2::Oid>,
+    pub log_filter: LogFilter,
+    /// The filter bar of the log view, applied with Enter.
+    pub log_filter_input: String,
+    pub comparison: Option<Comparison>,
+    /// Branch descriptions from the git config, by branch name.
+    pub descriptions: HashMap<String, String>,
+    /// The description being edited, saved with Enter.
+    pub description_input: String,
+    /// The lines of the diff view, `None` until the worker has them.
+    pub diff: Option<Vec<String>>,
+    /// The scroll position of the compare and diff views.
+    pub popup_scroll: u16,
+    last_click: Option<(Instant, usize)>,
+    /// Cached answers of the background worker.
+    pub current_branch: Option<String>,
+    pub logs: HashMap<String, Result<Vec<Commit>, String>>,
+    pub statuses: HashMap<String, BranchStatus>,
+    pub loading_statuses: bool,
+    pub fetching: bool,
+    /// Upstreams of the branches to delete, and whether to delete them as well.
+    pub remote_branches: Vec<RemoteBranch>,
+    pub delete_remote: bool,
+    /// The branch picked before searching for a target, and the target.
+    pub source_branch: Option<String>,
+    pub target_branch: Option<String>,
+    pub rebase: Option<RebaseState>,
+    /// What the worker is changing in the repository, until it reports back.
+    pub running: Option<Operation>,
+    /// Set when a command runs with `exit_after_command`, to exit once the worker is done.
+    exiting: bool,
+    pub merge: Option<MergeForm>,
+    /// The unresolved files of a merge in progress.
+    pub merge_conflicts: Option<Vec<String>>,
+    /// The cherry-pick stopped at conflicts, with the commits still to apply.
+    pub cherry_pick: Option<CherryPickState>,
+    /// `None` until the worker answers, or in a bare repository.
+    pub working_tree: Option<WorkingTreeStatus>,
+    /// When the working tree was last asked for, to notice edits the watcher can't see.
+    working_tree_requested: Instant,
+    /// The highlighted file of the status view, in the order of `WorkingTreeStatus::files`.
+    pub status_cursor: usize,
+    /// The file whose hunks are shown, and whether they are the staged ones.
+    pub hunk_file: Option<(String, bool)>,
+    pub hunks: Vec<Hunk>,
+    pub hunk_cursor: usize,
+    pub commit_message: String,
+    /// Who the commit is made as.
+    pub signature: Option<Signature>,
+    pub reword: Option<RewordForm>,
+    /// `HEAD` or the branch whose reflog is shown.
+    pub reflog_name: String,
+    pub reflog: Vec<ReflogEntry>,
+    pub reflog_cursor: usize,
+    /// The name of the branch to create at the highlighted reflog entry.
+    pub reflog_branch_input: String,
+    /// The branches checked out before the current one, most recent first.
+    pub recent_branches: Vec<String>,
+    worker: Worker,
 }
 
 const TOP_MARGIN: u16 = 1;
 const HELP_MESSAGE_HEIGHT: u16 = 1;
 const TEXT_INPUT_HEIGHT: u16 = 3;
+const STATUS_BAR_HEIGHT: u16 = 1;
 const LIST_WIDTH_PERCENTAGE: u16 = 40;
 const LOG_LIMIT: usize = 40;
+const REFLOG_LIMIT: usize = 100;
+const DESCRIPTION_HEIGHT: u16 = 6;
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+/// How often the working tree is read again, as edits to files aren't watched.
+const WORKING_TREE_INTERVAL: Duration = Duration::from_secs(2);
 
 impl App {
     pub fn new<P: AsRef<Path>>(path: P) -> anyhow::Result<App> {
-        let repo = crate::git::Repository::new(path)?;
+        let repo = crate::git::Repository::new(&path)?;
         let branches = repo.branches()?;
+        let config = repo.config()?;
+        let worker = Worker::spawn(&path)?;
 
-        Ok(App {
+        let mut app = App {
             input: String::new(),
+            filtered_by: String::new(),
             input_mode: InputMode::Search,
             selected: HashSet::new(),
-            repo: repo,
+            repo,
             all_branches: branches.clone(),
             branches: StatefulList::with_items(branches),
-        })
+            notification: None,
+            config,
+            picking: false,
+            picked: Vec::new(),
+            areas: Areas::default(),
+            log_cursor: 0,
+            marked_commits: Vec::new(),
+            log_filter: LogFilter::default(),
+            log_filter_input: String::new(),
+            comparison: None,
+            descriptions: HashMap::new(),
+            description_input: String::new(),
+            diff: None,
+            popup_scroll: 0,
+            last_click: None,
+            current_branch: None,
+            logs: HashMap::new(),
+            statuses: HashMap::new(),
+            loading_statuses: false,
+            fetching: false,
+            remote_branches: Vec::new(),
+            delete_remote: false,
+            source_branch: None,
+            target_branch: None,
+            rebase: None,
+            running: None,
+            exiting: false,
+            merge: None,
+            merge_conflicts: None,
+            cherry_pick: None,
+            working_tree: None,
+            working_tree_requested: Instant::now(),
+            status_cursor: 0,
+            hunk_file: None,
+            hunks: Vec::new(),
+            hunk_cursor: 0,
+            commit_message: String::new(),
+            signature: None,
+            reword: None,
+            reflog_name: "HEAD".to_owned(),
+            reflog: Vec::new(),
+            reflog_cursor: 0,
+            reflog_branch_input: String::new(),
+            recent_branches: Vec::new(),
+            worker,
+        };
+        app.invalidate();
+        app.check_rebase();
+        app.check_merge();
+        app.check_cherry_pick();
+        Ok(app)
+    }
+
+    /// Drops everything cached from the repository and asks the worker to load it again.
+    /// Called after git-ex changed the repository and when refs change on disk.
+    pub fn invalidate(&mut self) {
+        self.worker.invalidate();
+        self.logs.clear();
+        self.statuses.clear();
+        self.worker.request(Request::CurrentBranch);
+        self.worker
+            .request(Request::BranchStatuses(self.all_branches.clone()));
+        self.loading_statuses = true;
+        self.request_working_tree();
+        match self.input_mode {
+            InputMode::ShowLog | InputMode::LogFilter => self.request_logs(),
+            InputMode::Compare => self.request_comparison(),
+            InputMode::Diff => self.request_diff(),
+            _ => {}
+        }
+    }
+
+    /// Applies whatever the worker has finished since the last call.
+    pub fn update(&mut self) {
+        if self.worker.refs_changed() {
+            // branches may have been created or deleted in another terminal
+            if let Err(e) = self.reload_branches() {
+                self.notification = Some(Notification::Error(e.to_string()));
+            }
+            // the rebase or merge may have been finished with git
+            if self.rebase.is_some() {
+                self.check_rebase();
+            }
+            if self.merge_conflicts.is_some() {
+                self.check_merge();
+            }
+            if self.cherry_pick.is_some() {
+                self.check_cherry_pick();
+            }
+            self.invalidate();
+        } else if self.worker.index_changed()
+            || self.working_tree_requested.elapsed() >= WORKING_TREE_INTERVAL
+        {
+            self.request_working_tree();
+        }
+        while let Some(response) = self.worker.try_recv() {
+            self.apply(response);
+        }
+    }
+
+    fn apply(&mut self, response: Response) {
+        match response {
+            Response::CurrentBranch(branch) => self.current_branch = branch,
+            Response::Logs {
+                branch,
+                filter,
+                commits,
+            } => {
+                if filter == self.log_filter {
+                    self.logs.insert(branch, commits);
+                }
+            }
+            Response::BranchStatus { branch, status } => {
+                self.statuses.insert(branch, status);
+            }
+            Response::BranchStatusesDone => self.loading_statuses = false,
+            Response::FetchProgress(progress) => {
+                self.notification = Some(Notification::Info(progress.to_string()))
+            }
+            Response::Fetched(fetched) => {
+                self.fetching = false;
+                self.notification = Some(match fetched {
+                    Ok(remotes) => Notification::Info(format!("Fetched {}", remotes.join(", "))),
+                    Err(e) => Notification::Error(e),
+                });
+                if let Err(e) = self.reload_branches() {
+                    self.notification = Some(Notification::Error(e.to_string()));
+                }
+                self.invalidate();
+            }
+            Response::WorkingTree(status) => {
+                self.working_tree = status.ok();
+                let last = self.status_files_len().saturating_sub(1);
+                self.status_cursor = self.status_cursor.min(last);
+            }
+            Response::RebaseProgress { current, total } => {
+                if let Some(running) = &self.running {
+                    self.notification = Some(Notification::Info(format!(
+                        "{}: {}/{}",
+                        running, current, total
+                    )));
+                }
+            }
+            Response::Rebased(status) => {
+                let rebasing = match self.running.take() {
+                    Some(Operation::Rebase(rebasing)) => rebasing,
+                    _ => String::new(),
+                };
+                self.notification = match status {
+                    Ok(RebaseStatus::Done(count)) => Some(Notification::Info(format!(
+                        "Rebased {} ({} commit(s))",
+                        rebasing, count
+                    ))),
+                    Ok(RebaseStatus::Stopped(state)) => Some(Notification::Info(format!(
+                        "Rebase stopped at {}/{} with conflicts",
+                        state.current, state.total
+                    ))),
+                    Err(e) => Some(Notification::Error(e)),
+                };
+                self.check_rebase();
+                if let Err(e) = self.reload_branches() {
+                    self.notification = Some(Notification::Error(e.to_string()));
+                }
+                self.invalidate();
+            }
+            Response::Committed(committed) => {
+                let summary = match self.running.take() {
+                    Some(Operation::Commit(summary)) => summary,
+                    _ => String::new(),
+                };
+                match committed {
+                    Ok(id) => {
+                        self.notification =
+                            Some(Notification::Info(format!("Committed {} {}", id, summary)));
+                        self.commit_message.clear();
+                        if self.input_mode == InputMode::CommitMessage {
+                            self.input_mode = InputMode::Status;
+                        }
+                        self.invalidate();
+                    }
+                    Err(e) => self.notification = Some(Notification::Error(e)),
+                }
+            }
+            Response::Pushed(results) => {
+                self.running = None;
+                let mut pushed = Vec::new();
+                let mut failed = Vec::new();
+                for (branch, result) in results {
+                    match result {
+                        Ok(upstream) => pushed.push(format!("{} -> {}", branch, upstream)),
+                        Err(e) => failed.push(format!("{} ({})", branch, e)),
+                    }
+                }
+                let message = format!("Pushed {} branch(es): {}", pushed.len(), pushed.join(", "));
+                self.notification = Some(if failed.is_empty() {
+                    Notification::Info(message)
+                } else {
+                    Notification::Error(format!(
+                        "{}. Failed to push: {}",
+                        message,
+                        failed.join(", ")
+                    ))
+                });
+                self.invalidate();
+            }
+            Response::Deleted {
+                branches,
+                remote_branches,
+            } => {
+                self.running = None;
+                let mut deleted = Vec::new();
+                let mut deleted_remotes = Vec::new();
+                let mut failed = Vec::new();
+                for (branch, result) in branches {
+                    match result {
+                        Ok(()) => deleted.push(branch),
+                        Err(e) => failed.push(format!("{} ({})", branch, e)),
+                    }
+                }
+                for (remote_branch, result) in remote_branches {
+                    match result {
+                        Ok(()) => deleted_remotes.push(remote_branch),
+                        Err(e) => failed.push(format!("{} ({})", remote_branch, e)),
+                    }
+                }
+                let mut message = format!(
+                    "Deleted {} branch(es): {}",
+                    deleted.len(),
+                    deleted.join(", ")
+                );
+                if !deleted_remotes.is_empty() {
+                    message.push_str(&format!(", {}", deleted_remotes.join(", ")));
+                }
+                self.notification = Some(if failed.is_empty() {
+                    Notification::Info(message)
+                } else {
+                    Notification::Error(format!(
+                        "{}. Failed to delete: {}",
+                        message,
+                        failed.join(", ")
+                    ))
+                });
+                if let Err(e) = self.reload_branches() {
+                    self.notification = Some(Notification::Error(e.to_string()));
+                }
+                self.invalidate();
+            }
+            Response::Comparison(Ok(comparison)) => {
+                // the branches may have changed since it was asked for
+                if Some(&comparison.a) == self.source_branch.as_ref()
+                    && Some(&comparison.b) == self.target_branch.as_ref()
+                {
+                    self.comparison = Some(comparison);
+                }
+            }
+            Response::Comparison(Err(e)) => {
+                self.notification = Some(Notification::Error(e));
+                if self.input_mode == InputMode::Compare {
+                    self.search_mode();
+                }
+            }
+            Response::Diff { from, to, diff } => {
+                let compared = self
+                    .comparison
+                    .as_ref()
+                    .is_some_and(|comparison| comparison.b == from && comparison.a == to);
+                match diff {
+                    Ok(diff) if compared => self.diff = Some(diff),
+                    Ok(_) => {}
+                    Err(e) => {
+                        self.notification = Some(Notification::Error(e));
+                        if self.input_mode == InputMode::Diff {
+                            self.input_mode = InputMode::Compare;
+                        }
+                    }
+                }
+            }
+            Response::Synced(synced) => {
+                self.running = None;
+                let mut failed = false;
+                let results = synced
+                    .into_iter()
+                    .map(|(branch, result)| match result {
+                        Ok(result) => {
+                            failed |= result.is_diverged();
+                            format!("{}: {}", branch, result)
+                        }
+                        Err(e) => {
+                            failed = true;
+                            format!("{}: {}", branch, e)
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                self.notification = Some(if failed {
+                    Notification::Error(results)
+                } else {
+                    Notification::Info(results)
+                });
+                self.invalidate();
+            }
+            Response::Merged(merged) => {
+                let (branch, head) = match self.running.take() {
+                    Some(Operation::Merge { branch, into }) => (branch, into),
+                    _ => Default::default(),
+                };
+                self.notification = Some(match merged {
+                    Ok(MergeOutcome::UpToDate) => Notification::Info("Already up to date".into()),
+                    Ok(MergeOutcome::FastForwarded) => {
+                        Notification::Info(format!("Fast-forwarded {} to {}", head, branch))
+                    }
+                    Ok(MergeOutcome::Committed(id)) => {
+                        Notification::Info(format!("Merged {} into {} ({})", branch, head, id))
+                    }
+                    Ok(MergeOutcome::Conflicts(_)) => {
+                        Notification::Info("Merge stopped with conflicts".into())
+                    }
+                    Err(e) => Notification::Error(e),
+                });
+                self.check_merge();
+                if let Err(e) = self.reload_branches() {
+                    self.notification = Some(Notification::Error(e.to_string()));
+                }
+                self.invalidate();
+            }
+            Response::CherryPicked(status) => {
+                let head = match self.running.take() {
+                    Some(Operation::CherryPick(head)) => head,
+                    _ => String::new(),
+                };
+                self.notification = Some(match status {
+                    Ok(CherryPickStatus::Done(count)) => {
+                        self.cherry_pick = None;
+                        Notification::Info(format!(
+                            "Cherry-picked {} commit(s) onto {}",
+                            count, head
+                        ))
+                    }
+                    Ok(CherryPickStatus::Stopped(state)) => {
+                        let message =
+                            format!("Cherry-pick stopped at {} with conflicts", state.commit);
+                        self.cherry_pick = Some(state);
+                        Notification::Info(message)
+                    }
+                    Err(e) => Notification::Error(e),
+                });
+                self.check_cherry_pick();
+                if let Err(e) = self.reload_branches() {
+                    self.notification = Some(Notification::Error(e.to_string()));
+                }
+                self.invalidate();
+            }
+            Response::Reworded(reworded) => {
+                let id = match self.running.take() {
+                    Some(Operation::Reword(id)) => id,
+                    _ => String::new(),
+                };
+                match reworded {
+                    Ok(new_id) => {
+                        self.notification =
+                            Some(Notification::Info(format!("Reworded {} as {}", id, new_id)));
+                        self.reword = None;
+                        if self.input_mode == InputMode::Reword {
+                            self.input_mode = InputMode::ShowLog;
+                        }
+                        self.invalidate();
+                    }
+                    Err(e) => self.notification = Some(Notification::Error(e)),
+                }
+            }
+            Response::Error(e) => self.notification = Some(Notification::Error(e)),
+        }
     }
 
+    /// Fetches and prunes every remote in the background.
+    pub fn fetch(&mut self) {
+        if !self.fetching {
+            self.fetching = true;
+            self.notification = Some(Notification::Info("Fetching…".into()));
+            self.worker.request(Request::Fetch(None));
+        }
+    }
+
+    fn request_logs(&mut self) {
+        if let Some(branch) = self.selected_branch().cloned() {
+            if !self.logs.contains_key(&branch) {
+                self.worker.request(Request::Logs {
+                    branch,
+                    limit: LOG_LIMIT,
+                    filter: self.log_filter.clone(),
+                });
+            }
+        }
+    }
+
+    /// Re-reads the local branches from the repository, e.g. after a command changed them.
+    pub fn reload_branches(&mut self) -> anyhow::Result<()> {
+        self.all_branches = self.repo.branches()?;
+        self.descriptions = self.repo.branch_descriptions()?;
+        let all_branches = &self.all_branches;
+        self.selected.retain(|branch| all_branches.contains(branch));
+        self.refresh_branches();
+        Ok(())
+    }
+
+    /// Filters the branches by the input. A changed input highlights the first match,
+    /// otherwise the cursor stays on the same branch if it is still listed.
     pub fn refresh_branches(&mut self) {
+        let current = self
+            .selected_branch()
+            .cloned()
+            .filter(|_| self.filtered_by == self.input);
         self.branches.set_items(
             self.all_branches
                 .iter()
@@ -102,246 +804,3270 @@ impl App {
                 .cloned()
                 .collect(),
         );
+        self.filtered_by.clone_from(&self.input);
+        if let Some(index) =
+            current.and_then(|current| self.branches.items.iter().position(|x| *x == current))
+        {
+            self.branches.state.select(Some(index));
+        }
     }
 
     pub fn checkout_mode(&mut self) {
         if self.selected_branch().is_some() {
+            self.request_working_tree();
             self.input_mode = InputMode::Command(Command::Checkout);
         }
     }
 
-    pub fn search_mode(&mut self) {
-        self.input_mode = InputMode::Search;
+    pub fn status_mode(&mut self) {
+        self.request_working_tree();
+        self.input_mode = InputMode::Status;
     }
 
-    pub fn log_mode(&mut self) {
-        self.input_mode = InputMode::ShowLog;
+    /// Asks the worker to read the working tree again, e.g. for confirmations that warn
+    /// about changes.
+    fn request_working_tree(&mut self) {
+        self.working_tree_requested = Instant::now();
+        self.worker.request(Request::WorkingTree);
     }
 
-    pub fn help_mode(&mut self) {
-        self.input_mode = InputMode::Help;
+    fn status_files_len(&self) -> usize {
+        self.working_tree
+            .as_ref()
+            .map(|status| status.files().len())
+            .unwrap_or(0)
     }
 
-    pub fn delete_branch_mode(&mut self) {
-        if !self.selected.is_empty() {
-            self.input_mode = InputMode::Command(Command::DeleteBranch);
+    fn move_status_cursor(&mut self, delta: isize) {
+        let last = self.status_files_len().saturating_sub(1);
+        self.status_cursor = self.status_cursor.saturating_add_signed(delta).min(last);
+    }
+
+    /// The area and path of the highlighted file of the status view, and its path before a rename.
+    fn status_file(&self) -> Option<(Area, String, Option<String>)> {
+        let status = self.working_tree.as_ref()?;
+        let files = status.files();
+        let file = files.get(self.status_cursor)?;
+        Some((
+            file.area,
+            file.path.to_owned(),
+            file.old_path.map(ToOwned::to_owned),
+        ))
+    }
+
+    /// Unstages the highlighted file if it is staged, and stages it otherwise.
+    pub fn toggle_stage(&mut self) {
+        let result = match self.status_file() {
+            Some((Area::Staged, path, old_path)) => self.repo.unstage(&path).and_then(|_| {
+                old_path
+                    .map(|old_path| self.repo.unstage(&old_path))
+                    .unwrap_or(Ok(()))
+            }),
+            Some((_, path, _)) => self.repo.stage(&path),
+            None => return,
+        };
+        if let Err(e) = result {
+            self.notification = Some(Notification::Error(e.to_string()));
         }
+        self.request_working_tree();
     }
 
-    pub fn selected_branch(&self) -> Option<&String> {
-        self.branches.selected()
+    /// Shows the hunks of the highlighted file, if it has staged or unstaged changes.
+    pub fn hunks_mode(&mut self) {
+        let (path, staged) = match self.status_file() {
+            Some((Area::Staged, path, _)) => (path, true),
+            Some((Area::Unstaged, path, _)) => (path, false),
+            Some(_) => {
+                self.notification = Some(Notification::Error(
+                    "Only staged and unstaged changes have hunks.".into(),
+                ));
+                return;
+            }
+            None => return,
+        };
+        self.hunk_file = Some((path, staged));
+        self.hunk_cursor = 0;
+        self.reload_hunks();
+        if !self.hunks.is_empty() {
+            self.input_mode = InputMode::Hunks;
+        }
     }
 
-    pub fn run_command(&self) -> anyhow::Result<()> {
-        match self.input_mode {
-            InputMode::Command(command) => command.run(self)?,
-            _ => {}
+    fn reload_hunks(&mut self) {
+        if let Some((path, staged)) = &self.hunk_file {
+            match self.repo.hunks(path, *staged) {
+                Ok(hunks) => self.hunks = hunks,
+                Err(e) => {
+                    self.hunks.clear();
+                    self.notification = Some(Notification::Error(e.to_string()));
+                }
+            }
         }
-        Ok(())
+        self.hunk_cursor = self.hunk_cursor.min(self.hunks.len().saturating_sub(1));
     }
 
-    pub fn start_branch(&self, opts: &crate::cmd::StartBranchOpts) -> anyhow::Result<git2::Branch> {
-        let branch = self.repo
-            .checkout_new_branch(&opts.branch_name())?;
-        self.repo.checkout(branch.name()?.unwrap())?;
-        Ok(branch)
+    fn move_hunk_cursor(&mut self, delta: isize) {
+        let last = self.hunks.len().saturating_sub(1);
+        self.hunk_cursor = self.hunk_cursor.saturating_add_signed(delta).min(last);
     }
 
-    pub fn start(&mut self) -> anyhow::Result<()> {
-        // Terminal initialization
-        let stdout = io::stdout().into_raw_mode()?;
-        let stdout = MouseTerminal::from(stdout);
-        let stdout = AlternateScreen::from(stdout);
-        let backend = TermionBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
+    /// Stages the highlighted hunk, or unstages it if it is staged. Goes back to the
+    /// status when the file has no hunks left.
+    pub fn toggle_stage_hunk(&mut self) {
+        if let Some((path, staged)) = &self.hunk_file {
+            let result = if *staged {
+                self.repo.unstage_hunk(path, self.hunk_cursor)
+            } else {
+                self.repo.stage_hunk(path, self.hunk_cursor)
+            };
+            if let Err(e) = result {
+                self.notification = Some(Notification::Error(e.to_string()));
+            }
+        }
+        self.reload_hunks();
+        self.request_working_tree();
+        if self.hunks.is_empty() {
+            self.input_mode = InputMode::Status;
+        }
+    }
 
-        // Setup event handlers
-        let events = Events::new();
+    /// Asks for the message of a commit of the staged changes.
+    pub fn commit_mode(&mut self) {
+        if self
+            .working_tree
+            .as_ref()
+            .is_none_or(|status| status.staged.is_empty())
+        {
+            self.notification = Some(Notification::Error("Nothing is staged to commit.".into()));
+            return;
+        }
+        match self.repo.signature() {
+            Ok(signature) => {
+                self.signature = Some(signature);
+                self.input_mode = InputMode::CommitMessage;
+            }
+            Err(e) => self.notification = Some(Notification::Error(e.to_string())),
+        }
+    }
 
-        loop {
-            // Draw UI
-            terminal.draw(|mut f| {
-                let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .margin(TOP_MARGIN)
-                    .constraints(
-                        [
-                            Constraint::Length(HELP_MESSAGE_HEIGHT),
-                            Constraint::Length(TEXT_INPUT_HEIGHT),
-                            Constraint::Min(1),
-                        ]
-                        .as_ref(),
-                    )
-                    .split(f.size());
-
-                component::DefaultHelp::render(&mut f, &chunks[0], &self.input_mode);
-                component::SearchInput::render(&mut f, &chunks[1], &self.input);
+    /// Asks for a new message of the highlighted commit of the log view.
+    pub fn reword_mode(&mut self) {
+        let branch = match self.selected_branch() {
+            Some(branch) => branch.clone(),
+            None => return,
+        };
+        if let Some(commit) = self.shown_commits().get(self.log_cursor) {
+            self.reword = Some(RewordForm {
+                pushed: self.repo.is_pushed(&branch, commit.oid).unwrap_or(false),
+                branch,
+                oid: commit.oid,
+                id: commit.id.clone(),
+                message: commit.message.trim_end().to_owned(),
+            });
+            self.input_mode = InputMode::Reword;
+        }
+    }
 
-                {
-                    // main area
-                    let chunks = Layout::default()
-                        .direction(Direction::Horizontal)
-                        .constraints(
-                            [
-                                Constraint::Percentage(LIST_WIDTH_PERCENTAGE),
-                                Constraint::Percentage(100 - LIST_WIDTH_PERCENTAGE),
-                            ]
-                            .as_ref(),
-                        )
-                        .split(chunks[2]);
-
-                    // branches
-                    component::BranchList::render(
-                        &mut f,
-                        &chunks[0],
-                        &mut self.branches,
-                        self.repo.current_branch().unwrap(),
-                    );
-                    // selected
-                    component::SelectedList::render(&mut f, &chunks[1], &self.selected);
-                }
+    /// Changes the message of the commit on the worker, rebuilding the commits after it.
+    /// The form is kept to edit if that fails.
+    pub fn reword(&mut self) {
+        if let Some(form) = &self.reword {
+            let request = Request::Reword {
+                branch: form.branch.clone(),
+                oid: form.oid,
+                message: form.message.clone(),
+            };
+            self.run_on_worker(Operation::Reword(form.id.clone()), request);
+        }
+    }
 
-                {
-                    match self.input_mode {
-                        InputMode::Help => {
-                            component::Help::render(&mut f);
-                        }
-                        InputMode::ShowLog => {
-                            if let Some(ref branch_name) = self.selected_branch() {
-                                let commits = self.repo.logs(branch_name, LOG_LIMIT).unwrap();
-                                component::Logs::render(&mut f, commits);
-                            }
-                        }
-                        InputMode::Command(command) => match command {
-                            Command::Checkout => {
-                                if let Some(ref branch_name) = self.selected_branch() {
-                                    component::CheckoutConfirmation::render(&mut f, branch_name);
-                                }
-                            }
-                            Command::DeleteBranch => {
-                                component::DeleteBranchConfirmation::render(&mut f, &self.selected);
-                            }
-                        },
-                        _ => {}
-                    }
-                }
-            })?;
+    /// Commits the staged changes on the worker, as the hooks may take a while.
+    /// The message is kept to edit if the commit fails.
+    pub fn commit(&mut self) {
+        let summary = self.commit_message.lines().next().unwrap_or_default();
+        self.run_on_worker(
+            Operation::Commit(summary.to_owned()),
+            Request::Commit(self.commit_message.clone()),
+        );
+    }
 
-            // Put the cursor back inside the input box
-            write!(
-                terminal.backend_mut(),
-                "{}",
-                Goto(
-                    TOP_MARGIN + 2 + self.input.width() as u16,
-                    TOP_MARGIN + HELP_MESSAGE_HEIGHT + TEXT_INPUT_HEIGHT - 1
-                )
-            )?;
+    /// Shows the reflog of HEAD.
+    pub fn reflog_mode(&mut self) {
+        self.reflog_name = "HEAD".to_owned();
+        self.reflog_cursor = 0;
+        self.reload_reflog();
+        self.input_mode = InputMode::Reflog;
+    }
 
-            // stdout is buffered, flush it to see the effect immediately when hitting backspace
-            io::stdout().flush().ok();
+    /// Switches between the reflog of HEAD and the one of the highlighted branch.
+    fn toggle_reflog_name(&mut self) {
+        self.reflog_name = match self.selected_branch() {
+            Some(branch) if self.reflog_name == "HEAD" => branch.clone(),
+            _ => "HEAD".to_owned(),
+        };
+        self.reflog_cursor = 0;
+        self.reload_reflog();
+    }
 
-            // Handle input
-            match events.next()? {
-                Event::Input(input) => match self.input_mode {
-                    InputMode::Command(_) => match input {
-                        Key::Esc | Key::Ctrl('c') | Key::Char('n') | Key::Char('q') => {
-                            self.search_mode();
-                        }
-                        Key::Char('y') | Key::Char('\n') => {
-                            self.run_command()?;
-                            break;
-                        }
-                        _ => {}
-                    },
-                    InputMode::Search => match input {
-                        // exit
-                        Key::Esc | Key::Ctrl('c') => {
-                            break;
-                        }
-                        // press Enter
-                        Key::Char('\n') => {
-                            if let Some(x) = self.branches.selected() {
-                                match x.as_ref() {
-                                    "master" => {}
-                                    _ => {
-                                        self.selected.insert(x.clone());
-                                        self.branches.next();
-                                    }
-                                }
-                            }
-                        }
-                        Key::Char(c) => {
-                            self.input.push(c);
-                            self.refresh_branches();
-                        }
-                        Key::Ctrl('h') | Key::Backspace | Key::Delete => {
-                            self.input.pop();
-                            self.refresh_branches();
-                        }
-                        Key::Ctrl('n') | Key::Down => {
-                            self.branches.next();
-                        }
-                        Key::Ctrl('p') | Key::Up => {
-                            self.branches.previous();
-                        }
-                        Key::Ctrl('o') => {
-                            self.checkout_mode();
-                        }
-                        Key::Ctrl('l') => {
-                            self.log_mode();
-                        }
-                        Key::Alt('h') => {
-                            self.help_mode();
-                        }
-                        Key::Ctrl('d') => {
-                            self.delete_branch_mode();
-                        }
-                        _ => {}
-                    },
-                    _ => match input {
-                        Key::Char('y')
-                        | Key::Char('q')
-                        | Key::Char('\n')
-                        | Key::Esc
-                        | Key::Ctrl('n') => {
-                            self.search_mode();
-                        }
-                        _ => {}
-                    },
-                },
-                _ => {}
+    fn reload_reflog(&mut self) {
+        match self.repo.reflog(&self.reflog_name, REFLOG_LIMIT) {
+            Ok(entries) => self.reflog = entries,
+            Err(e) => {
+                self.reflog.clear();
+                self.notification = Some(Notification::Error(e.to_string()));
+            }
+        }
+        self.reflog_cursor = self.reflog_cursor.min(self.reflog.len().saturating_sub(1));
+    }
+
+    fn move_reflog_cursor(&mut self, delta: isize) {
+        let last = self.reflog.len().saturating_sub(1);
+        self.reflog_cursor = self.reflog_cursor.saturating_add_signed(delta).min(last);
+    }
+
+    /// Asks for the name of a branch to create at the highlighted reflog entry.
+    fn reflog_branch_mode(&mut self) {
+        if self.reflog.get(self.reflog_cursor).is_some() {
+            self.reflog_branch_input.clear();
+            self.input_mode = InputMode::ReflogBranch;
+        }
+    }
+
+    /// Asks whether to check out the highlighted reflog entry, warning about changes
+    /// to the working tree.
+    fn reflog_checkout_mode(&mut self) {
+        if self.reflog.get(self.reflog_cursor).is_some() {
+            self.request_working_tree();
+            self.input_mode = InputMode::ReflogCheckout;
+        }
+    }
+
+    /// Checks out the highlighted reflog entry, or creates the typed branch at it,
+    /// and shows the reflog of HEAD with the new entry.
+    pub fn checkout_reflog_entry(&mut self, new_branch: bool) -> anyhow::Result<()> {
+        let entry = match self.reflog.get(self.reflog_cursor) {
+            Some(entry) => entry,
+            None => return Ok(()),
+        };
+        let result = if new_branch {
+            let name = self.reflog_branch_input.trim();
+            self.repo
+                .checkout_new_branch_at(name, entry.oid)
+                .map(|_| format!("Switched to a new branch '{}' at {}", name, entry.selector))
+        } else {
+            self.repo
+                .checkout_detached(entry.oid)
+                .map(|_| format!("HEAD is now at {} ({})", entry.id, entry.selector))
+        };
+        match result {
+            Ok(message) => {
+                self.notification = Some(Notification::Info(message));
+                self.reload_branches()?;
+                self.reflog_name = "HEAD".to_owned();
+                self.reflog_cursor = 0;
+                self.reload_reflog();
+                self.input_mode = InputMode::Reflog;
+                self.invalidate();
+            }
+            Err(e) => {
+                self.notification = Some(Notification::Error(e.to_string()));
+                self.input_mode = InputMode::Reflog;
             }
         }
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Lists the branches checked out last, read from the reflog of HEAD.
+    pub fn recent_mode(&mut self) {
+        match self.repo.previous_branches() {
+            Ok(mut branches) => {
+                branches.truncate(self.config.recent_branches);
+                self.recent_branches = branches;
+            }
+            Err(e) => {
+                self.notification = Some(Notification::Error(e.to_string()));
+                return;
+            }
+        }
+        if self.recent_branches.is_empty() {
+            self.notification = Some(Notification::Error(
+                "No other branch was checked out before.".into(),
+            ));
+            return;
+        }
+        self.request_working_tree();
+        self.input_mode = InputMode::Recent;
+    }
 
-    #[test]
-    fn test_delete_branch_mode_when_empty_selected() -> anyhow::Result<()> {
-        let mut app = App::new(std::env::current_dir()?)?;
+    /// Checks out a branch of the recent view and goes back to the branch list.
+    pub fn checkout_recent(&mut self, index: usize) -> anyhow::Result<()> {
+        let branch = match self.recent_branches.get(index) {
+            Some(branch) => branch.clone(),
+            None => return Ok(()),
+        };
+        self.notification = Some(match self.repo.checkout(&branch) {
+            Ok(()) => Notification::Info(format!("Switched to branch '{}'", branch)),
+            Err(e) => Notification::Error(e.to_string()),
+        });
+        self.reload_branches()?;
+        self.search_mode();
+        self.invalidate();
+        Ok(())
+    }
 
-        app.delete_branch_mode();
+    pub fn search_mode(&mut self) {
+        self.input_mode = InputMode::Search;
+    }
 
-        Ok(assert_ne!(
-            app.input_mode,
-            InputMode::Command(Command::DeleteBranch)
-        ))
+    pub fn log_mode(&mut self) {
+        self.log_cursor = 0;
+        self.marked_commits.clear();
+        self.input_mode = InputMode::ShowLog;
+        self.request_logs();
     }
 
-    #[test]
-    fn test_delete_branch_mode_when_not_empty_selected() -> anyhow::Result<()> {
-        let mut app = App::new(std::env::current_dir()?)?;
-        app.selected.insert("Hoge".into());
+    pub fn log_filter_mode(&mut self) {
+        self.log_filter_input = self.log_filter.to_string();
+        self.input_mode = InputMode::LogFilter;
+    }
 
-        app.delete_branch_mode();
+    /// Shows the log with the filter of the filter bar, if it is valid.
+    pub fn apply_log_filter(&mut self) {
+        match LogFilter::parse(&self.log_filter_input) {
+            Ok(filter) => {
+                if filter != self.log_filter {
+                    self.log_filter = filter;
+                    self.logs.clear();
+                }
+                self.log_mode();
+            }
+            Err(e) => self.notification = Some(Notification::Error(e.to_string())),
+        }
+    }
 
-        Ok(assert_eq!(
-            app.input_mode,
-            InputMode::Command(Command::DeleteBranch)
-        ))
+    /// The commits shown in the log view, once loaded.
+    fn shown_commits(&self) -> &[Commit] {
+        self.selected_branch()
+            .and_then(|branch| self.logs.get(branch))
+            .and_then(|logs| logs.as_ref().ok())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn move_log_cursor(&mut self, delta: isize) {
+        let last = self.shown_commits().len().saturating_sub(1);
+        self.log_cursor = self.log_cursor.saturating_add_signed(delta).min(last);
+    }
+
+    fn toggle_commit_mark(&mut self) {
+        if let Some(oid) = self.shown_commits().get(self.log_cursor).map(|c| c.oid) {
+            if let Some(index) = self.marked_commits.iter().position(|x| *x == oid) {
+                self.marked_commits.remove(index);
+            } else {
+                self.marked_commits.push(oid);
+            }
+        }
+    }
+
+    /// The marked commits, or the highlighted one if none is marked, oldest first.
+    pub fn cherry_pick_commits(&self) -> Vec<&Commit> {
+        let commits = self.shown_commits();
+        let mut picked = if self.marked_commits.is_empty() {
+            commits.get(self.log_cursor).into_iter().collect::<Vec<_>>()
+        } else {
+            commits
+                .iter()
+                .filter(|c| self.marked_commits.contains(&c.oid))
+                .collect()
+        };
+        picked.reverse();
+        picked
+    }
+
+    pub fn cherry_pick_mode(&mut self) {
+        if self.cherry_pick.is_some() {
+            self.input_mode = InputMode::CherryPickConflicts;
+        } else if self.selected_branch().is_some()
+            && self.selected_branch() == self.current_branch.as_ref()
+        {
+            self.notification = Some(Notification::Error(
+                "The commits are already on the current branch.".into(),
+            ));
+        } else if !self.cherry_pick_commits().is_empty() {
+            self.input_mode = InputMode::Command(Command::CherryPick);
+        }
+    }
+
+    pub fn help_mode(&mut self) {
+        self.input_mode = InputMode::Help;
+    }
+
+    pub fn delete_branch_mode(&mut self) {
+        if !self.selected.is_empty() {
+            let mut branches = self.selected.iter().collect::<Vec<_>>();
+            branches.sort();
+            self.remote_branches = branches
+                .into_iter()
+                .filter_map(|branch| self.repo.remote_branch(branch).ok().flatten())
+                .collect();
+            self.delete_remote = false;
+            self.request_working_tree();
+            self.input_mode = InputMode::Command(Command::DeleteBranch);
+        }
+    }
+
+    pub fn push_mode(&mut self, force: bool) {
+        if !self.marked_or_highlighted().is_empty() {
+            self.input_mode = InputMode::Command(if force {
+                Command::ForcePush
+            } else {
+                Command::Push
+            });
+        }
+    }
+
+    /// Deletes the selected branches on the worker, with their remote branches if asked to.
+    /// The remote branches were read with `delete_branch_mode`, before the upstream
+    /// config goes away with the local branches.
+    pub fn delete_branches(&mut self) {
+        let mut branches = self.selected.drain().collect::<Vec<_>>();
+        branches.sort();
+        let remote_branches = if self.delete_remote {
+            self.remote_branches.clone()
+        } else {
+            Vec::new()
+        };
+        self.run_on_worker(
+            Operation::DeleteBranches,
+            Request::DeleteBranches {
+                branches,
+                remote_branches,
+            },
+        );
+        self.search_mode();
+    }
+
+    /// Pushes the marked or highlighted branches on the worker.
+    pub fn push(&mut self) {
+        let force = self.input_mode == InputMode::Command(Command::ForcePush);
+        let branches = self.marked_or_highlighted();
+        self.run_on_worker(Operation::Push, Request::Push { branches, force });
+        self.selected.clear();
+        self.search_mode();
+    }
+
+    /// Fast-forwards the marked or highlighted branches on the worker,
+    /// which reports what happened to each.
+    pub fn sync(&mut self) {
+        let branches = self.marked_or_highlighted();
+        if !branches.is_empty() {
+            self.run_on_worker(Operation::Sync, Request::Sync(branches));
+        }
+    }
+
+    /// Searches for the branch to rebase the highlighted one onto,
+    /// or shows the rebase in progress.
+    pub fn rebase_mode(&mut self) {
+        if self.rebase.is_some() {
+            self.input_mode = InputMode::Rebase;
+        } else if let Some(branch) = self.selected_branch().cloned() {
+            self.source_branch = Some(branch);
+            self.target_branch = None;
+            self.input.clear();
+            self.refresh_branches();
+            self.input_mode = InputMode::SelectTarget(Target::Rebase);
+        }
+    }
+
+    fn select_target(&mut self, target: Target) -> Action {
+        let branch = match self.selected_branch() {
+            Some(branch) if Some(branch) != self.source_branch.as_ref() => branch.clone(),
+            _ => return Action::None,
+        };
+        self.target_branch = Some(branch);
+        self.input.clear();
+        self.refresh_branches();
+        match target {
+            Target::Rebase => {
+                self.input_mode = InputMode::Command(Command::Rebase);
+                Action::None
+            }
+            Target::Compare => Action::Open(InputMode::Compare),
+        }
+    }
+
+    pub fn edit_description_mode(&mut self) {
+        if let Some(branch) = self.selected_branch() {
+            self.description_input = self.descriptions.get(branch).cloned().unwrap_or_default();
+            self.input_mode = InputMode::EditDescription;
+        }
+    }
+
+    /// Saves the edited description of the highlighted branch, removing it when blank.
+    pub fn save_description(&mut self) -> anyhow::Result<()> {
+        if let Some(branch) = self.selected_branch().cloned() {
+            self.notification = Some(
+                match self
+                    .repo
+                    .set_branch_description(&branch, &self.description_input)
+                {
+                    Ok(()) => Notification::Info(format!("Updated the description of {}", branch)),
+                    Err(e) => Notification::Error(e.to_string()),
+                },
+            );
+            self.descriptions = self.repo.branch_descriptions()?;
+        }
+        self.search_mode();
+        Ok(())
+    }
+
+    /// Opens the issue of the highlighted branch, found by the branch template.
+    pub fn open_branch_issue(&mut self) {
+        if let Some(branch) = self.selected_branch().cloned() {
+            match self.branch_template().issue(&branch) {
+                Some(issue) => self.open_issue(&issue),
+                None => {
+                    self.notification =
+                        Some(Notification::Error(format!("No issue in {}", branch)));
+                }
+            }
+        }
+    }
+
+    /// Opens the first issue the highlighted commit of the log view refers to.
+    pub fn open_commit_issue(&mut self) {
+        if let Some(commit) = self.shown_commits().get(self.log_cursor) {
+            let id = commit.id.clone();
+            match issue::issues_in_message(&commit.message).first().cloned() {
+                Some(issue) => self.open_issue(&issue),
+                None => {
+                    self.notification = Some(Notification::Error(format!(
+                        "No issue referenced by {}",
+                        id
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Hands the link to the issue to `ex.opener`, or shows it if there is no opener.
+    fn open_issue(&mut self, issue: &str) {
+        let url = match &self.config.issue_url {
+            Some(pattern) => issue::issue_url(pattern, issue),
+            None => {
+                self.notification = Some(Notification::Error(
+                    "Set ex.issueUrl to open issues, e.g. https://tracker/browse/{issue}".into(),
+                ));
+                return;
+            }
+        };
+        let mut opener = self
+            .config
+            .opener
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace();
+        self.notification = Some(match opener.next() {
+            None => Notification::Info(url),
+            Some(program) => match std::process::Command::new(program)
+                .args(opener)
+                .arg(&url)
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn()
+            {
+                Ok(_) => Notification::Info(format!("Opened {}", url)),
+                Err(e) => Notification::Error(format!("{}: {}", program, e)),
+            },
+        });
+    }
+
+    /// Asks for a branch to compare the highlighted one with, suggesting the current branch.
+    pub fn compare_mode(&mut self) {
+        if let Some(branch) = self.selected_branch().cloned() {
+            self.source_branch = Some(branch);
+            self.target_branch = None;
+            self.input.clear();
+            self.refresh_branches();
+            if let Some(index) = self
+                .branches
+                .items
+                .iter()
+                .position(|x| Some(x) == self.current_branch.as_ref())
+            {
+                self.branches.state.select(Some(index));
+            }
+            self.input_mode = InputMode::SelectTarget(Target::Compare);
+        }
+    }
+
+    fn compare_branches(&mut self) {
+        if self.source_branch.is_some() && self.target_branch.is_some() {
+            self.comparison = None;
+            self.popup_scroll = 0;
+            self.input_mode = InputMode::Compare;
+            self.request_comparison();
+        }
+    }
+
+    fn request_comparison(&mut self) {
+        if let (Some(a), Some(b)) = (&self.source_branch, &self.target_branch) {
+            self.worker.request(Request::Compare {
+                a: a.clone(),
+                b: b.clone(),
+                limit: LOG_LIMIT,
+            });
+        }
+    }
+
+    /// Shows what the first compared branch changes against the second one.
+    pub fn diff_mode(&mut self) {
+        if self.comparison.is_some() {
+            self.diff = None;
+            self.popup_scroll = 0;
+            self.input_mode = InputMode::Diff;
+            self.request_diff();
+        }
+    }
+
+    fn request_diff(&mut self) {
+        if let Some(comparison) = &self.comparison {
+            self.worker.request(Request::Diff {
+                from: comparison.b.clone(),
+                to: comparison.a.clone(),
+            });
+        }
+    }
+
+    /// Shows the rebase in progress, if there is one, e.g. after a rebase stopped.
+    pub fn check_rebase(&mut self) {
+        self.rebase = self.repo.rebase_state().ok().flatten();
+        if self.rebase.is_some() {
+            self.input_mode = InputMode::Rebase;
+        } else if self.input_mode == InputMode::Rebase {
+            self.search_mode();
+        }
+    }
+
+    /// Hands a change of the repository to the worker, which answers with its outcome.
+    fn run_on_worker(&mut self, operation: Operation, request: Request) {
+        self.notification = Some(Notification::Info(format!("{}…", operation)));
+        self.worker.request(request);
+        self.running = Some(operation);
+    }
+
+    /// Rebases the source branch onto the target on the worker, which reports progress.
+    pub fn start_rebase(&mut self) {
+        if let (Some(branch), Some(onto)) = (&self.source_branch, &self.target_branch) {
+            let request = Request::Rebase {
+                branch: branch.clone(),
+                onto: onto.clone(),
+            };
+            self.run_on_worker(
+                Operation::Rebase(format!("{} onto {}", branch, onto)),
+                request,
+            );
+        }
+        self.search_mode();
+    }
+
+    /// Continues or skips on the worker, and aborts right away.
+    pub fn step_rebase(&mut self, step: RebaseStep) -> anyhow::Result<()> {
+        if step == RebaseStep::Abort {
+            self.notification = Some(match self.repo.abort_rebase() {
+                Ok(()) => Notification::Info("Aborted the rebase".into()),
+                Err(e) => Notification::Error(e.to_string()),
+            });
+            self.check_rebase();
+            self.reload_branches()?;
+            self.invalidate();
+            return Ok(());
+        }
+        let rebasing = self
+            .rebase
+            .as_ref()
+            .map(|state| format!("{} onto {}", state.branch, state.onto))
+            .unwrap_or_default();
+        self.run_on_worker(
+            Operation::Rebase(rebasing),
+            Request::ContinueRebase {
+                skip: step == RebaseStep::Skip,
+            },
+        );
+        Ok(())
+    }
+
+    /// Previews merging the highlighted branch into HEAD, or shows the merge in progress.
+    pub fn merge_mode(&mut self) {
+        if self.merge_conflicts.is_some() {
+            self.input_mode = InputMode::MergeConflicts;
+            return;
+        }
+        let branch = match self.selected_branch() {
+            Some(branch) if Some(branch) != self.current_branch.as_ref() => branch.clone(),
+            _ => return,
+        };
+        match self.repo.merge_analysis(&branch) {
+            Ok(analysis) => {
+                self.merge = Some(MergeForm {
+                    message: format!("Merge branch '{}'", branch),
+                    branch,
+                    analysis,
+                    strategy: if analysis == MergeAnalysis::FastForward {
+                        MergeStrategy::FastForward
+                    } else {
+                        MergeStrategy::Commit
+                    },
+                });
+                self.input_mode = InputMode::Command(Command::Merge);
+            }
+            Err(e) => self.notification = Some(Notification::Error(e.to_string())),
+        }
+    }
+
+    /// Merges the branch of the merge form into HEAD on the worker.
+    pub fn start_merge(&mut self) -> anyhow::Result<()> {
+        if let Some(form) = &self.merge {
+            let request = Request::Merge {
+                branch: form.branch.clone(),
+                strategy: form.strategy,
+                message: form.message.clone(),
+            };
+            let operation = Operation::Merge {
+                branch: form.branch.clone(),
+                into: self.repo.current_branch()?.unwrap_or_else(|| "HEAD".into()),
+            };
+            self.run_on_worker(operation, request);
+        }
+        self.search_mode();
+        Ok(())
+    }
+
+    /// Shows the merge in progress, if there is one, e.g. after a merge stopped.
+    pub fn check_merge(&mut self) {
+        self.merge_conflicts = self.repo.merge_conflicts().ok().flatten();
+        if self.merge_conflicts.is_some() {
+            self.input_mode = InputMode::MergeConflicts;
+        } else if self.input_mode == InputMode::MergeConflicts {
+            self.search_mode();
+        }
+    }
+
+    pub fn step_merge(&mut self, step: MergeStep) -> anyhow::Result<()> {
+        self.notification = match step {
+            MergeStep::Commit => match self.repo.commit_merge() {
+                Ok(MergeOutcome::Committed(id)) => {
+                    Some(Notification::Info(format!("Committed the merge ({})", id)))
+                }
+                Ok(_) => None,
+                Err(e) => Some(Notification::Error(e.to_string())),
+            },
+            MergeStep::Abort => match self.repo.abort_merge() {
+                Ok(()) => Some(Notification::Info("Aborted the merge".into())),
+                Err(e) => Some(Notification::Error(e.to_string())),
+            },
+        };
+        self.check_merge();
+        self.reload_branches()?;
+        self.invalidate();
+        Ok(())
+    }
+
+    /// Cherry-picks the marked commits onto HEAD on the worker, keeping the ones after
+    /// a conflict to apply with `step_cherry_pick`.
+    pub fn start_cherry_pick(&mut self) -> anyhow::Result<()> {
+        let ids = self
+            .cherry_pick_commits()
+            .iter()
+            .map(|commit| commit.oid)
+            .collect::<Vec<_>>();
+        let head = self.repo.current_branch()?.unwrap_or_else(|| "HEAD".into());
+        self.run_on_worker(Operation::CherryPick(head), Request::CherryPick(ids));
+        self.search_mode();
+        Ok(())
+    }
+
+    /// Shows the cherry-pick in progress, if any, with the commits it still has to apply.
+    pub fn check_cherry_pick(&mut self) {
+        let remaining = self
+            .cherry_pick
+            .take()
+            .map(|state| state.remaining)
+            .unwrap_or_default();
+        self.cherry_pick = self.repo.cherry_pick_state(remaining).ok().flatten();
+        if self.cherry_pick.is_some() {
+            self.input_mode = InputMode::CherryPickConflicts;
+        } else if self.input_mode == InputMode::CherryPickConflicts {
+            self.search_mode();
+        }
+    }
+
+    /// Continues on the worker, and aborts right away.
+    pub fn step_cherry_pick(&mut self, step: CherryPickStep) -> anyhow::Result<()> {
+        let remaining = match &self.cherry_pick {
+            Some(state) => state.remaining.clone(),
+            None => return Ok(()),
+        };
+        if step == CherryPickStep::Continue {
+            let head = self.repo.current_branch()?.unwrap_or_else(|| "HEAD".into());
+            self.run_on_worker(
+                Operation::CherryPick(head),
+                Request::ContinueCherryPick(remaining),
+            );
+            return Ok(());
+        }
+        self.notification = Some(match self.repo.abort_cherry_pick() {
+            Ok(()) => {
+                self.cherry_pick = None;
+                Notification::Info("Aborted the cherry-pick".into())
+            }
+            Err(e) => Notification::Error(e.to_string()),
+        });
+        self.check_cherry_pick();
+        self.reload_branches()?;
+        self.invalidate();
+        Ok(())
+    }
+
+    /// The marked branches in name order, or the highlighted one when nothing is marked.
+    pub fn marked_or_highlighted(&self) -> Vec<String> {
+        if self.selected.is_empty() {
+            self.selected_branch().into_iter().cloned().collect()
+        } else {
+            let mut branches = self.selected.iter().cloned().collect::<Vec<_>>();
+            branches.sort();
+            branches
+        }
+    }
+
+    /// Picks the marked branches, or the highlighted one when nothing is marked.
+    pub fn pick_branches(&mut self) {
+        self.picked = self.marked_or_highlighted();
+    }
+
+    /// Handles a mouse event against the areas drawn in the last frame.
+    /// Clicking an answer of a confirmation popup returns the key it stands for.
+    pub fn handle_mouse(&mut self, mouse: Mouse) -> Option<Key> {
+        let Mouse { kind, column, row } = mouse;
+        match self.input_mode {
+            InputMode::Command(_) | InputMode::ReflogCheckout => {
+                let buttons = self.areas.buttons?;
+                match kind {
+                    MouseKind::Down(MouseButton::Left)
+                        if util::contains(&buttons.yes, column, row) =>
+                    {
+                        Some(Key::Char('\n'))
+                    }
+                    MouseKind::Down(MouseButton::Left)
+                        if util::contains(&buttons.no, column, row) =>
+                    {
+                        Some(Key::Esc)
+                    }
+                    _ => None,
+                }
+            }
+            InputMode::ShowLog => {
+                let log = self.areas.log?;
+                if !util::contains(&log, column, row) {
+                    return None;
+                }
+                match kind {
+                    MouseKind::ScrollDown => self.move_log_cursor(1),
+                    MouseKind::ScrollUp => self.move_log_cursor(-1),
+                    _ => {}
+                }
+                None
+            }
+            InputMode::Search | InputMode::SelectTarget(_) => {
+                let list = self.areas.branch_list;
+                if !util::contains(&list, column, row) {
+                    return None;
+                }
+                match kind {
+                    MouseKind::ScrollDown => self.branches.next(),
+                    MouseKind::ScrollUp => self.branches.previous(),
+                    MouseKind::Down(MouseButton::Left) => {
+                        // skip the border of the list
+                        let index = row
+                            .checked_sub(list.y + 1)
+                            .filter(|row| *row < list.height.saturating_sub(2))
+                            .and_then(|row| self.branches.index_at(row))?;
+                        let now = Instant::now();
+                        let double_click = matches!(
+                            self.last_click,
+                            Some((at, i)) if i == index && now - at < DOUBLE_CLICK_INTERVAL
+                        );
+                        self.branches.state.select(Some(index));
+                        if double_click {
+                            self.last_click = None;
+                            if !self.picking && self.input_mode == InputMode::Search {
+                                self.checkout_mode();
+                            }
+                        } else {
+                            self.last_click = Some((now, index));
+                        }
+                    }
+                    _ => {}
+                }
+                None
+            }
+            InputMode::Status | InputMode::Hunks | InputMode::Reflog => {
+                let popup = self.areas.log?;
+                if !util::contains(&popup, column, row) {
+                    return None;
+                }
+                match kind {
+                    MouseKind::ScrollDown => Some(Key::Down),
+                    MouseKind::ScrollUp => Some(Key::Up),
+                    _ => None,
+                }
+            }
+            InputMode::Compare | InputMode::Diff => {
+                let popup = self.areas.log?;
+                if !util::contains(&popup, column, row) {
+                    return None;
+                }
+                match kind {
+                    MouseKind::ScrollDown => {
+                        self.popup_scroll = self.popup_scroll.saturating_add(1)
+                    }
+                    MouseKind::ScrollUp => self.popup_scroll = self.popup_scroll.saturating_sub(1),
+                    _ => {}
+                }
+                None
+            }
+            InputMode::Help
+            | InputMode::Rebase
+            | InputMode::MergeConflicts
+            | InputMode::CherryPickConflicts
+            | InputMode::LogFilter
+            | InputMode::EditDescription
+            | InputMode::CommitMessage
+            | InputMode::Reword
+            | InputMode::ReflogBranch
+            | InputMode::Recent => None,
+        }
+    }
+
+    /// Appends pasted text to the search input. Only the first line is used,
+    /// except for commit messages and descriptions.
+    pub fn paste(&mut self, text: &str) {
+        if self.input_mode == InputMode::CommitMessage {
+            self.commit_message.push_str(text);
+        } else if self.input_mode == InputMode::EditDescription {
+            self.description_input.push_str(text);
+        } else if let (InputMode::Reword, Some(form)) = (self.input_mode, self.reword.as_mut()) {
+            form.message.push_str(text);
+        } else if matches!(
+            self.input_mode,
+            InputMode::Search | InputMode::SelectTarget(_)
+        ) {
+            self.input
+                .push_str(text.lines().next().unwrap_or_default().trim());
+            self.refresh_branches();
+        }
+    }
+
+    pub fn selected_branch(&self) -> Option<&String> {
+        self.branches.selected()
+    }
+
+    pub fn run_command(&self) -> anyhow::Result<String> {
+        match self.input_mode {
+            InputMode::Command(command) => command.run(self),
+            _ => Ok(String::new()),
+        }
+    }
+
+    /// Runs the pending command and goes back to the branch list,
+    /// reporting the result in the status bar instead of exiting.
+    pub fn run_command_and_stay(&mut self) -> anyhow::Result<()> {
+        self.notification = Some(match self.run_command() {
+            Ok(message) => Notification::Info(message),
+            Err(e) => Notification::Error(e.to_string()),
+        });
+        self.selected.clear();
+        self.reload_branches()?;
+        self.search_mode();
+        self.check_rebase();
+        self.check_merge();
+        self.invalidate();
+        Ok(())
+    }
+
+    pub fn branch_template(&self) -> BranchTemplate {
+        BranchTemplate::new(&self.config.branch_template)
+    }
+
+    /// Runs the branch list on the terminal. With `exit_after_command`, prints what the
+    /// command did once it is done, or fails with its error.
+    pub fn start(&mut self) -> anyhow::Result<()> {
+        self.run()?;
+        match self.notification.take() {
+            Some(Notification::Info(message)) if self.exiting => println!("{}", message),
+            Some(Notification::Error(e)) if self.exiting => return Err(anyhow::anyhow!(e)),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Whether the command run with `exit_after_command` is done, so the app can exit.
+    pub fn is_done(&self) -> bool {
+        self.exiting && self.running.is_none()
+    }
+
+    /// Runs the branch list on the terminal and returns the picked branches instead of
+    /// checking anything out. Nothing is written to stdout, so it can be captured.
+    pub fn pick(&mut self) -> anyhow::Result<Vec<String>> {
+        self.picking = true;
+        {
+            let _tty = crate::util::tty::StdoutToTty::new()?;
+            self.run()?;
+        }
+        Ok(std::mem::take(&mut self.picked))
+    }
+
+    fn run(&mut self) -> anyhow::Result<()> {
+        // Terminal initialization
+        let stdout = io::stdout().into_raw_mode()?;
+        let stdout = MouseTerminal::from(stdout);
+        let stdout = BracketedPasteTerminal::from(stdout);
+        let stdout = AlternateScreen::from(stdout);
+        let backend = TermionBackend::new(stdout);
+        let mut terminal = Terminal::new(backend)?;
+
+        // Setup event handlers
+        let events = Events::new()?;
+        self.worker.set_waker(events.waker());
+
+        loop {
+            self.update();
+            if self.is_done() {
+                break;
+            }
+
+            // Draw UI
+            terminal.draw(|mut f| self.draw(&mut f))?;
+
+            // Put the cursor back inside the input box
+            let (x, y) = self.cursor_position();
+            terminal.set_cursor(x, y)?;
+
+            // Handle input
+            let input = match events.next()? {
+                Event::Input(input) => input,
+                Event::Mouse(mouse) => match self.handle_mouse(mouse) {
+                    Some(input) => input,
+                    None => continue,
+                },
+                Event::Paste(text) => {
+                    self.paste(&text);
+                    continue;
+                }
+                // the next draw picks up the new terminal size
+                Event::Resize(..) | Event::Tick => continue,
+            };
+            let action = self.handle_key(input);
+            if !self.perform(action)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(TOP_MARGIN)
+            .constraints(
+                [
+                    Constraint::Length(HELP_MESSAGE_HEIGHT),
+                    Constraint::Length(TEXT_INPUT_HEIGHT),
+                    Constraint::Min(1),
+                    Constraint::Length(STATUS_BAR_HEIGHT),
+                ]
+                .as_ref(),
+            )
+            .split(f.size());
+
+        {
+            // help, and the current branch with the state of the working tree
+            let current_branch = self.current_branch.as_deref();
+            let summary_width =
+                component::WorkingTreeSummary::width(current_branch, self.working_tree.as_ref());
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(1), Constraint::Length(summary_width)].as_ref())
+                .split(chunks[0]);
+            component::DefaultHelp::render(f, &chunks[0], &self.input_mode);
+            component::WorkingTreeSummary::render(
+                f,
+                &chunks[1],
+                current_branch,
+                self.working_tree.as_ref(),
+            );
+        }
+        let title = match (self.input_mode, &self.source_branch) {
+            (InputMode::SelectTarget(Target::Rebase), Some(branch)) => {
+                format!("Rebase {} onto", branch)
+            }
+            (InputMode::SelectTarget(Target::Compare), Some(branch)) => {
+                format!("Compare {} with", branch)
+            }
+            (InputMode::LogFilter, _) => "Log filter".to_owned(),
+            (InputMode::ReflogBranch, _) => format!(
+                "New branch at {}",
+                self.reflog
+                    .get(self.reflog_cursor)
+                    .map(|entry| entry.selector.as_str())
+                    .unwrap_or_default()
+            ),
+            _ => "Input".to_owned(),
+        };
+        component::SearchInput::render(f, &chunks[1], self.shown_input(), &title);
+
+        {
+            // main area
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [
+                        Constraint::Percentage(LIST_WIDTH_PERCENTAGE),
+                        Constraint::Percentage(100 - LIST_WIDTH_PERCENTAGE),
+                    ]
+                    .as_ref(),
+                )
+                .split(chunks[2]);
+
+            // branches
+            self.areas.branch_list = chunks[0];
+            let template = self.branch_template();
+            component::BranchList::render(
+                f,
+                &chunks[0],
+                &mut self.branches,
+                self.current_branch.as_deref(),
+                &self.statuses,
+                self.loading_statuses,
+                &template,
+            );
+            // selected, and the description of the highlighted branch if it has one
+            let description = self
+                .selected_branch()
+                .and_then(|branch| self.descriptions.get(branch));
+            let side = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Min(1),
+                        Constraint::Length(if description.is_some() {
+                            DESCRIPTION_HEIGHT
+                        } else {
+                            0
+                        }),
+                    ]
+                    .as_ref(),
+                )
+                .split(chunks[1]);
+            component::SelectedList::render(f, &side[0], &self.selected);
+            if let Some(description) = description {
+                component::Description::render(f, &side[1], description);
+            }
+        }
+
+        component::StatusBar::render(f, &chunks[3], self.notification.as_ref());
+
+        self.areas.log = None;
+        self.areas.buttons = None;
+        match self.input_mode {
+            InputMode::Help => {
+                component::Help::render(f);
+            }
+            InputMode::Compare => {
+                if let (Some(a), Some(b)) = (&self.source_branch, &self.target_branch) {
+                    self.areas.log = Some(component::Compare::render(
+                        f,
+                        a,
+                        b,
+                        self.comparison.as_ref(),
+                        self.popup_scroll,
+                    ));
+                }
+            }
+            InputMode::Status => {
+                self.areas.log = Some(component::WorkingTree::render(
+                    f,
+                    self.working_tree.as_ref(),
+                    self.status_cursor,
+                ));
+            }
+            InputMode::Hunks => {
+                if let Some((path, staged)) = &self.hunk_file {
+                    let title = format!(
+                        "{} changes of {}",
+                        if *staged { "Staged" } else { "Unstaged" },
+                        path
+                    );
+                    self.areas.log = Some(component::Hunks::render(
+                        f,
+                        &title,
+                        &self.hunks,
+                        self.hunk_cursor,
+                    ));
+                }
+            }
+            InputMode::CommitMessage => {
+                let staged = self
+                    .working_tree
+                    .as_ref()
+                    .map(|status| status.staged.len())
+                    .unwrap_or(0);
+                let title = format!(
+                    "Commit {} file{} as {}",
+                    staged,
+                    if staged == 1 { "" } else { "s" },
+                    self.signature
+                        .as_ref()
+                        .map(ToString::to_string)
+                        .unwrap_or_default()
+                );
+                self.areas.log = Some(component::CommitMessage::render(
+                    f,
+                    &title,
+                    &self.commit_message,
+                    None,
+                ));
+            }
+            InputMode::EditDescription => {
+                if let Some(branch) = self.selected_branch() {
+                    let title = format!("Description of {}", branch);
+                    self.areas.log = Some(component::CommitMessage::render(
+                        f,
+                        &title,
+                        &self.description_input,
+                        None,
+                    ));
+                }
+            }
+            InputMode::Reword => {
+                if let Some(form) = &self.reword {
+                    let title = format!("Reword {} on {}", form.id, form.branch);
+                    let warning = format!(
+                        "{} is pushed already, the branch will need a force push.",
+                        form.id
+                    );
+                    self.areas.log = Some(component::CommitMessage::render(
+                        f,
+                        &title,
+                        &form.message,
+                        Some(warning.as_str()).filter(|_| form.pushed),
+                    ));
+                }
+            }
+            InputMode::Recent => {
+                self.areas.log = Some(component::RecentBranches::render(
+                    f,
+                    &self.recent_branches,
+                    self.working_tree.as_ref(),
+                ));
+            }
+            InputMode::Reflog | InputMode::ReflogBranch | InputMode::ReflogCheckout => {
+                self.areas.log = Some(component::Reflog::render(
+                    f,
+                    &self.reflog_name,
+                    &self.reflog,
+                    self.reflog_cursor,
+                ));
+                if let (InputMode::ReflogCheckout, Some(entry)) =
+                    (self.input_mode, self.reflog.get(self.reflog_cursor))
+                {
+                    self.areas.buttons = Some(component::CheckoutConfirmation::render_detached(
+                        f,
+                        entry,
+                        self.working_tree.as_ref(),
+                    ));
+                }
+            }
+            InputMode::Diff => {
+                if let Some(comparison) = &self.comparison {
+                    let title = format!("Diff {}...{}", comparison.b, comparison.a);
+                    self.areas.log = Some(component::Diff::render(
+                        f,
+                        &title,
+                        self.diff.as_deref(),
+                        self.popup_scroll,
+                    ));
+                }
+            }
+            InputMode::ShowLog | InputMode::LogFilter => {
+                if let Some(branch_name) = self.selected_branch() {
+                    let logs = self.logs.get(branch_name);
+                    self.areas.log = Some(component::Logs::render(
+                        f,
+                        logs,
+                        &self.log_filter,
+                        self.log_cursor,
+                        &self.marked_commits,
+                    ));
+                }
+            }
+            InputMode::Command(command) => match command {
+                Command::Checkout => {
+                    if let Some(branch_name) = self.selected_branch() {
+                        self.areas.buttons = Some(component::CheckoutConfirmation::render(
+                            f,
+                            branch_name,
+                            self.working_tree.as_ref(),
+                        ));
+                    }
+                }
+                Command::DeleteBranch => {
+                    self.areas.buttons = Some(component::DeleteBranchConfirmation::render(
+                        f,
+                        &self.selected,
+                        &self.remote_branches,
+                        self.delete_remote,
+                        self.working_tree.as_ref(),
+                    ));
+                }
+                Command::Push => {
+                    self.areas.buttons = Some(component::PushConfirmation::render(
+                        f,
+                        &self.marked_or_highlighted(),
+                    ));
+                }
+                Command::ForcePush => {
+                    self.areas.buttons = Some(component::PushConfirmation::render_force(
+                        f,
+                        &self.marked_or_highlighted(),
+                    ));
+                }
+                Command::CherryPick => {
+                    self.areas.buttons = Some(component::CherryPickConfirmation::render(
+                        f,
+                        &self.cherry_pick_commits(),
+                        self.current_branch.as_deref(),
+                    ));
+                }
+                Command::Merge => {
+                    if let Some(form) = &self.merge {
+                        component::MergeConfirmation::render(
+                            f,
+                            form,
+                            self.current_branch.as_deref(),
+                        );
+                    }
+                }
+                Command::Rebase => {
+                    if let (Some(branch), Some(onto)) = (&self.source_branch, &self.target_branch) {
+                        self.areas.buttons =
+                            Some(component::RebaseConfirmation::render(f, branch, onto));
+                    }
+                }
+            },
+            InputMode::Rebase => {
+                if let Some(state) = &self.rebase {
+                    component::RebaseConflicts::render(f, state);
+                }
+            }
+            InputMode::MergeConflicts => {
+                if let Some(conflicts) = &self.merge_conflicts {
+                    component::MergeConflicts::render(f, conflicts);
+                }
+            }
+            InputMode::CherryPickConflicts => {
+                if let Some(state) = &self.cherry_pick {
+                    component::CherryPickConflicts::render(f, state);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The text of the input box, which is the search input unless another one is edited.
+    fn shown_input(&self) -> &str {
+        match self.input_mode {
+            InputMode::LogFilter => &self.log_filter_input,
+            InputMode::ReflogBranch => &self.reflog_branch_input,
+            _ => &self.input,
+        }
+    }
+
+    /// Where the terminal cursor goes: at the end of the search input.
+    pub fn cursor_position(&self) -> (u16, u16) {
+        let message = match self.input_mode {
+            InputMode::CommitMessage => Some(&self.commit_message),
+            InputMode::EditDescription => Some(&self.description_input),
+            InputMode::Reword => self.reword.as_ref().map(|form| &form.message),
+            _ => None,
+        };
+        if let (Some(message), Some(area)) = (message, self.areas.log) {
+            // after the last character of the message
+            let line = message.rsplit('\n').next().unwrap_or_default();
+            let row = message.matches('\n').count() as u16;
+            return (
+                (area.x + 1 + line.width() as u16).min(area.right().saturating_sub(2)),
+                (area.y + 1 + row).min(area.bottom().saturating_sub(2)),
+            );
+        }
+        (
+            TOP_MARGIN + 1 + self.shown_input().width() as u16,
+            TOP_MARGIN + HELP_MESSAGE_HEIGHT + 1,
+        )
+    }
+
+    /// Updates the state for a key press. Anything that touches the repository is left
+    /// to `perform`, so key handling can be tested without a terminal.
+    pub fn handle_key(&mut self, input: Key) -> Action {
+        self.notification = None;
+        match self.input_mode {
+            InputMode::Command(Command::Merge) => match input {
+                Key::Esc | Key::Ctrl('c') => {
+                    self.search_mode();
+                }
+                Key::Char('\n') => {
+                    return Action::RunCommand;
+                }
+                Key::Char('\t') => {
+                    if let Some(form) = self.merge.as_mut() {
+                        form.next_strategy();
+                    }
+                }
+                Key::Char(c) => {
+                    if let Some(form) = self.merge.as_mut() {
+                        form.message.push(c);
+                    }
+                }
+                Key::Ctrl('h') | Key::Backspace | Key::Delete => {
+                    if let Some(form) = self.merge.as_mut() {
+                        form.message.pop();
+                    }
+                }
+                _ => {}
+            },
+            InputMode::Command(_) => match input {
+                Key::Esc | Key::Ctrl('c') | Key::Char('n') | Key::Char('q') => {
+                    self.search_mode();
+                }
+                Key::Char('y') | Key::Char('\n') => {
+                    return Action::RunCommand;
+                }
+                Key::Char('r') if self.input_mode == InputMode::Command(Command::DeleteBranch) => {
+                    self.delete_remote = !self.delete_remote;
+                }
+                _ => {}
+            },
+            InputMode::Search => match input {
+                // exit
+                Key::Esc | Key::Ctrl('c') => {
+                    return Action::Quit;
+                }
+                // press Enter
+                Key::Char('\n') => {
+                    if let Some(x) = self.branches.selected() {
+                        match x.as_ref() {
+                            "master" if !self.picking => {}
+                            _ => {
+                                self.selected.insert(x.clone());
+                                self.branches.next();
+                            }
+                        }
+                    }
+                }
+                Key::Char(c) => {
+                    self.input.push(c);
+                    self.refresh_branches();
+                }
+                Key::Ctrl('h') | Key::Backspace | Key::Delete => {
+                    self.input.pop();
+                    self.refresh_branches();
+                }
+                Key::Ctrl('n') | Key::Down => {
+                    self.branches.next();
+                }
+                Key::Ctrl('p') | Key::Up => {
+                    self.branches.previous();
+                }
+                Key::Ctrl('o') if self.picking => {
+                    self.pick_branches();
+                    if !self.picked.is_empty() {
+                        return Action::Quit;
+                    }
+                }
+                Key::Ctrl('o') => {
+                    self.checkout_mode();
+                }
+                Key::Ctrl('l') => {
+                    self.log_mode();
+                }
+                Key::Alt('h') => {
+                    self.help_mode();
+                }
+                Key::Ctrl('d') if !self.picking => {
+                    return Action::Open(InputMode::Command(Command::DeleteBranch));
+                }
+                Key::Ctrl('f') => {
+                    self.fetch();
+                }
+                Key::Ctrl('u') if !self.picking => {
+                    self.push_mode(false);
+                }
+                Key::Alt('u') if !self.picking => {
+                    self.push_mode(true);
+                }
+                Key::Ctrl('s') if !self.picking => {
+                    return Action::Sync;
+                }
+                Key::Ctrl('r') if !self.picking => {
+                    self.rebase_mode();
+                }
+                Key::Ctrl('g') if !self.picking => {
+                    return Action::Open(InputMode::Command(Command::Merge));
+                }
+                Key::Alt('c') => {
+                    self.compare_mode();
+                }
+                Key::Alt('o') => {
+                    return Action::OpenIssue;
+                }
+                Key::Alt('s') => {
+                    self.status_mode();
+                }
+                Key::Alt('r') => {
+                    return Action::Open(InputMode::Reflog);
+                }
+                Key::Alt('b') if !self.picking => {
+                    return Action::Open(InputMode::Recent);
+                }
+                Key::Ctrl('e') if !self.picking => {
+                    self.edit_description_mode();
+                }
+                _ => {}
+            },
+            InputMode::SelectTarget(target) => match input {
+                Key::Esc | Key::Ctrl('c') => {
+                    self.input.clear();
+                    self.refresh_branches();
+                    self.search_mode();
+                }
+                Key::Char('\n') => {
+                    return self.select_target(target);
+                }
+                Key::Char(c) => {
+                    self.input.push(c);
+                    self.refresh_branches();
+                }
+                Key::Ctrl('h') | Key::Backspace | Key::Delete => {
+                    self.input.pop();
+                    self.refresh_branches();
+                }
+                Key::Ctrl('n') | Key::Down => {
+                    self.branches.next();
+                }
+                Key::Ctrl('p') | Key::Up => {
+                    self.branches.previous();
+                }
+                _ => {}
+            },
+            InputMode::Status => match input {
+                Key::Down | Key::Char('j') => {
+                    self.move_status_cursor(1);
+                }
+                Key::Up | Key::Char('k') => {
+                    self.move_status_cursor(-1);
+                }
+                Key::Char(' ') => {
+                    return Action::Stage;
+                }
+                Key::Char('\n') => {
+                    return Action::Open(InputMode::Hunks);
+                }
+                Key::Char('c') => {
+                    return Action::Open(InputMode::CommitMessage);
+                }
+                Key::Char('r') => {
+                    self.request_working_tree();
+                }
+                Key::Char('q') | Key::Esc | Key::Ctrl('c') | Key::Alt('s') => {
+                    self.search_mode();
+                }
+                _ => {}
+            },
+            InputMode::Hunks => match input {
+                Key::Down | Key::Char('j') => {
+                    self.move_hunk_cursor(1);
+                }
+                Key::Up | Key::Char('k') => {
+                    self.move_hunk_cursor(-1);
+                }
+                Key::Char(' ') => {
+                    return Action::Stage;
+                }
+                Key::Char('q') | Key::Esc | Key::Ctrl('c') => {
+                    self.input_mode = InputMode::Status;
+                }
+                _ => {}
+            },
+            InputMode::Reflog => match input {
+                Key::Down | Key::Char('j') => {
+                    self.move_reflog_cursor(1);
+                }
+                Key::Up | Key::Char('k') => {
+                    self.move_reflog_cursor(-1);
+                }
+                Key::Char('\t') => {
+                    return Action::SwitchReflog;
+                }
+                Key::Char('\n') => {
+                    self.reflog_checkout_mode();
+                }
+                Key::Char('b') => {
+                    self.reflog_branch_mode();
+                }
+                Key::Char('q') | Key::Esc | Key::Ctrl('c') | Key::Alt('r') => {
+                    self.search_mode();
+                }
+                _ => {}
+            },
+            InputMode::Recent => match input {
+                Key::Char(c @ '1'..='9') => {
+                    let index = c as usize - '1' as usize;
+                    if index < self.recent_branches.len() {
+                        return Action::CheckoutRecent(index);
+                    }
+                }
+                Key::Char('q') | Key::Esc | Key::Ctrl('c') | Key::Alt('b') => {
+                    self.search_mode();
+                }
+                _ => {}
+            },
+            InputMode::ReflogCheckout => match input {
+                Key::Esc | Key::Ctrl('c') | Key::Char('n') | Key::Char('q') => {
+                    self.input_mode = InputMode::Reflog;
+                }
+                Key::Char('y') | Key::Char('\n') => return Action::CheckoutReflogEntry,
+                _ => {}
+            },
+            InputMode::ReflogBranch => match input {
+                Key::Esc | Key::Ctrl('c') => {
+                    self.input_mode = InputMode::Reflog;
+                }
+                Key::Char('\n') => return Action::BranchAtReflogEntry,
+                Key::Char(c) => {
+                    self.reflog_branch_input.push(c);
+                }
+                Key::Ctrl('h') | Key::Backspace | Key::Delete => {
+                    self.reflog_branch_input.pop();
+                }
+                _ => {}
+            },
+            InputMode::Reword => match input {
+                Key::Esc | Key::Ctrl('c') => {
+                    self.reword = None;
+                    self.input_mode = InputMode::ShowLog;
+                }
+                Key::Ctrl('s') => return Action::Reword,
+                Key::Char(c) => {
+                    if let Some(form) = self.reword.as_mut() {
+                        form.message.push(c);
+                    }
+                }
+                Key::Ctrl('h') | Key::Backspace | Key::Delete => {
+                    if let Some(form) = self.reword.as_mut() {
+                        form.message.pop();
+                    }
+                }
+                _ => {}
+            },
+            InputMode::CommitMessage => match input {
+                Key::Esc | Key::Ctrl('c') => {
+                    self.input_mode = InputMode::Status;
+                }
+                Key::Ctrl('s') => return Action::Commit,
+                Key::Char(c) => {
+                    self.commit_message.push(c);
+                }
+                Key::Ctrl('h') | Key::Backspace | Key::Delete => {
+                    self.commit_message.pop();
+                }
+                _ => {}
+            },
+            InputMode::Compare | InputMode::Diff => match input {
+                Key::Down | Key::Char('j') => {
+                    self.popup_scroll = self.popup_scroll.saturating_add(1);
+                }
+                Key::Up | Key::Char('k') => {
+                    self.popup_scroll = self.popup_scroll.saturating_sub(1);
+                }
+                Key::Char('d') if self.input_mode == InputMode::Compare => {
+                    return Action::Open(InputMode::Diff);
+                }
+                Key::Char('q') | Key::Esc | Key::Ctrl('c') => {
+                    if self.input_mode == InputMode::Diff {
+                        self.popup_scroll = 0;
+                        self.input_mode = InputMode::Compare;
+                    } else {
+                        self.search_mode();
+                    }
+                }
+                _ => {}
+            },
+            InputMode::EditDescription => match input {
+                Key::Esc | Key::Ctrl('c') => {
+                    self.search_mode();
+                }
+                Key::Ctrl('s') => return Action::SaveDescription,
+                Key::Char(c) => {
+                    self.description_input.push(c);
+                }
+                Key::Ctrl('h') | Key::Backspace | Key::Delete => {
+                    self.description_input.pop();
+                }
+                _ => {}
+            },
+            InputMode::LogFilter => match input {
+                Key::Esc | Key::Ctrl('c') => {
+                    self.input_mode = InputMode::ShowLog;
+                }
+                Key::Char('\n') => {
+                    self.apply_log_filter();
+                }
+                Key::Char(c) => {
+                    self.log_filter_input.push(c);
+                }
+                Key::Ctrl('h') | Key::Backspace | Key::Delete => {
+                    self.log_filter_input.pop();
+                }
+                _ => {}
+            },
+            InputMode::CherryPickConflicts => match input {
+                Key::Char('c') => return Action::CherryPick(CherryPickStep::Continue),
+                Key::Char('a') => return Action::CherryPick(CherryPickStep::Abort),
+                Key::Char('q') | Key::Esc | Key::Ctrl('c') => {
+                    self.search_mode();
+                }
+                _ => {}
+            },
+            InputMode::MergeConflicts => match input {
+                Key::Char('c') => return Action::Merge(MergeStep::Commit),
+                Key::Char('a') => return Action::Merge(MergeStep::Abort),
+                Key::Char('q') | Key::Esc | Key::Ctrl('c') => {
+                    self.search_mode();
+                }
+                _ => {}
+            },
+            InputMode::Rebase => match input {
+                Key::Char('c') => return Action::Rebase(RebaseStep::Continue),
+                Key::Char('s') => return Action::Rebase(RebaseStep::Skip),
+                Key::Char('a') => return Action::Rebase(RebaseStep::Abort),
+                Key::Char('q') | Key::Esc | Key::Ctrl('c') => {
+                    self.search_mode();
+                }
+                _ => {}
+            },
+            InputMode::ShowLog => match input {
+                Key::Down | Key::Char('j') => {
+                    self.move_log_cursor(1);
+                }
+                Key::Up | Key::Char('k') => {
+                    self.move_log_cursor(-1);
+                }
+                Key::Char(' ') => {
+                    self.toggle_commit_mark();
+                }
+                Key::Char('c') => {
+                    self.cherry_pick_mode();
+                }
+                Key::Char('/') => {
+                    self.log_filter_mode();
+                }
+                Key::Char('o') => {
+                    return Action::OpenIssue;
+                }
+                Key::Char('r') => {
+                    return Action::Open(InputMode::Reword);
+                }
+                Key::Char('y') | Key::Char('q') | Key::Char('\n') | Key::Esc | Key::Ctrl('n') => {
+                    self.search_mode();
+                }
+                _ => {}
+            },
+            _ => match input {
+                Key::Char('y') | Key::Char('q') | Key::Char('\n') | Key::Esc | Key::Ctrl('n') => {
+                    self.search_mode();
+                }
+                _ => {}
+            },
+        }
+        Action::None
+    }
+
+    /// Carries out an action returned by `handle_key`. Returns `false` when the app should quit.
+    pub fn perform(&mut self, action: Action) -> anyhow::Result<bool> {
+        if let (true, Some(running)) = (action.changes_repository(), &self.running) {
+            self.notification = Some(Notification::Error(format!(
+                "{}, wait until it is done.",
+                running
+            )));
+            return Ok(true);
+        }
+        match action {
+            Action::None => Ok(true),
+            Action::Quit => Ok(false),
+            Action::RunCommand | Action::CheckoutRecent(_)
+                if self.config.exit_after_command && !self.exiting =>
+            {
+                self.exiting = true;
+                Ok(self.perform(action)? && !self.is_done())
+            }
+            Action::RunCommand
+                if matches!(
+                    self.input_mode,
+                    InputMode::Command(Command::Push | Command::ForcePush)
+                ) =>
+            {
+                self.push();
+                Ok(true)
+            }
+            Action::RunCommand if self.input_mode == InputMode::Command(Command::DeleteBranch) => {
+                self.delete_branches();
+                Ok(true)
+            }
+            Action::RunCommand if self.input_mode == InputMode::Command(Command::Merge) => {
+                self.start_merge()?;
+                Ok(true)
+            }
+            Action::RunCommand if self.input_mode == InputMode::Command(Command::Rebase) => {
+                self.start_rebase();
+                Ok(true)
+            }
+            Action::RunCommand if self.input_mode == InputMode::Command(Command::CherryPick) => {
+                self.start_cherry_pick()?;
+                Ok(true)
+            }
+            Action::RunCommand => {
+                self.run_command_and_stay()?;
+                Ok(true)
+            }
+            Action::Open(mode) => {
+                match mode {
+                    InputMode::Command(Command::Merge) => self.merge_mode(),
+                    InputMode::Command(Command::DeleteBranch) => self.delete_branch_mode(),
+                    InputMode::Compare => self.compare_branches(),
+                    InputMode::Diff => self.diff_mode(),
+                    InputMode::Hunks => self.hunks_mode(),
+                    InputMode::CommitMessage => self.commit_mode(),
+                    InputMode::Reword => self.reword_mode(),
+                    InputMode::Reflog => self.reflog_mode(),
+                    InputMode::Recent => self.recent_mode(),
+                    mode => self.input_mode = mode,
+                }
+                Ok(true)
+            }
+            Action::SwitchReflog => {
+                self.toggle_reflog_name();
+                Ok(true)
+            }
+            Action::Stage => {
+                if self.input_mode == InputMode::Hunks {
+                    self.toggle_stage_hunk();
+                } else {
+                    self.toggle_stage();
+                }
+                Ok(true)
+            }
+            Action::OpenIssue => {
+                if self.input_mode == InputMode::ShowLog {
+                    self.open_commit_issue();
+                } else {
+                    self.open_branch_issue();
+                }
+                Ok(true)
+            }
+            Action::Sync => {
+                self.sync();
+                Ok(true)
+            }
+            Action::Rebase(step) => {
+                self.step_rebase(step)?;
+                Ok(true)
+            }
+            Action::Merge(step) => {
+                self.step_merge(step)?;
+                Ok(true)
+            }
+            Action::CherryPick(step) => {
+                self.step_cherry_pick(step)?;
+                Ok(true)
+            }
+            Action::SaveDescription => {
+                self.save_description()?;
+                Ok(true)
+            }
+            Action::Commit => {
+                self.commit();
+                Ok(true)
+            }
+            Action::Reword => {
+                self.reword();
+                Ok(true)
+            }
+            Action::CheckoutRecent(index) => {
+                self.checkout_recent(index)?;
+                Ok(true)
+            }
+            Action::CheckoutReflogEntry => {
+                self.checkout_reflog_entry(false)?;
+                Ok(true)
+            }
+            Action::BranchAtReflogEntry => {
+                self.checkout_reflog_entry(true)?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{buffer_lines, diverged_branches, TempRepo};
+    use tui::backend::TestBackend;
+
+    fn press(app: &mut App, keys: &[Key]) -> anyhow::Result<bool> {
+        for key in keys {
+            let action = app.handle_key(*key);
+            if !app.perform(action)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn type_str(app: &mut App, s: &str) -> anyhow::Result<bool> {
+        press(app, &s.chars().map(Key::Char).collect::<Vec<_>>())
+    }
+
+    /// Applies every response the worker still owes.
+    fn wait_for_worker(app: &mut App) {
+        while !app.worker.is_idle() {
+            match app.worker.recv_timeout(Duration::from_secs(5)) {
+                Some(response) => app.apply(response),
+                None => panic!("the worker did not answer"),
+            }
+        }
+    }
+
+    fn render(app: &mut App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|mut f| app.draw(&mut f)).unwrap();
+        buffer_lines(terminal.backend().buffer())
+    }
+
+    fn repo_with_branches() -> TempRepo {
+        let repo = TempRepo::new();
+        repo.branch("develop")
+            .branch("feature/1-foo")
+            .branch("feature/2-bar");
+        repo
+    }
+
+    #[test]
+    fn test_delete_branch_mode_when_empty_selected() -> anyhow::Result<()> {
+        let repo = TempRepo::new();
+        let mut app = App::new(repo.path())?;
+
+        app.delete_branch_mode();
+
+        assert_ne!(app.input_mode, InputMode::Command(Command::DeleteBranch));
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_branch_mode_when_not_empty_selected() -> anyhow::Result<()> {
+        let repo = TempRepo::new();
+        let mut app = App::new(repo.path())?;
+        app.selected.insert("Hoge".into());
+
+        app.delete_branch_mode();
+
+        assert_eq!(app.input_mode, InputMode::Command(Command::DeleteBranch));
+        Ok(())
+    }
+
+    #[test]
+    fn test_paste() -> anyhow::Result<()> {
+        let repo = TempRepo::new();
+        let mut app = App::new(repo.path())?;
+
+        app.paste(" feature/1234\nignored");
+        assert_eq!(app.input, "feature/1234");
+
+        app.help_mode();
+        app.paste("foo");
+        assert_eq!(app.input, "feature/1234");
+        Ok(())
+    }
+
+    #[test]
+    fn test_pick_branches() -> anyhow::Result<()> {
+        let repo = TempRepo::new();
+        let mut app = App::new(repo.path())?;
+        app.branches
+            .set_items(vec!["main".into(), "develop".into()]);
+
+        app.pick_branches();
+        assert_eq!(app.picked, vec!["main".to_owned()]);
+
+        app.selected.insert("foo".into());
+        app.selected.insert("bar".into());
+        app.pick_branches();
+        assert_eq!(app.picked, vec!["bar".to_owned(), "foo".to_owned()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_and_render() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        let mut app = App::new(repo.path())?;
+
+        assert!(type_str(&mut app, "feat")?);
+        assert_eq!(app.branches.items, vec!["feature/1-foo", "feature/2-bar"]);
+        assert_eq!(
+            app.selected_branch().map(|x| x.as_str()),
+            Some("feature/1-foo")
+        );
+
+        assert!(press(&mut app, &[Key::Down, Key::Char('\n')])?);
+        assert!(app.selected.contains("feature/2-bar"));
+
+        wait_for_worker(&mut app);
+        let lines = render(&mut app, 60, 12);
+        assert_eq!(
+            lines[2].trim_end(),
+            " ┌Input───────────────────────────────────────────────────┐"
+        );
+        assert_eq!(
+            lines[3].trim_end(),
+            " │feat                                                    │"
+        );
+        assert_eq!(
+            lines[6].trim_end(),
+            " │➢ #1 feature/1-foo me││0: feature/2-bar                 │"
+        );
+        assert_eq!(
+            lines[7].trim_end(),
+            " │  #2 feature/2-bar me││                                 │"
+        );
+        assert_eq!(app.cursor_position(), (6, 3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_background_loading() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        repo.checkout("develop").commit_file("a.txt", "a", "add a");
+        repo.checkout("master");
+        let mut app = App::new(repo.path())?;
+
+        let lines = render(&mut app, 60, 12);
+        assert!(lines[5].contains("Branches (loading…)"));
+
+        wait_for_worker(&mut app);
+        assert_eq!(app.current_branch, Some("master".into()));
+        assert!(!app.loading_statuses);
+        assert!(app.statuses["feature/1-foo"].merged);
+        assert!(!app.statuses["develop"].merged);
+
+        assert!(type_str(&mut app, "develop")?);
+        assert!(press(&mut app, &[Key::Ctrl('l')])?);
+        assert!(render(&mut app, 60, 12)
+            .iter()
+            .any(|l| l.contains("Loading…")));
+
+        wait_for_worker(&mut app);
+        assert_eq!(app.logs["develop"].as_ref().unwrap().len(), 2);
+        // a commit takes one line, which needs the width
+        assert!(render(&mut app, 100, 12)
+            .iter()
+            .any(|l| l.contains("add a")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_refresh_on_refs_change() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        let mut app = App::new(repo.path())?;
+        assert!(type_str(&mut app, "feature")?);
+        assert!(press(&mut app, &[Key::Down, Key::Char('\n'), Key::Down])?);
+        assert_eq!(app.selected_branch().unwrap(), "feature/2-bar");
+
+        repo.branch("feature/0-new");
+        repo.repo
+            .find_branch("feature/1-foo", git2::BranchType::Local)?
+            .delete()?;
+
+        let start = Instant::now();
+        while !app.all_branches.contains(&"feature/0-new".to_owned()) {
+            assert!(start.elapsed() < Duration::from_secs(5), "not refreshed");
+            std::thread::sleep(Duration::from_millis(10));
+            app.update();
+        }
+        assert_eq!(app.branches.items, vec!["feature/0-new", "feature/2-bar"]);
+        assert_eq!(app.selected_branch().unwrap(), "feature/2-bar");
+        assert_eq!(app.selected, HashSet::from(["feature/2-bar".to_owned()]));
+
+        // a changed search starts from the first match again
+        assert!(press(&mut app, &[Key::Backspace])?);
+        assert_eq!(app.branches.items, vec!["feature/0-new", "feature/2-bar"]);
+        assert_eq!(app.selected_branch().unwrap(), "feature/0-new");
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        let origin = repo.add_remote("origin");
+        repo.push("origin", "develop")
+            .push("origin", "feature/1-foo");
+        origin
+            .repo
+            .find_branch("feature/1-foo", git2::BranchType::Local)?
+            .delete()?;
+        let mut app = App::new(repo.path())?;
+
+        assert!(press(&mut app, &[Key::Ctrl('f')])?);
+        assert!(app.fetching);
+        wait_for_worker(&mut app);
+        assert!(!app.fetching);
+        assert_eq!(
+            app.notification,
+            Some(Notification::Info("Fetched origin".into()))
+        );
+
+        wait_for_worker(&mut app);
+        assert!(app.statuses["feature/1-foo"].upstream_gone);
+        assert!(!app.statuses["develop"].upstream_gone);
+        let lines = render(&mut app, 80, 12);
+        assert!(lines.iter().any(|l| l.contains("feature/1-foo gone")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_push() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        let origin = repo.add_remote("origin");
+        let mut app = App::new(repo.path())?;
+
+        assert!(type_str(&mut app, "feature")?);
+        assert!(press(
+            &mut app,
+            &[Key::Char('\n'), Key::Char('\n'), Key::Ctrl('u')]
+        )?);
+        assert_eq!(app.input_mode, InputMode::Command(Command::Push));
+        let lines = render(&mut app, 60, 20);
+        assert!(lines.iter().any(|l| l.contains("Push Branch")));
+        assert!(lines.iter().any(|l| l.contains("--> feature/2-bar")));
+
+        assert!(press(&mut app, &[Key::Char('y')])?);
+        assert_eq!(app.running, Some(Operation::Push));
+        wait_for_worker(&mut app);
+        assert_eq!(
+            app.notification,
+            Some(Notification::Info(
+                "Pushed 2 branch(es): feature/1-foo -> origin/feature/1-foo, \
+                 feature/2-bar -> origin/feature/2-bar"
+                    .into()
+            ))
+        );
+        assert!(origin
+            .repo
+            .find_reference("refs/heads/feature/1-foo")
+            .is_ok());
+        assert!(app.repo.branch_status("feature/2-bar")?.upstream.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_force_push_needs_its_own_confirmation() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        let _origin = repo.add_remote("origin");
+        let mut app = App::new(repo.path())?;
+
+        assert!(type_str(&mut app, "develop")?);
+        assert!(press(&mut app, &[Key::Alt('u')])?);
+        assert_eq!(app.input_mode, InputMode::Command(Command::ForcePush));
+        let lines = render(&mut app, 60, 20);
+        assert!(lines.iter().any(|l| l.contains("Force Push Branch")));
+
+        assert!(press(&mut app, &[Key::Char('n')])?);
+        assert_eq!(app.input_mode, InputMode::Search);
+        assert!(app.repo.branch_status("develop")?.upstream.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkout_and_stay() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        let mut app = App::new(repo.path())?;
+
+        assert!(type_str(&mut app, "develop")?);
+        assert!(press(&mut app, &[Key::Ctrl('o')])?);
+        assert_eq!(app.input_mode, InputMode::Command(Command::Checkout));
+
+        let lines = render(&mut app, 60, 12);
+        assert!(lines[3].contains("Checkout Branch"));
+        assert!(lines[4].contains("Would you like to checkout develop ?"));
+        assert!(lines[6].contains("Enter: y or n"));
+
+        assert!(press(&mut app, &[Key::Char('y')])?);
+        assert_eq!(app.repo.current_branch()?, Some("develop".into()));
+        assert_eq!(app.input_mode, InputMode::Search);
+        assert_eq!(
+            app.notification,
+            Some(Notification::Info("Switched to branch 'develop'".into()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_branches() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        let mut app = App::new(repo.path())?;
+
+        assert!(type_str(&mut app, "feature")?);
+        assert!(press(
+            &mut app,
+            &[Key::Char('\n'), Key::Char('\n'), Key::Ctrl('d')]
+        )?);
+        assert_eq!(app.input_mode, InputMode::Command(Command::DeleteBranch));
+        assert!(press(&mut app, &[Key::Char('y')])?);
+        assert!(app.selected.is_empty());
+        assert_eq!(app.running, Some(Operation::DeleteBranches));
+        wait_for_worker(&mut app);
+
+        assert_eq!(app.all_branches, vec!["develop", "master"]);
+        assert!(app.selected.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_remote_branches() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        let origin = repo.add_remote("origin");
+        repo.push("origin", "feature/1-foo")
+            .push("origin", "feature/2-bar");
+        // an upstream on a remote that cannot be reached
+        let broken = repo.add_remote("broken");
+        repo.push("broken", "develop");
+        drop(broken);
+        let mut app = App::new(repo.path())?;
+
+        app.selected.insert("develop".into());
+        assert!(type_str(&mut app, "feature")?);
+        assert!(press(
+            &mut app,
+            &[Key::Char('\n'), Key::Char('\n'), Key::Ctrl('d')]
+        )?);
+        assert_eq!(
+            app.remote_branches
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "broken/develop",
+                "origin/feature/1-foo",
+                "origin/feature/2-bar"
+            ]
+        );
+        let lines = render(&mut app, 60, 30);
+        assert!(lines.iter().any(|l| l.contains("--> origin/feature/1-foo")));
+        assert!(lines.iter().any(|l| l.contains("[ ] r: also delete")));
+
+        assert!(press(&mut app, &[Key::Char('r')])?);
+        let lines = render(&mut app, 60, 30);
+        assert!(lines.iter().any(|l| l.contains("[x] r: also delete")));
+        assert!(press(&mut app, &[Key::Char('y')])?);
+        wait_for_worker(&mut app);
+
+        match &app.notification {
+            Some(Notification::Error(message)) => {
+                assert!(message.starts_with(
+                    "Deleted 3 branch(es): develop, feature/1-foo, feature/2-bar, \
+                     origin/feature/1-foo, origin/feature/2-bar. \
+                     Failed to delete: broken/develop"
+                ));
+            }
+            n => panic!("unexpected notification: {:?}", n),
+        }
+        assert!(origin
+            .repo
+            .find_reference("refs/heads/feature/1-foo")
+            .is_err());
+        assert!(origin
+            .repo
+            .find_reference("refs/heads/feature/2-bar")
+            .is_err());
+        assert_eq!(app.all_branches, vec!["master"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_remote_branches_only_with_local_ones() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        let origin = repo.add_remote("origin");
+        repo.push("origin", "feature/1-foo")
+            .push("origin", "feature/2-bar")
+            .checkout("feature/1-foo");
+        let mut app = App::new(repo.path())?;
+
+        // the current branch cannot be deleted, so its remote branch stays
+        app.selected.insert("feature/1-foo".into());
+        app.selected.insert("feature/2-bar".into());
+        app.delete_branch_mode();
+        assert_eq!(app.remote_branches.len(), 2);
+        assert!(press(&mut app, &[Key::Char('r'), Key::Char('y')])?);
+        wait_for_worker(&mut app);
+
+        match &app.notification {
+            Some(Notification::Error(message)) => {
+                assert!(message.starts_with(
+                    "Deleted 1 branch(es): feature/2-bar, origin/feature/2-bar. \
+                     Failed to delete: feature/1-foo"
+                ));
+            }
+            n => panic!("unexpected notification: {:?}", n),
+        }
+        assert!(origin
+            .repo
+            .find_reference("refs/heads/feature/1-foo")
+            .is_ok());
+        assert!(origin
+            .repo
+            .find_reference("refs/heads/feature/2-bar")
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sync() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        let _origin = repo.add_remote("origin");
+        repo.push("origin", "develop")
+            .push("origin", "feature/1-foo");
+        repo.commit_file("a.txt", "a", "add a");
+        let new = repo.repo.revparse_single("master")?.id();
+        repo.repo
+            .reference("refs/remotes/origin/develop", new, true, "")?;
+        repo.checkout("feature/1-foo")
+            .commit_file("b.txt", "b", "add b");
+        repo.repo
+            .reference("refs/remotes/origin/feature/1-foo", new, true, "")?;
+        repo.checkout("master");
+        let mut app = App::new(repo.path())?;
+        app.selected.insert("develop".into());
+        app.selected.insert("feature/1-foo".into());
+
+        assert!(press(&mut app, &[Key::Ctrl('s')])?);
+        assert_eq!(app.running, Some(Operation::Sync));
+        wait_for_worker(&mut app);
+        match &app.notification {
+            Some(Notification::Error(message)) => {
+                assert!(message.starts_with("develop: fast-forwarded "));
+                assert!(message.ends_with(", feature/1-foo: diverged (↑1 ↓1)"));
+            }
+            n => panic!("unexpected notification: {:?}", n),
+        }
+        assert_eq!(repo.repo.revparse_single("develop")?.id(), new);
+        assert_eq!(app.repo.current_branch()?, Some("master".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_description() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(type_str(&mut app, "1-foo")?);
+        let lines = render(&mut app, 100, 20);
+        assert!(!lines.iter().any(|l| l.contains("Description")));
+
+        assert!(press(&mut app, &[Key::Ctrl('e')])?);
+        assert_eq!(app.input_mode, InputMode::EditDescription);
+        assert!(type_str(&mut app, "Fix the login\n\nIt fails on Safari.")?);
+        let lines = render(&mut app, 100, 20);
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("Description of feature/1-foo")));
+        let (x, y) = app.cursor_position();
+        assert!(lines[y as usize].contains("It fails on Safari."));
+        assert!(x > 0);
+        assert!(press(&mut app, &[Key::Ctrl('s')])?);
+
+        assert_eq!(app.input_mode, InputMode::Search);
+        assert_eq!(
+            app.descriptions["feature/1-foo"],
+            "Fix the login\n\nIt fails on Safari."
+        );
+        let lines = render(&mut app, 100, 20);
+        assert!(lines.iter().any(|l| l.contains("┌Description")));
+        assert!(lines.iter().any(|l| l.contains("│Fix the login")));
+        assert!(lines.iter().any(|l| l.contains("│It fails on Safari.")));
+
+        // editing starts from the description, and clearing it removes it
+        assert!(press(&mut app, &[Key::Ctrl('e')])?);
+        assert_eq!(
+            app.description_input,
+            "Fix the login\n\nIt fails on Safari."
+        );
+        app.description_input.clear();
+        assert!(press(&mut app, &[Key::Ctrl('s')])?);
+        assert!(app.descriptions.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_working_tree_status() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        repo.commit_file("a.txt", "a", "add a");
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+        assert!(render(&mut app, 100, 20)[1]
+            .trim_end()
+            .ends_with("master ✔"));
+
+        std::fs::write(repo.path().join("a.txt"), "changed")?;
+        std::fs::write(repo.path().join("b.txt"), "b")?;
+        assert!(press(&mut app, &[Key::Alt('s')])?);
+        assert_eq!(app.input_mode, InputMode::Status);
+        wait_for_worker(&mut app);
+        let lines = render(&mut app, 100, 20);
+        assert!(lines[1].trim_end().ends_with("master ~1 ?1"));
+        assert!(lines.iter().any(|l| l.contains("Unstaged (1):")));
+        assert!(lines.iter().any(|l| l.contains("M a.txt")));
+        assert!(lines.iter().any(|l| l.contains("? b.txt")));
+        assert!(press(&mut app, &[Key::Esc])?);
+
+        // checking out warns about the changes
+        assert!(type_str(&mut app, "develop")?);
+        assert!(press(&mut app, &[Key::Ctrl('o')])?);
+        wait_for_worker(&mut app);
+        assert!(render(&mut app, 100, 20)
+            .iter()
+            .any(|l| l.contains("uncommitted changes (1 unstaged)")));
+        assert!(press(&mut app, &[Key::Esc])?);
+
+        std::fs::write(repo.path().join("a.txt"), "a")?;
+        assert!(press(&mut app, &[Key::Ctrl('o')])?);
+        wait_for_worker(&mut app);
+        assert!(!render(&mut app, 100, 20)
+            .iter()
+            .any(|l| l.contains("uncommitted changes")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stage_and_commit() -> anyhow::Result<()> {
+        let repo = TempRepo::new();
+        let lines = (1..=20)
+            .map(|i| format!("line {}\n", i))
+            .collect::<String>();
+        repo.commit_file("a.txt", &lines, "add a");
+        std::fs::write(
+            repo.path().join("a.txt"),
+            lines
+                .replace("line 2\n", "line two\n")
+                .replace("line 19\n", "line nineteen\n"),
+        )?;
+        std::fs::write(repo.path().join("b.txt"), "b")?;
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(press(&mut app, &[Key::Alt('s'), Key::Char('c')])?);
+        assert_eq!(app.input_mode, InputMode::Status);
+        assert_eq!(
+            app.notification,
+            Some(Notification::Error("Nothing is staged to commit.".into()))
+        );
+
+        // stage the first hunk of a.txt
+        assert!(press(&mut app, &[Key::Char('\n')])?);
+        assert_eq!(app.input_mode, InputMode::Hunks);
+        assert_eq!(app.hunks.len(), 2);
+        assert!(render(&mut app, 100, 30)
+            .iter()
+            .any(|l| l.contains("Unstaged changes of a.txt")));
+        assert!(press(&mut app, &[Key::Char(' '), Key::Esc])?);
+        assert_eq!(app.input_mode, InputMode::Status);
+        wait_for_worker(&mut app);
+        let status = app.working_tree.as_ref().unwrap();
+        assert_eq!(status.staged.len(), 1);
+        assert_eq!(status.unstaged.len(), 1);
+
+        // and all of b.txt
+        assert!(press(
+            &mut app,
+            &[Key::Char('j'), Key::Char('j'), Key::Char(' ')]
+        )?);
+        wait_for_worker(&mut app);
+        assert_eq!(app.working_tree.as_ref().unwrap().staged.len(), 2);
+        assert!(press(&mut app, &[Key::Char('c')])?);
+        assert_eq!(app.input_mode, InputMode::CommitMessage);
+        assert!(type_str(&mut app, "Add b\n\nAnd a bit of a")?);
+        let lines = render(&mut app, 100, 30);
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("Commit 2 files as git-ex <git-ex@example.com>")));
+        let (x, y) = app.cursor_position();
+        assert!(lines[y as usize][..].contains("And a bit of a"));
+        assert!(x > 0);
+
+        assert!(press(&mut app, &[Key::Ctrl('s')])?);
+        assert_eq!(
+            app.notification,
+            Some(Notification::Info("Running the commit hooks…".into()))
+        );
+        wait_for_worker(&mut app);
+        assert_eq!(app.input_mode, InputMode::Status);
+        let head = repo.repo.head()?.peel_to_commit()?;
+        assert_eq!(head.message(), Some("Add b\n\nAnd a bit of a\n"));
+        assert!(app.commit_message.is_empty());
+        wait_for_worker(&mut app);
+        let status = app.working_tree.as_ref().unwrap();
+        assert!(status.staged.is_empty());
+        assert_eq!(status.unstaged[0].path, "a.txt");
+
+        // unstaging a whole file
+        std::fs::write(repo.path().join("b.txt"), "bb")?;
+        assert!(press(&mut app, &[Key::Char('r')])?);
+        wait_for_worker(&mut app);
+        assert!(press(&mut app, &[Key::Char('j'), Key::Char(' ')])?);
+        wait_for_worker(&mut app);
+        assert_eq!(app.working_tree.as_ref().unwrap().staged[0].path, "b.txt");
+        assert!(press(&mut app, &[Key::Char('k'), Key::Char(' ')])?);
+        wait_for_worker(&mut app);
+        assert!(app.working_tree.as_ref().unwrap().staged.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_keys_leave_the_repository_to_perform() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        std::fs::write(repo.path().join("b.txt"), "b")?;
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert_eq!(
+            app.handle_key(Key::Ctrl('g')),
+            Action::Open(InputMode::Command(Command::Merge))
+        );
+        assert_eq!(
+            app.handle_key(Key::Alt('r')),
+            Action::Open(InputMode::Reflog)
+        );
+        assert!(press(&mut app, &[Key::Alt('s')])?);
+        assert_eq!(app.handle_key(Key::Char(' ')), Action::Stage);
+        assert_eq!(app.input_mode, InputMode::Status);
+        assert_eq!(
+            repo.repo.status_file(Path::new("b.txt"))?,
+            git2::Status::WT_NEW
+        );
+
+        assert!(app.perform(Action::Stage)?);
+        assert_eq!(
+            repo.repo.status_file(Path::new("b.txt"))?,
+            git2::Status::INDEX_NEW
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_reword() -> anyhow::Result<()> {
+        let repo = TempRepo::new();
+        let _origin = repo.add_remote("origin");
+        repo.branch("feature")
+            .checkout("feature")
+            .commit_file("a.txt", "a", "Add a")
+            .push("origin", "feature")
+            .commit_file("b.txt", "b", "Add b")
+            .commit_file("c.txt", "c", "Add c");
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(type_str(&mut app, "feature")?);
+        assert!(press(&mut app, &[Key::Ctrl('l')])?);
+        wait_for_worker(&mut app);
+        assert!(press(&mut app, &[Key::Char('j'), Key::Char('r')])?);
+        assert_eq!(app.input_mode, InputMode::Reword);
+        let form = app.reword.as_ref().unwrap();
+        assert_eq!(form.message, "Add b");
+        assert!(!form.pushed);
+        assert!(!render(&mut app, 100, 30)
+            .iter()
+            .any(|l| l.contains("pushed already")));
+
+        assert!(press(&mut app, &[Key::Backspace])?);
+        assert!(type_str(&mut app, "B\n\nWith a body")?);
+        assert!(press(&mut app, &[Key::Ctrl('s')])?);
+        assert!(matches!(app.running, Some(Operation::Reword(_))));
+        wait_for_worker(&mut app);
+        assert_eq!(app.input_mode, InputMode::ShowLog);
+        let head = repo.repo.head()?.peel_to_commit()?;
+        assert_eq!(head.message(), Some("Add c"));
+        assert_eq!(head.parent(0)?.message(), Some("Add B\n\nWith a body\n"));
+
+        // the pushed commit warns
+        wait_for_worker(&mut app);
+        // the cursor stays on the reworded commit
+        assert!(press(&mut app, &[Key::Char('j'), Key::Char('r')])?);
+        assert_eq!(app.reword.as_ref().unwrap().message, "Add a");
+        assert!(app.reword.as_ref().unwrap().pushed);
+        assert!(render(&mut app, 100, 30)
+            .iter()
+            .any(|l| l.contains("pushed already, the branch will need a force push")));
+        assert!(press(&mut app, &[Key::Esc])?);
+        assert_eq!(app.input_mode, InputMode::ShowLog);
+        assert!(app.reword.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reflog() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        repo.checkout("develop").commit_file("a.txt", "a", "Add a");
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(type_str(&mut app, "develop")?);
+        assert!(press(&mut app, &[Key::Alt('r')])?);
+        assert_eq!(app.input_mode, InputMode::Reflog);
+        assert_eq!(app.reflog_name, "HEAD");
+        assert_eq!(app.reflog[0].message, "Add a");
+        let lines = render(&mut app, 120, 30);
+        assert!(lines.iter().any(|l| l.contains("Reflog of HEAD")));
+        assert!(lines.iter().any(
+            |l| l.contains("HEAD@{1}") && l.contains("checkout: moving from master to develop")
+        ));
+
+        // the reflog of the highlighted branch
+        assert!(press(&mut app, &[Key::Char('\t')])?);
+        assert_eq!(app.reflog_name, "develop");
+        assert_eq!(app.reflog[0].selector, "develop@{0}");
+        assert!(press(&mut app, &[Key::Char('\t')])?);
+        assert_eq!(app.reflog_name, "HEAD");
+
+        // check out where HEAD was before the commit, after confirming it
+        std::fs::write(repo.path().join("a.txt"), "changed")?;
+        assert!(press(&mut app, &[Key::Char('j'), Key::Char('\n')])?);
+        assert_eq!(app.input_mode, InputMode::ReflogCheckout);
+        wait_for_worker(&mut app);
+        let lines = render(&mut app, 120, 30);
+        assert!(lines.iter().any(|l| l.contains("HEAD will be detached")));
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("uncommitted changes (1 unstaged)")));
+        assert!(app.areas.buttons.is_some());
+        assert!(press(&mut app, &[Key::Esc])?);
+        assert_eq!(app.input_mode, InputMode::Reflog);
+        assert!(!repo.repo.head_detached()?);
+        std::fs::write(repo.path().join("a.txt"), "a")?;
+
+        assert!(press(&mut app, &[Key::Char('\n'), Key::Char('y')])?);
+        let before = repo.repo.find_branch("master", git2::BranchType::Local)?;
+        assert_eq!(repo.repo.head()?.target(), before.get().target());
+        assert!(repo.repo.head_detached()?);
+        assert_eq!(app.reflog_cursor, 0);
+        assert_eq!(app.reflog[0].action, "checkout");
+        assert!(
+            matches!(&app.notification, Some(Notification::Info(m)) if m.starts_with("HEAD is now at"))
+        );
+
+        // and a branch at the commit
+        assert!(press(&mut app, &[Key::Char('j'), Key::Char('b')])?);
+        assert_eq!(app.input_mode, InputMode::ReflogBranch);
+        assert!(type_str(&mut app, "restored")?);
+        assert!(render(&mut app, 120, 30)
+            .iter()
+            .any(|l| l.contains("New branch at HEAD@{1}")));
+        assert!(press(&mut app, &[Key::Char('\n')])?);
+        assert_eq!(app.input_mode, InputMode::Reflog);
+        assert_eq!(app.repo.current_branch()?.as_deref(), Some("restored"));
+        let develop = repo.repo.find_branch("develop", git2::BranchType::Local)?;
+        assert_eq!(repo.repo.head()?.target(), develop.get().target());
+        assert_eq!(app.reflog[0].message.rsplit(' ').next(), Some("restored"));
+        assert!(app.all_branches.contains(&"restored".to_owned()));
+
+        assert!(press(&mut app, &[Key::Esc])?);
+        assert_eq!(app.input_mode, InputMode::Search);
+        Ok(())
+    }
+
+    #[test]
+    fn test_recent_branches() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        repo.checkout("feature/1-foo")
+            .checkout("develop")
+            .checkout("feature/2-bar")
+            .checkout("develop")
+            .checkout("master");
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(press(&mut app, &[Key::Alt('b')])?);
+        assert_eq!(app.input_mode, InputMode::Recent);
+        assert_eq!(
+            app.recent_branches,
+            vec!["develop", "feature/2-bar", "feature/1-foo"]
+        );
+        let lines = render(&mut app, 100, 30);
+        assert!(lines.iter().any(|l| l.contains("1 develop")));
+        assert!(lines.iter().any(|l| l.contains("3 feature/1-foo")));
+
+        // keys without a branch do nothing
+        assert!(press(&mut app, &[Key::Char('4')])?);
+        assert_eq!(app.input_mode, InputMode::Recent);
+
+        assert!(press(&mut app, &[Key::Char('2')])?);
+        assert_eq!(app.input_mode, InputMode::Search);
+        assert_eq!(app.repo.current_branch()?.as_deref(), Some("feature/2-bar"));
+        assert_eq!(
+            app.notification,
+            Some(Notification::Info(
+                "Switched to branch 'feature/2-bar'".into()
+            ))
+        );
+
+        // the one left comes first
+        assert!(press(&mut app, &[Key::Alt('b')])?);
+        assert_eq!(app.recent_branches[0], "master");
+        assert!(press(&mut app, &[Key::Esc])?);
+        assert_eq!(app.input_mode, InputMode::Search);
+
+        // only as many as configured
+        app.config.recent_branches = 2;
+        assert!(press(&mut app, &[Key::Alt('b')])?);
+        assert_eq!(app.recent_branches, vec!["master", "develop"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_issue() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        repo.checkout("feature/1-foo")
+            .commit_file("a.txt", "a", "Fix the login (PROJ-7)");
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(type_str(&mut app, "1-foo")?);
+        assert!(press(&mut app, &[Key::Alt('o')])?);
+        assert!(
+            matches!(app.notification, Some(Notification::Error(ref e)) if e.contains("ex.issueUrl"))
+        );
+
+        app.config.issue_url = Some("https://tracker/browse/{issue}".into());
+        assert!(press(&mut app, &[Key::Alt('o')])?);
+        assert_eq!(
+            app.notification,
+            Some(Notification::Info("https://tracker/browse/1".into()))
+        );
+
+        assert!(press(&mut app, &[Key::Ctrl('l')])?);
+        wait_for_worker(&mut app);
+        assert!(press(&mut app, &[Key::Char('o')])?);
+        assert_eq!(
+            app.notification,
+            Some(Notification::Info("https://tracker/browse/PROJ-7".into()))
+        );
+        assert!(press(&mut app, &[Key::Char('j'), Key::Char('o')])?);
+        assert!(
+            matches!(app.notification, Some(Notification::Error(ref e)) if e.starts_with("No issue"))
+        );
+
+        // without an issue in the name
+        assert!(press(&mut app, &[Key::Esc])?);
+        app.input.clear();
+        assert!(type_str(&mut app, "develop")?);
+        assert!(press(&mut app, &[Key::Alt('o')])?);
+        assert_eq!(
+            app.notification,
+            Some(Notification::Error("No issue in develop".into()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        repo.checkout("feature/1-foo")
+            .commit_file("b.txt", "b", "add b")
+            .checkout("master")
+            .commit_file("c.txt", "c", "add c");
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(type_str(&mut app, "1-foo")?);
+        assert!(press(&mut app, &[Key::Alt('c')])?);
+        assert_eq!(app.input_mode, InputMode::SelectTarget(Target::Compare));
+        // the current branch is suggested
+        assert_eq!(app.selected_branch(), Some(&"master".to_owned()));
+        assert!(render(&mut app, 60, 12)[2].contains("Compare feature/1-foo with"));
+
+        assert!(press(&mut app, &[Key::Char('\n')])?);
+        assert_eq!(app.input_mode, InputMode::Compare);
+        assert!(render(&mut app, 100, 30)
+            .iter()
+            .any(|l| l.contains("Loading…")));
+        wait_for_worker(&mut app);
+        let lines = render(&mut app, 100, 30);
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("merge base:") && l.contains("initial commit")));
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("only in feature/1-foo (1):")));
+        assert!(lines.iter().any(|l| l.contains("add b")));
+        assert!(lines.iter().any(|l| l.contains("only in master (1):")));
+
+        assert!(press(&mut app, &[Key::Char('d')])?);
+        assert_eq!(app.input_mode, InputMode::Diff);
+        wait_for_worker(&mut app);
+        let lines = render(&mut app, 100, 30);
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("Diff master...feature/1-foo")));
+        assert!(lines.iter().any(|l| l.contains("+++ b/b.txt")));
+        // master's own changes are not the branch's
+        assert!(!lines.iter().any(|l| l.contains("c.txt")));
+
+        assert!(press(&mut app, &[Key::Esc])?);
+        assert_eq!(app.input_mode, InputMode::Compare);
+        assert!(press(&mut app, &[Key::Esc])?);
+        assert_eq!(app.input_mode, InputMode::Search);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rebase() -> anyhow::Result<()> {
+        let repo = diverged_branches();
+        let mut app = App::new(repo.path())?;
+
+        assert!(type_str(&mut app, "feature")?);
+        assert!(press(&mut app, &[Key::Ctrl('r')])?);
+        assert_eq!(app.input_mode, InputMode::SelectTarget(Target::Rebase));
+        assert_eq!(app.source_branch, Some("feature".into()));
+        assert!(render(&mut app, 60, 12)[2].contains("Rebase feature onto"));
+
+        // the branch itself is no target
+        assert!(press(&mut app, &[Key::Char('\n')])?);
+        assert_eq!(app.input_mode, InputMode::SelectTarget(Target::Rebase));
+        assert!(type_str(&mut app, "master")?);
+        assert!(press(&mut app, &[Key::Char('\n')])?);
+        assert_eq!(app.input_mode, InputMode::Command(Command::Rebase));
+        let lines = render(&mut app, 60, 12);
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("Would you like to rebase feature onto master ?")));
+
+        assert!(press(&mut app, &[Key::Char('y')])?);
+        assert_eq!(
+            app.notification,
+            Some(Notification::Info("Rebasing feature onto master…".into()))
+        );
+        wait_for_worker(&mut app);
+        assert_eq!(app.input_mode, InputMode::Rebase);
+        assert_eq!(app.rebase.as_ref().unwrap().conflicts, vec!["a.txt"]);
+        let lines = render(&mut app, 60, 20);
+        assert!(lines.iter().any(|l| l.contains("--> a.txt")));
+
+        // back to the list and in again
+        assert!(press(&mut app, &[Key::Esc, Key::Ctrl('r')])?);
+        assert_eq!(app.input_mode, InputMode::Rebase);
+
+        assert!(press(&mut app, &[Key::Char('c')])?);
+        wait_for_worker(&mut app);
+        assert_eq!(app.input_mode, InputMode::Rebase);
+        repo.write("a.txt", "resolved").add("a.txt");
+        assert!(press(&mut app, &[Key::Char('c')])?);
+        wait_for_worker(&mut app);
+        assert_eq!(app.input_mode, InputMode::Search);
+        assert_eq!(app.rebase, None);
+        assert_eq!(
+            app.notification,
+            Some(Notification::Info(
+                "Rebased feature onto master (2 commit(s))".into()
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_checkout_during_rebase() -> anyhow::Result<()> {
+        let repo = diverged_branches();
+        repo.branch("develop");
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(type_str(&mut app, "feature")?);
+        assert!(press(&mut app, &[Key::Ctrl('r')])?);
+        assert!(type_str(&mut app, "master")?);
+        assert!(press(&mut app, &[Key::Char('\n'), Key::Char('y')])?);
+        assert_eq!(
+            app.running,
+            Some(Operation::Rebase("feature onto master".into()))
+        );
+
+        // the rebase has not been applied yet
+        assert!(type_str(&mut app, "develop")?);
+        assert!(press(&mut app, &[Key::Ctrl('o'), Key::Char('y')])?);
+        assert_eq!(
+            app.notification,
+            Some(Notification::Error(
+                "Rebasing feature onto master, wait until it is done.".into()
+            ))
+        );
+        assert_eq!(app.input_mode, InputMode::Command(Command::Checkout));
+
+        wait_for_worker(&mut app);
+        assert_eq!(app.running, None);
+        assert_eq!(app.input_mode, InputMode::Rebase);
+        assert_ne!(app.repo.current_branch()?.as_deref(), Some("develop"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_staging_during_commit() -> anyhow::Result<()> {
+        let repo = TempRepo::new();
+        repo.commit_file("a.txt", "a", "Add a");
+        repo.write("a.txt", "aa").add("a.txt").write("b.txt", "b");
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(press(&mut app, &[Key::Alt('s'), Key::Char('c')])?);
+        assert!(type_str(&mut app, "Change a")?);
+        assert!(press(&mut app, &[Key::Ctrl('s')])?);
+        assert_eq!(app.running, Some(Operation::Commit("Change a".into())));
+
+        // the hooks have not finished yet
+        assert!(app.perform(Action::Stage)?);
+        assert_eq!(
+            app.notification,
+            Some(Notification::Error(
+                "Running the commit hooks, wait until it is done.".into()
+            ))
+        );
+
+        wait_for_worker(&mut app);
+        assert_eq!(app.running, None);
+        assert_eq!(
+            repo.repo.status_file(Path::new("b.txt"))?,
+            git2::Status::WT_NEW
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        repo.checkout("feature/1-foo")
+            .commit_file("b.txt", "b", "add b")
+            .checkout("master");
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(type_str(&mut app, "1-foo")?);
+        assert!(press(&mut app, &[Key::Ctrl('g')])?);
+        assert_eq!(app.input_mode, InputMode::Command(Command::Merge));
+        let form = app.merge.clone().unwrap();
+        assert_eq!(form.analysis, MergeAnalysis::FastForward);
+        assert_eq!(form.strategy, MergeStrategy::FastForward);
+        let lines = render(&mut app, 80, 20);
+        assert!(lines.iter().any(|l| l.contains("Can be fast-forwarded")));
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("Enter: merge, Tab: strategy, Esc: cancel")));
+        assert!(!lines.iter().any(|l| l.contains("y or n")));
+        assert!(app.areas.buttons.is_none());
+
+        // a merge commit with an edited message
+        assert!(press(&mut app, &[Key::Char('\t')])?);
+        assert!(type_str(&mut app, "!")?);
+        let lines = render(&mut app, 80, 20);
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("message: Merge branch 'feature/1-foo'!")));
+        assert!(press(&mut app, &[Key::Char('\n')])?);
+        assert_eq!(
+            app.running,
+            Some(Operation::Merge {
+                branch: "feature/1-foo".into(),
+                into: "master".into()
+            })
+        );
+        wait_for_worker(&mut app);
+
+        assert_eq!(app.input_mode, InputMode::Search);
+        assert!(matches!(
+            &app.notification,
+            Some(Notification::Info(message))
+                if message.starts_with("Merged feature/1-foo into master (")
+        ));
+        let head = repo.repo.head()?.peel_to_commit()?;
+        assert_eq!(head.message(), Some("Merge branch 'feature/1-foo'!"));
+        assert_eq!(head.parent_count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_conflicts() -> anyhow::Result<()> {
+        let repo = diverged_branches();
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(type_str(&mut app, "feature")?);
+        assert!(press(&mut app, &[Key::Ctrl('g')])?);
+        assert_eq!(app.merge.as_ref().unwrap().analysis, MergeAnalysis::Normal);
+        assert!(press(&mut app, &[Key::Char('\n')])?);
+        wait_for_worker(&mut app);
+
+        assert_eq!(app.input_mode, InputMode::MergeConflicts);
+        assert_eq!(app.merge_conflicts, Some(vec!["a.txt".into()]));
+        let lines = render(&mut app, 80, 20);
+        assert!(lines.iter().any(|l| l.contains("--> a.txt")));
+
+        assert!(press(&mut app, &[Key::Char('a')])?);
+        assert_eq!(app.input_mode, InputMode::Search);
+        assert_eq!(app.merge_conflicts, None);
+        assert_eq!(
+            app.notification,
+            Some(Notification::Info("Aborted the merge".into()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_graph() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        repo.checkout("feature/1-foo")
+            .commit_file("b.txt", "b", "add b")
+            .checkout("master")
+            .commit_file("c.txt", "c", "add c");
+        repo.merge("feature/1-foo", "merge foo");
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(type_str(&mut app, "master")?);
+        assert!(press(&mut app, &[Key::Ctrl('l')])?);
+        wait_for_worker(&mut app);
+        let lines = render(&mut app, 120, 30);
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("●─╮") && l.contains("(HEAD -> master) merge foo")));
+        assert!(lines.iter().any(|l| l.contains("(feature/1-foo) add b")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_filter() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        repo.commit_file("src/a.txt", "a", "add a")
+            .commit_file("b.txt", "b", "add b");
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(type_str(&mut app, "master")?);
+        assert!(press(&mut app, &[Key::Ctrl('l')])?);
+        wait_for_worker(&mut app);
+        assert_eq!(app.logs["master"].as_ref().unwrap().len(), 3);
+
+        assert!(press(&mut app, &[Key::Char('/')])?);
+        assert_eq!(app.input_mode, InputMode::LogFilter);
+        assert!(type_str(&mut app, "path:src")?);
+        let lines = render(&mut app, 100, 30);
+        assert!(lines.iter().any(|l| l.contains("path:src")));
+        assert!(press(&mut app, &[Key::Char('\n')])?);
+        assert_eq!(app.input_mode, InputMode::ShowLog);
+        assert_eq!(app.log_filter.path.as_deref(), Some("src"));
+        wait_for_worker(&mut app);
+
+        let logs = app.logs["master"].as_ref().unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].message, "add a");
+        let lines = render(&mut app, 100, 30);
+        assert!(lines.iter().any(|l| l.contains("Log (path:src)")));
+
+        // an invalid filter keeps the filter bar open
+        assert!(press(&mut app, &[Key::Char('/')])?);
+        assert!(type_str(&mut app, " since:someday")?);
+        assert!(press(&mut app, &[Key::Char('\n')])?);
+        assert_eq!(app.input_mode, InputMode::LogFilter);
+        assert!(matches!(app.notification, Some(Notification::Error(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cherry_pick() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        repo.checkout("feature/1-foo")
+            .commit_file("b.txt", "b", "add b")
+            .commit_file("c.txt", "c", "add c")
+            .commit_file("d.txt", "d", "add d")
+            .checkout("master");
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(type_str(&mut app, "1-foo")?);
+        assert!(press(&mut app, &[Key::Ctrl('l')])?);
+        wait_for_worker(&mut app);
+        // mark "add d" and "add b"
+        assert!(press(
+            &mut app,
+            &[Key::Char(' '), Key::Down, Key::Down, Key::Char(' ')]
+        )?);
+        let lines = render(&mut app, 100, 30);
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("* ") && l.contains("add d")));
+        assert!(!lines
+            .iter()
+            .any(|l| l.contains("* ") && l.contains("add c")));
+
+        assert!(press(&mut app, &[Key::Char('c')])?);
+        assert_eq!(app.input_mode, InputMode::Command(Command::CherryPick));
+        let summaries = app
+            .cherry_pick_commits()
+            .iter()
+            .map(|c| c.message.trim().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(summaries, vec!["add b", "add d"]);
+        assert!(press(&mut app, &[Key::Char('y')])?);
+        assert_eq!(app.running, Some(Operation::CherryPick("master".into())));
+        wait_for_worker(&mut app);
+
+        assert_eq!(
+            app.notification,
+            Some(Notification::Info(
+                "Cherry-picked 2 commit(s) onto master".into()
+            ))
+        );
+        let head = repo.repo.head()?.peel_to_commit()?;
+        assert_eq!(head.summary(), Some("add d"));
+        assert_eq!(head.parent(0)?.summary(), Some("add b"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cherry_pick_conflicts() -> anyhow::Result<()> {
+        let repo = diverged_branches();
+        let mut app = App::new(repo.path())?;
+        wait_for_worker(&mut app);
+
+        assert!(type_str(&mut app, "feature")?);
+        assert!(press(&mut app, &[Key::Ctrl('l')])?);
+        wait_for_worker(&mut app);
+        assert!(press(
+            &mut app,
+            &[Key::Char(' '), Key::Down, Key::Char(' '), Key::Char('c')]
+        )?);
+        assert!(press(&mut app, &[Key::Char('y')])?);
+        wait_for_worker(&mut app);
+
+        assert_eq!(app.input_mode, InputMode::CherryPickConflicts);
+        let state = app.cherry_pick.clone().unwrap();
+        assert_eq!(state.conflicts, vec!["a.txt".to_owned()]);
+        assert_eq!(state.remaining.len(), 1);
+        let lines = render(&mut app, 80, 20);
+        assert!(lines.iter().any(|l| l.contains("Cherry-pick Conflicts")));
+        assert!(lines.iter().any(|l| l.contains("1 more commit(s)")));
+
+        // the conflicts have to be resolved first
+        assert!(press(&mut app, &[Key::Char('c')])?);
+        wait_for_worker(&mut app);
+        assert!(matches!(app.notification, Some(Notification::Error(_))));
+        assert_eq!(app.cherry_pick, Some(state));
+
+        repo.write("a.txt", "resolved").add("a.txt");
+        assert!(press(&mut app, &[Key::Char('c')])?);
+        wait_for_worker(&mut app);
+        assert_eq!(
+            app.notification,
+            Some(Notification::Info(
+                "Cherry-picked 2 commit(s) onto master".into()
+            ))
+        );
+        assert_eq!(app.input_mode, InputMode::Search);
+        assert_eq!(app.cherry_pick, None);
+        let head = repo.repo.head()?.peel_to_commit()?;
+        assert_eq!(head.summary(), Some("add b"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_exit_after_command() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        let mut app = App::new(repo.path())?;
+        app.config.exit_after_command = true;
+
+        assert!(type_str(&mut app, "develop")?);
+        assert!(!press(&mut app, &[Key::Ctrl('o'), Key::Char('y')])?);
+        assert_eq!(app.repo.current_branch()?, Some("develop".into()));
+        assert_eq!(
+            app.notification,
+            Some(Notification::Info("Switched to branch 'develop'".into()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_exit_after_command_on_the_worker() -> anyhow::Result<()> {
+        let repo = TempRepo::new();
+        repo.commit_file("a.txt", "a", "Add a")
+            .branch("feature")
+            .checkout("feature")
+            .commit_file("b.txt", "b", "Add b")
+            .checkout("master")
+            .commit_file("c.txt", "c", "Add c");
+        let mut app = App::new(repo.path())?;
+        app.config.exit_after_command = true;
+        wait_for_worker(&mut app);
+
+        assert!(type_str(&mut app, "feature")?);
+        assert!(press(&mut app, &[Key::Ctrl('r')])?);
+        assert!(type_str(&mut app, "master")?);
+        // the app waits for the rebase before exiting
+        assert!(press(&mut app, &[Key::Char('\n'), Key::Char('y')])?);
+        assert!(!app.is_done());
+        wait_for_worker(&mut app);
+        assert!(app.is_done());
+        assert_eq!(
+            app.notification,
+            Some(Notification::Info(
+                "Rebased feature onto master (1 commit(s))".into()
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_quit() -> anyhow::Result<()> {
+        let repo = TempRepo::new();
+        let mut app = App::new(repo.path())?;
+
+        assert!(press(&mut app, &[Key::Alt('h'), Key::Esc])?);
+        assert!(!press(&mut app, &[Key::Esc])?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pick_needs_a_branch() -> anyhow::Result<()> {
+        let repo = repo_with_branches();
+        let mut app = App::new(repo.path())?;
+        app.picking = true;
+
+        assert!(press(&mut app, &[Key::Ctrl('o')])?);
+        assert!(!press(&mut app, &[Key::Down, Key::Ctrl('o')])?);
+        assert_eq!(app.picked, vec!["develop".to_owned()]);
+        Ok(())
     }
 }
@@ -1,5 +1,11 @@
+use anyhow::anyhow;
 use clap::Args;
 
+use crate::{
+    git::{self, graph, LogFilter, Repository},
+    issue::BranchTemplate,
+};
+
 #[derive(Debug, Clone, PartialEq, Args)]
 pub struct StartBranchOpts {
     /// f: feature, h: hotfix, s: spark, etc...
@@ -8,17 +14,158 @@ pub struct StartBranchOpts {
     issue_number: String,
     /// description of branch.
     description: String,
+    /// Note on what the branch is for, stored as its description (`branch.<name>.description`).
+    #[arg(short = 'n', long = "note")]
+    pub note: Option<String>,
 }
 
 impl StartBranchOpts {
-    pub fn branch_name(&self) -> String {
-        format!(
-            "{}/{}-{}",
-            self.branch_type, self.issue_number, self.description
+    pub fn branch_name(&self, template: &BranchTemplate) -> String {
+        template.format(
+            &self.branch_type.to_string(),
+            &self.issue_number,
+            &self.description,
         )
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Args)]
+pub struct PickOpts {
+    /// Separate branch names with NUL instead of newline.
+    #[arg(short = 'z', long = "null")]
+    null: bool,
+}
+
+impl PickOpts {
+    pub fn format(&self, branches: &[String]) -> String {
+        let separator = if self.null { '\0' } else { '\n' };
+        branches
+            .iter()
+            .map(|branch| format!("{}{}", branch, separator))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Args)]
+pub struct FetchOpts {
+    /// Remote to fetch. All remotes are fetched if omitted.
+    pub remote: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Args)]
+pub struct SyncOpts {
+    /// Fetch and prune all remotes first.
+    #[arg(short = 'f', long = "fetch")]
+    pub fetch: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Args)]
+pub struct AmendOpts {
+    /// New message of the commit. The message of HEAD is kept if omitted.
+    #[arg(short = 'm', long = "message")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Args)]
+pub struct BackOpts {
+    /// Which of the branches checked out before to switch to, 1 being the last one.
+    /// Unlike `@{-n}`, each branch counts once, and the current and deleted ones are skipped.
+    #[arg(default_value_t = 1)]
+    pub n: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Args)]
+pub struct LogOpts {
+    /// Branch to show. The current branch if omitted.
+    #[arg(short = 'b', long = "branch")]
+    pub branch: Option<String>,
+    /// Show at most this many commits.
+    #[arg(short = 'n', long = "max-count", default_value_t = 40)]
+    pub max_count: usize,
+    /// Commits whose author name or email contains this.
+    #[arg(long = "author")]
+    author: Option<String>,
+    /// Commits whose committer name or email contains this.
+    #[arg(long = "committer")]
+    committer: Option<String>,
+    /// Commits after a date like 2024-01-31, yesterday or 2.weeks.
+    #[arg(long = "since")]
+    since: Option<String>,
+    /// Commits before a date like 2024-01-31, yesterday or 2.weeks.
+    #[arg(long = "until")]
+    until: Option<String>,
+    /// Commits whose message contains this.
+    #[arg(long = "grep")]
+    grep: Option<String>,
+    /// Follow only the first parent of merge commits.
+    #[arg(long = "first-parent")]
+    first_parent: bool,
+    /// Leave out merge commits.
+    #[arg(long = "no-merges")]
+    no_merges: bool,
+    /// Commits changing this file or directory.
+    #[arg(last = true)]
+    path: Option<String>,
+}
+
+impl LogOpts {
+    pub fn filter(&self) -> anyhow::Result<LogFilter> {
+        let now = chrono::Local::now();
+        let date = |date: &Option<String>| {
+            date.as_deref()
+                .map(|date| git::parse_date(date, now))
+                .transpose()
+        };
+        Ok(LogFilter {
+            author: self.author.clone(),
+            committer: self.committer.clone(),
+            since: date(&self.since)?,
+            until: date(&self.until)?,
+            path: self.path.clone(),
+            grep: self.grep.clone(),
+            first_parent: self.first_parent,
+            no_merges: self.no_merges,
+        })
+    }
+
+    /// The lines of `git ex log`: the graph, id, refs and summary of each commit.
+    /// The graph is left out when the filter hides commits, as its lines would break.
+    pub fn lines(&self, repo: &Repository) -> anyhow::Result<Vec<String>> {
+        let branch = match self.branch.clone() {
+            Some(branch) => branch,
+            None => repo
+                .current_branch()?
+                .ok_or_else(|| anyhow!("HEAD is not on a branch."))?,
+        };
+        let filter = self.filter()?;
+        let commits = repo.logs(&branch, self.max_count, &filter)?;
+        let lanes = if filter.hides_commits() {
+            vec![String::new(); commits.len()]
+        } else {
+            graph::graph(&commits)
+        };
+        Ok(commits
+            .iter()
+            .zip(lanes)
+            .map(|(commit, lanes)| {
+                let mut line = lanes;
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(&commit.id);
+                if !commit.refs.is_empty() {
+                    line.push_str(&format!(" ({})", commit.refs.join(", ")));
+                }
+                format!(
+                    "{} {}",
+                    line,
+                    commit.message.lines().next().unwrap_or_default()
+                )
+            })
+            .collect())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum BranchType {
     Feature,
@@ -62,6 +209,8 @@ impl std::fmt::Display for BranchType {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::TempRepo;
+    use clap::Parser;
     use std::str::FromStr;
 
     #[test]
@@ -91,37 +240,107 @@ mod tests {
 
     #[test]
     fn test_branch_opts_to_branch_name() -> anyhow::Result<()> {
+        let template = BranchTemplate::default();
         let opts = StartBranchOpts {
             branch_type: BranchType::Feature,
             issue_number: "number".into(),
             description: "test-desc".into(),
+            note: None,
         };
 
-        assert_eq!(opts.branch_name(), "feature/number-test-desc");
+        assert_eq!(opts.branch_name(&template), "feature/number-test-desc");
 
         let opts = StartBranchOpts {
             branch_type: BranchType::Hotfix,
             issue_number: "number".into(),
             description: "test-desc".into(),
+            note: None,
         };
 
-        assert_eq!(opts.branch_name(), "hotfix/number-test-desc");
+        assert_eq!(opts.branch_name(&template), "hotfix/number-test-desc");
 
         let opts = StartBranchOpts {
             branch_type: BranchType::Spark,
             issue_number: "number".into(),
             description: "test-desc".into(),
+            note: None,
         };
 
-        assert_eq!(opts.branch_name(), "spark/number-test-desc");
+        assert_eq!(opts.branch_name(&template), "spark/number-test-desc");
 
         let opts = StartBranchOpts {
             branch_type: BranchType::Other("hoge".into()),
             issue_number: "number".into(),
             description: "test-desc".into(),
+            note: None,
         };
 
-        assert_eq!(opts.branch_name(), "hoge/number-test-desc");
+        assert_eq!(opts.branch_name(&template), "hoge/number-test-desc");
+
+        let template = BranchTemplate::new("{issue}_{description}");
+        assert_eq!(opts.branch_name(&template), "number_test-desc");
+        Ok(())
+    }
+
+    #[test]
+    fn test_pick_opts_format() {
+        let branches = vec!["main".to_owned(), "feature/1-x".to_owned()];
+
+        let opts = PickOpts { null: false };
+        assert_eq!(opts.format(&branches), "main\nfeature/1-x\n");
+
+        let opts = PickOpts { null: true };
+        assert_eq!(opts.format(&branches), "main\0feature/1-x\0");
+    }
+
+    #[derive(clap::Parser)]
+    struct LogCommand {
+        #[command(flatten)]
+        opts: LogOpts,
+    }
+
+    #[test]
+    fn test_log_opts_lines() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("develop")
+            .checkout("develop")
+            .commit_file("a.txt", "a", "add a\n\nwith a body")
+            .checkout("master")
+            .commit_file("b.txt", "b", "add b");
+        temp.merge("develop", "merge develop");
+        let repo = Repository::new(temp.path())?;
+        let opts = |args: &[&str]| {
+            LogCommand::parse_from(std::iter::once("log").chain(args.iter().copied())).opts
+        };
+        let strip_ids = |lines: Vec<String>| {
+            lines
+                .iter()
+                .map(|line| {
+                    line.split_whitespace()
+                        .filter(|word| {
+                            word.len() < 7 || !word.chars().all(|c| c.is_ascii_hexdigit())
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            strip_ids(opts(&[]).lines(&repo)?),
+            vec![
+                "●─╮ (HEAD -> master) merge develop",
+                "● │ add b",
+                "│ ● (develop) add a",
+                "●─╯ initial commit",
+            ]
+        );
+        // without the graph when commits are left out
+        assert_eq!(
+            strip_ids(opts(&["-b", "develop", "--grep", "add"]).lines(&repo)?),
+            vec!["(develop) add a"]
+        );
+        assert!(opts(&["-b", "nope"]).lines(&repo).is_err());
         Ok(())
     }
 }
@@ -1,17 +1,41 @@
 pub mod branch_list;
 pub mod checkout;
-pub mod delete_branch;
+pub mod cherry_pick;
+pub mod commit_message;
+pub mod compare;
+pub mod confirm;
 pub mod default_help;
+pub mod delete_branch;
+pub mod description;
 pub mod help;
 pub mod logs;
+pub mod merge;
+pub mod push;
+pub mod rebase;
+pub mod recent;
+pub mod reflog;
 pub mod search_input;
 pub mod selected_list;
+pub mod status_bar;
+pub mod working_tree;
 
 pub use branch_list::BranchList;
 pub use checkout::CheckoutConfirmation;
-pub use delete_branch::DeleteBranchConfirmation;
+pub use cherry_pick::{CherryPickConfirmation, CherryPickConflicts};
+pub use commit_message::CommitMessage;
+pub use compare::{Compare, Diff};
+pub use confirm::ConfirmButtons;
 pub use default_help::DefaultHelp;
+pub use delete_branch::DeleteBranchConfirmation;
+pub use description::Description;
 pub use help::Help;
 pub use logs::Logs;
+pub use merge::{MergeConfirmation, MergeConflicts};
+pub use push::PushConfirmation;
+pub use rebase::{RebaseConfirmation, RebaseConflicts};
+pub use recent::RecentBranches;
+pub use reflog::Reflog;
 pub use search_input::SearchInput;
 pub use selected_list::SelectedList;
+pub use status_bar::StatusBar;
+pub use working_tree::{Hunks, WorkingTree, WorkingTreeSummary};
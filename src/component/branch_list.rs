@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use tui::{
     backend::Backend,
     layout::Rect,
@@ -5,8 +7,9 @@ use tui::{
     terminal::Frame,
     widgets::{Block, Borders, List, Text},
 };
+use unicode_width::UnicodeWidthStr;
 
-use crate::util::StatefulList;
+use crate::{git::BranchStatus, issue::BranchTemplate, util::StatefulList};
 
 pub struct BranchList;
 impl BranchList {
@@ -14,15 +17,54 @@ impl BranchList {
         f: &mut Frame<B>,
         chunk: &Rect,
         branches: &mut StatefulList<String>,
-        current_branch: Option<String>,
+        current_branch: Option<&str>,
+        statuses: &HashMap<String, BranchStatus>,
+        loading: bool,
+        template: &BranchTemplate,
     ) {
-        let items = List::new(branches.items.iter().map(|x| match current_branch {
-            Some(ref current) if current == x => {
-                Text::styled(format!("⚓ {}", x), Style::default().fg(Color::LightCyan))
+        branches.update_offset(chunk.height.saturating_sub(2) as usize);
+        let issues = branches
+            .items
+            .iter()
+            .map(|x| template.issue(x).map(|issue| Self::issue_label(&issue)))
+            .collect::<Vec<_>>();
+        // the issue column is left out when no branch has an issue
+        let issue_width = issues
+            .iter()
+            .flatten()
+            .map(|issue| issue.width() + 1)
+            .max()
+            .unwrap_or(0);
+        let items = List::new(branches.items.iter().zip(issues).map(|(x, issue)| {
+            let status = statuses.get(x);
+            let issue = format!(
+                "{:<width$}",
+                issue.map(|issue| issue + " ").unwrap_or_default(),
+                width = issue_width
+            );
+            match current_branch {
+                Some(current) if current == x => Text::styled(
+                    format!("{}⚓ {}{}", issue, x, Self::status_label(status, true)),
+                    Style::default().fg(Color::LightCyan),
+                ),
+                // deleted on the remote, so probably safe to delete here too
+                _ if status.map(|s| s.upstream_gone).unwrap_or(false) => Text::styled(
+                    format!("{}{}{}", issue, x, Self::status_label(status, false)),
+                    Style::default().fg(Color::LightRed),
+                ),
+                _ => Text::raw(format!(
+                    "{}{}{}",
+                    issue,
+                    x,
+                    Self::status_label(status, false)
+                )),
             }
-            _ => Text::raw(x),
         }))
-        .block(Block::default().borders(Borders::ALL).title("Branches"))
+        .block(Block::default().borders(Borders::ALL).title(if loading {
+            "Branches (loading…)"
+        } else {
+            "Branches"
+        }))
         .style(Style::default().fg(Color::Yellow))
         .highlight_style(
             Style::default()
@@ -32,4 +74,35 @@ impl BranchList {
         .highlight_symbol("➢ ");
         f.render_stateful_widget(items, *chunk, &mut branches.state);
     }
+
+    /// `#12` for issue numbers, and keys like `PROJ-12` as they are.
+    fn issue_label(issue: &str) -> String {
+        if issue.chars().all(|c| c.is_ascii_digit()) {
+            format!("#{}", issue)
+        } else {
+            issue.to_owned()
+        }
+    }
+
+    /// e.g. ` ↑1 ↓2 merged` or ` gone merged`
+    fn status_label(status: Option<&BranchStatus>, current: bool) -> String {
+        let mut label = String::new();
+        if let Some(status) = status {
+            if let Some((ahead, behind)) = status.upstream {
+                if ahead > 0 {
+                    label.push_str(&format!(" ↑{}", ahead));
+                }
+                if behind > 0 {
+                    label.push_str(&format!(" ↓{}", behind));
+                }
+            }
+            if status.upstream_gone {
+                label.push_str(" gone");
+            }
+            if status.merged && !current {
+                label.push_str(" merged");
+            }
+        }
+        label
+    }
 }
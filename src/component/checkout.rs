@@ -6,25 +6,65 @@ use tui::{
     widgets::{Block, BorderType, Borders, Clear, Paragraph, Text},
 };
 
-use crate::util;
+use super::confirm::{self, ConfirmButtons};
+use super::WorkingTree;
+use crate::{
+    git::{ReflogEntry, WorkingTreeStatus},
+    util,
+};
 
 pub struct CheckoutConfirmation;
 impl CheckoutConfirmation {
-    pub fn render<B: Backend>(f: &mut Frame<B>, branch_name: &str) {
-        let text = [
+    pub fn render<B: Backend>(
+        f: &mut Frame<B>,
+        branch_name: &str,
+        working_tree: Option<&WorkingTreeStatus>,
+    ) -> ConfirmButtons {
+        let text = vec![
             Text::raw("Would you like to checkout "),
             Text::styled(branch_name.to_owned(), Style::default().fg(Color::Green)),
             Text::raw(" ?"),
-            Text::raw("\n\n"),
-            Text::raw("Enter: "),
-            Text::styled("y", Style::default().fg(Color::Green)),
-            Text::raw(" or "),
-            Text::styled("n", Style::default().fg(Color::LightMagenta)),
         ];
+        Self::render_popup(f, "Checkout Branch", text, 1, working_tree)
+    }
+
+    /// Same as `render`, but for a reflog entry, which detaches HEAD.
+    pub fn render_detached<B: Backend>(
+        f: &mut Frame<B>,
+        entry: &ReflogEntry,
+        working_tree: Option<&WorkingTreeStatus>,
+    ) -> ConfirmButtons {
+        let text = vec![
+            Text::raw("Would you like to checkout "),
+            Text::styled(&entry.selector, Style::default().fg(Color::Magenta)),
+            Text::raw(" ("),
+            Text::styled(&entry.id, Style::default().fg(Color::Yellow)),
+            Text::raw(") ?\n"),
+            Text::styled("HEAD will be detached.", Style::default().fg(Color::Yellow)),
+        ];
+        Self::render_popup(f, "Checkout Reflog Entry", text, 2, working_tree)
+    }
+
+    /// `lines` is how many lines `text` takes, before the warning about the working tree.
+    fn render_popup<B: Backend>(
+        f: &mut Frame<B>,
+        title: &str,
+        mut text: Vec<Text>,
+        lines: u16,
+        working_tree: Option<&WorkingTreeStatus>,
+    ) -> ConfirmButtons {
+        let warning = WorkingTree::dirty_warning(working_tree);
+        if let Some(warning) = &warning {
+            text.push(Text::raw("\n\n"));
+            text.push(Text::styled(
+                warning.clone(),
+                Style::default().fg(Color::Red),
+            ));
+        }
         let paragraph = Paragraph::new(text.iter())
             .block(
                 Block::default()
-                    .title("Checkout Branch")
+                    .title(title)
                     .borders(Borders::ALL)
                     .border_type(BorderType::Double)
                     .border_style(Style::default().fg(Color::Cyan)),
@@ -32,9 +72,11 @@ impl CheckoutConfirmation {
             .alignment(Alignment::Left)
             .wrap(true);
 
-        let area = util::centered_fix_rect(100, 5, f.size());
+        let height = lines + if warning.is_some() { 6 } else { 4 };
+        let area = util::centered_fix_rect(100, height, f.size());
 
         f.render_widget(Clear, area); //this clears out the background
         f.render_widget(paragraph, area);
+        confirm::render_buttons(f, area)
     }
 }
@@ -0,0 +1,103 @@
+use tui::{
+    backend::Backend,
+    layout::Alignment,
+    style::{Color, Style},
+    terminal::Frame,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Text},
+};
+
+use super::confirm::{self, ConfirmButtons};
+use crate::{
+    git::{CherryPickState, Commit},
+    util,
+};
+
+pub struct CherryPickConfirmation;
+impl CherryPickConfirmation {
+    /// `commits` are listed in the order they are applied.
+    pub fn render<B: Backend>(
+        f: &mut Frame<B>,
+        commits: &[&Commit],
+        onto: Option<&str>,
+    ) -> ConfirmButtons {
+        let mut text = vec![
+            Text::raw("Would you like to cherry-pick onto "),
+            Text::styled(
+                onto.unwrap_or("HEAD").to_owned(),
+                Style::default().fg(Color::Green),
+            ),
+            Text::raw(" ?\n"),
+        ];
+        commits.iter().for_each(|commit| {
+            text.push(Text::raw("--> "));
+            text.push(Text::styled(&commit.id, Style::default().fg(Color::Yellow)));
+            text.push(Text::raw(" "));
+            text.push(Text::raw(commit.message.lines().next().unwrap_or_default()));
+            text.push(Text::raw("\n"));
+        });
+
+        let paragraph = Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .title("Cherry-pick")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .alignment(Alignment::Left)
+            .wrap(true);
+
+        let area = util::centered_fix_rect(100, 30, f.size());
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(paragraph, area);
+        confirm::render_buttons(f, area)
+    }
+}
+
+/// Shows where a cherry-pick stopped and the files to resolve before continuing.
+pub struct CherryPickConflicts;
+impl CherryPickConflicts {
+    pub fn render<B: Backend>(f: &mut Frame<B>, state: &CherryPickState) {
+        let mut text = vec![
+            Text::raw("The cherry-pick stopped at:\n"),
+            Text::styled(&state.commit, Style::default().fg(Color::Yellow)),
+            Text::raw(format!(
+                "\n{} more commit(s) to apply after it.\n\n",
+                state.remaining.len()
+            )),
+        ];
+        if state.conflicts.is_empty() {
+            text.push(Text::raw("All conflicts are resolved.\n"));
+        } else {
+            text.push(Text::styled(
+                "conflicted files:\n",
+                Style::default().fg(Color::Yellow),
+            ));
+            state.conflicts.iter().for_each(|path| {
+                text.push(Text::raw("--> "));
+                text.push(Text::styled(path, Style::default().fg(Color::Red)));
+                text.push(Text::raw("\n"));
+            });
+        }
+        text.push(Text::raw(
+            "\nResolve and stage the files, then c: continue, a: abort",
+        ));
+
+        let paragraph = Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .title("Cherry-pick Conflicts")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double)
+                    .border_style(Style::default().fg(Color::Red)),
+            )
+            .alignment(Alignment::Left)
+            .wrap(true);
+
+        let area = util::centered_rect(80, 70, f.size());
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(paragraph, area);
+    }
+}
@@ -0,0 +1,48 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    terminal::Frame,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Text},
+};
+
+use crate::util;
+
+/// A message typed over several lines: of a new or reworded commit, or a branch description.
+pub struct CommitMessage;
+impl CommitMessage {
+    pub fn render<B: Backend>(
+        f: &mut Frame<B>,
+        title: &str,
+        message: &str,
+        warning: Option<&str>,
+    ) -> Rect {
+        let text = [Text::styled(message, Style::default().fg(Color::Yellow))];
+        let paragraph = Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .alignment(Alignment::Left);
+
+        let area = util::centered_fix_rect(80, 12, f.size());
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(paragraph, area);
+        if let Some(warning) = warning {
+            // on the last line, where confirmations have their buttons
+            let line = Rect::new(
+                area.x + 1,
+                area.bottom().saturating_sub(2),
+                area.width.saturating_sub(2),
+                1,
+            );
+            let text = [Text::styled(warning, Style::default().fg(Color::Red))];
+            f.render_widget(Paragraph::new(text.iter()), line);
+        }
+        area
+    }
+}
@@ -0,0 +1,139 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    terminal::Frame,
+    widgets::{Block, Borders, Clear, Paragraph, Text},
+};
+
+use crate::{
+    git::{Commit, Comparison},
+    util,
+};
+
+pub struct Compare;
+impl Compare {
+    /// Shows `Loading…` until the comparison of `a` and `b` arrives.
+    pub fn render<B: Backend>(
+        f: &mut Frame<B>,
+        a: &str,
+        b: &str,
+        comparison: Option<&Comparison>,
+        scroll: u16,
+    ) -> Rect {
+        let mut text = Vec::new();
+        match comparison {
+            None => text.push(Text::raw("Loading…")),
+            Some(comparison) => {
+                text.push(Text::raw("merge base: "));
+                match &comparison.merge_base {
+                    Some(base) => Self::push_commit(&mut text, base),
+                    None => text.push(Text::styled(
+                        "none, the branches share no history\n",
+                        Style::default().fg(Color::Red),
+                    )),
+                }
+                Self::push_side(
+                    &mut text,
+                    &comparison.a,
+                    comparison.count_a,
+                    &comparison.only_in_a,
+                );
+                Self::push_side(
+                    &mut text,
+                    &comparison.b,
+                    comparison.count_b,
+                    &comparison.only_in_b,
+                );
+            }
+        }
+
+        let title = format!("Compare {} and {}", a, b);
+        let paragraph = Paragraph::new(text.iter())
+            .block(Block::default().title(&title).borders(Borders::ALL))
+            .alignment(Alignment::Left)
+            .scroll(scroll);
+
+        let area = util::centered_rect(80, 70, f.size());
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(paragraph, area);
+        area
+    }
+
+    fn push_side<'a>(
+        text: &mut Vec<Text<'a>>,
+        branch: &'a str,
+        count: usize,
+        commits: &'a [Commit],
+    ) {
+        text.push(Text::raw("\nonly in "));
+        text.push(Text::styled(branch, Style::default().fg(Color::Green)));
+        text.push(Text::raw(format!(" ({}):\n", count)));
+        commits.iter().for_each(|commit| {
+            text.push(Text::raw("  "));
+            Self::push_commit(text, commit);
+        });
+        if commits.len() < count {
+            text.push(Text::styled(
+                format!("  … {} more\n", count - commits.len()),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+    }
+
+    fn push_commit<'a>(text: &mut Vec<Text<'a>>, commit: &'a Commit) {
+        text.push(Text::styled(&commit.id, Style::default().fg(Color::Yellow)));
+        text.push(Text::raw(" "));
+        text.push(Text::raw(commit.message.lines().next().unwrap_or_default()));
+        text.push(Text::raw("\n"));
+    }
+}
+
+/// A patch with added lines in green and removed lines in red.
+pub struct Diff;
+impl Diff {
+    pub fn render<B: Backend>(
+        f: &mut Frame<B>,
+        title: &str,
+        lines: Option<&[String]>,
+        scroll: u16,
+    ) -> Rect {
+        let text = match lines {
+            None => vec![Text::raw("Loading…")],
+            Some(lines) => lines
+                .iter()
+                .flat_map(|line| {
+                    let color = if line.starts_with("+++") || line.starts_with("---") {
+                        Color::White
+                    } else if line.starts_with('+') {
+                        Color::Green
+                    } else if line.starts_with('-') {
+                        Color::Red
+                    } else if line.starts_with("@@") {
+                        Color::Cyan
+                    } else if line.starts_with(' ') {
+                        Color::Reset
+                    } else {
+                        Color::Yellow
+                    };
+                    vec![
+                        Text::styled(line, Style::default().fg(color)),
+                        Text::raw("\n"),
+                    ]
+                })
+                .collect::<Vec<_>>(),
+        };
+
+        let paragraph = Paragraph::new(text.iter())
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .alignment(Alignment::Left)
+            .scroll(scroll);
+
+        let area = util::centered_rect(90, 80, f.size());
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(paragraph, area);
+        area
+    }
+}
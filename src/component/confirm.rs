@@ -0,0 +1,41 @@
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Style},
+    terminal::Frame,
+    widgets::{Paragraph, Text},
+};
+
+const ANSWER_PREFIX: &str = "Enter: ";
+
+/// Where the `y` / `n` answers of a confirmation popup were drawn, so they can be clicked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfirmButtons {
+    pub yes: Rect,
+    pub no: Rect,
+}
+
+/// Renders `Enter: y or n` on the last line inside the popup `area`.
+pub fn render_buttons<B: Backend>(f: &mut Frame<B>, area: Rect) -> ConfirmButtons {
+    let line = Rect::new(
+        area.x + 1,
+        area.bottom().saturating_sub(2),
+        area.width.saturating_sub(2),
+        1,
+    );
+    let text = [
+        Text::raw(ANSWER_PREFIX),
+        Text::styled("y", Style::default().fg(Color::Green)),
+        Text::raw(" or "),
+        Text::styled("n", Style::default().fg(Color::LightMagenta)),
+    ];
+    f.render_widget(Paragraph::new(text.iter()), line);
+
+    // the space on each side of a letter is clickable too
+    let yes_x = line.x + ANSWER_PREFIX.len() as u16;
+    let no_x = yes_x + " or ".len() as u16 + 1;
+    ConfirmButtons {
+        yes: Rect::new(yes_x - 1, line.y, 3, 1),
+        no: Rect::new(no_x - 1, line.y, 3, 1),
+    }
+}
@@ -6,18 +6,65 @@ use tui::{
     widgets::{Paragraph, Text},
 };
 
-use crate::app::InputMode;
+use crate::app::{Command, InputMode};
 
 const HELP_COMMAND: &str = "Press q or Ctrl+c to exit, e to start search mode.";
 const HELP_SEARCH: &str = "Press Esc or Ctrl+c to exit, Enter to record the message. (Help: Alt+h)";
+const HELP_TARGET: &str = "Search for the target branch and press Enter, Esc to cancel.";
+const HELP_REBASE: &str = "Press c to continue, s to skip, a to abort, Esc to go back to Search.";
+const HELP_MERGE: &str = "Press Tab to change the strategy, Enter to merge, Esc to cancel.";
+const HELP_MERGE_CONFLICTS: &str = "Press c to commit, a to abort, Esc to go back to Search.";
+const HELP_CHERRY_PICK_CONFLICTS: &str =
+    "Press c to continue, a to abort, Esc to go back to Search.";
+const HELP_LOG: &str =
+    "Press Space to mark commits, c to cherry-pick them onto the current branch, r to reword, o to open the issue, / to filter, Esc to go back.";
+const HELP_LOG_FILTER: &str =
+    "author: committer: since: until: path: grep: first-parent no-merges. Press Enter to apply, Esc to cancel.";
+const HELP_COMPARE: &str = "Press j/k to scroll, d to show the diff, Esc to go back to Search.";
+const HELP_DIFF: &str = "Press j/k to scroll, Esc to go back to the comparison.";
+const HELP_DESCRIPTION: &str =
+    "Type the description, Enter for a new line, Ctrl+s to save it (an empty one is removed), Esc to cancel.";
+const HELP_STATUS: &str =
+    "Press Space to stage or unstage, Enter for hunks, c to commit, r to refresh, Esc to go back.";
+const HELP_HUNKS: &str =
+    "Press j/k to choose a hunk, Space to stage or unstage it, Esc to go back.";
+const HELP_COMMIT: &str =
+    "Type the message, Enter for a new line, Ctrl+s to commit, Esc to cancel.";
+const HELP_REWORD: &str =
+    "Edit the message, Enter for a new line, Ctrl+s to reword the commit, Esc to cancel.";
+const HELP_REFLOG: &str =
+    "Press Enter to check out the entry, b to create a branch at it, Tab for the reflog of the branch, Esc to go back.";
+const HELP_REFLOG_BRANCH: &str =
+    "Type the name of the new branch, Enter to create it, Esc to cancel.";
+const HELP_REFLOG_CHECKOUT: &str =
+    "Press y or Enter to check out the entry, n or Esc to go back to the reflog.";
+const HELP_RECENT: &str = "Press the number of a branch to check it out, Esc to go back.";
 const HELP_OTHER: &str = "Press Esc or q or Ctrl+c or Enter back to Search";
 
 pub struct DefaultHelp;
 impl DefaultHelp {
     pub fn render<B: Backend>(f: &mut Frame<B>, chunk: &Rect, mode: &InputMode) {
         let msg = match mode {
+            InputMode::Command(Command::Merge) => HELP_MERGE,
             InputMode::Command(_) => HELP_COMMAND,
             InputMode::Search => HELP_SEARCH,
+            InputMode::SelectTarget(_) => HELP_TARGET,
+            InputMode::Rebase => HELP_REBASE,
+            InputMode::MergeConflicts => HELP_MERGE_CONFLICTS,
+            InputMode::CherryPickConflicts => HELP_CHERRY_PICK_CONFLICTS,
+            InputMode::ShowLog => HELP_LOG,
+            InputMode::LogFilter => HELP_LOG_FILTER,
+            InputMode::Compare => HELP_COMPARE,
+            InputMode::Diff => HELP_DIFF,
+            InputMode::EditDescription => HELP_DESCRIPTION,
+            InputMode::Status => HELP_STATUS,
+            InputMode::Hunks => HELP_HUNKS,
+            InputMode::CommitMessage => HELP_COMMIT,
+            InputMode::Reword => HELP_REWORD,
+            InputMode::Reflog => HELP_REFLOG,
+            InputMode::ReflogBranch => HELP_REFLOG_BRANCH,
+            InputMode::ReflogCheckout => HELP_REFLOG_CHECKOUT,
+            InputMode::Recent => HELP_RECENT,
             _ => HELP_OTHER,
         };
 
@@ -38,7 +85,7 @@ mod tests {
         Terminal,
     };
 
-    use crate::app::Command;
+    use crate::app::Target;
 
     macro_rules! assert_render {
         ($mode:expr, $message:expr) => {
@@ -82,8 +129,47 @@ mod tests {
         assert_render!(InputMode::Command(Command::DeleteBranch), HELP_COMMAND);
     }
     #[test]
+    fn test_render_select_target() {
+        assert_render!(InputMode::SelectTarget(Target::Rebase), HELP_TARGET);
+    }
+    #[test]
+    fn test_render_rebase() {
+        assert_render!(InputMode::Rebase, HELP_REBASE);
+    }
+    #[test]
+    fn test_render_merge() {
+        assert_render!(InputMode::Command(Command::Merge), HELP_MERGE);
+        assert_render!(InputMode::MergeConflicts, HELP_MERGE_CONFLICTS);
+        assert_render!(InputMode::CherryPickConflicts, HELP_CHERRY_PICK_CONFLICTS);
+    }
+    #[test]
+    fn test_render_log() {
+        assert_render!(InputMode::ShowLog, HELP_LOG);
+        assert_render!(InputMode::LogFilter, HELP_LOG_FILTER);
+        assert_render!(InputMode::Reword, HELP_REWORD);
+        assert_render!(InputMode::Reflog, HELP_REFLOG);
+        assert_render!(InputMode::ReflogBranch, HELP_REFLOG_BRANCH);
+        assert_render!(InputMode::ReflogCheckout, HELP_REFLOG_CHECKOUT);
+        assert_render!(InputMode::Recent, HELP_RECENT);
+    }
+    #[test]
+    fn test_render_compare() {
+        assert_render!(InputMode::SelectTarget(Target::Compare), HELP_TARGET);
+        assert_render!(InputMode::Compare, HELP_COMPARE);
+        assert_render!(InputMode::Diff, HELP_DIFF);
+    }
+    #[test]
+    fn test_render_description() {
+        assert_render!(InputMode::EditDescription, HELP_DESCRIPTION);
+    }
+    #[test]
+    fn test_render_status() {
+        assert_render!(InputMode::Status, HELP_STATUS);
+        assert_render!(InputMode::Hunks, HELP_HUNKS);
+        assert_render!(InputMode::CommitMessage, HELP_COMMIT);
+    }
+    #[test]
     fn test_render_other() {
         assert_render!(InputMode::Help, HELP_OTHER);
-        assert_render!(InputMode::ShowLog, HELP_OTHER);
     }
 }
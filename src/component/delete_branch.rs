@@ -7,29 +7,67 @@ use tui::{
     widgets::{Block, BorderType, Borders, Clear, Paragraph, Text},
 };
 
-use crate::util;
+use super::confirm::{self, ConfirmButtons};
+use super::WorkingTree;
+use crate::{
+    git::{RemoteBranch, WorkingTreeStatus},
+    util,
+};
 
 pub struct DeleteBranchConfirmation;
 impl DeleteBranchConfirmation {
-    pub fn render<B: Backend>(f: &mut Frame<B>, selected: &HashSet<String>) {
+    pub fn render<B: Backend>(
+        f: &mut Frame<B>,
+        selected: &HashSet<String>,
+        remote_branches: &[RemoteBranch],
+        delete_remote: bool,
+        working_tree: Option<&WorkingTreeStatus>,
+    ) -> ConfirmButtons {
         let mut text = vec![
             Text::raw("Would you like to "),
             Text::styled("delete branches", Style::default().fg(Color::Green)),
             Text::raw(" ?"),
-            Text::raw("\n"),
-            Text::raw("Enter: "),
-            Text::styled("y", Style::default().fg(Color::Green)),
-            Text::raw(" or "),
-            Text::styled("n", Style::default().fg(Color::LightMagenta)),
             Text::raw("\n\n"),
             Text::styled("selected branches:\n", Style::default().fg(Color::Yellow)),
         ];
-        selected.iter().for_each(|branch_name| {
+        let mut selected = selected.iter().collect::<Vec<_>>();
+        selected.sort();
+        selected.into_iter().for_each(|branch_name| {
             text.push(Text::raw("--> "));
             text.push(Text::styled(branch_name, Style::default().fg(Color::Cyan)));
             text.push(Text::raw("\n"));
         });
 
+        if !remote_branches.is_empty() {
+            // dimmed while they are not going to be deleted
+            let color = if delete_remote {
+                Color::Red
+            } else {
+                Color::DarkGray
+            };
+            text.push(Text::styled(
+                "\nremote branches:\n",
+                Style::default().fg(Color::Yellow),
+            ));
+            remote_branches.iter().for_each(|remote_branch| {
+                text.push(Text::raw("--> "));
+                text.push(Text::styled(
+                    remote_branch.to_string(),
+                    Style::default().fg(color),
+                ));
+                text.push(Text::raw("\n"));
+            });
+            text.push(Text::raw(if delete_remote { "[x]" } else { "[ ]" }));
+            text.push(Text::raw(" r: also delete remote branches\n"));
+        }
+
+        if let Some(warning) = WorkingTree::dirty_warning(working_tree) {
+            text.push(Text::styled(
+                format!("\n{}\n", warning),
+                Style::default().fg(Color::Red),
+            ));
+        }
+
         let paragraph = Paragraph::new(text.iter())
             .block(
                 Block::default()
@@ -45,5 +83,6 @@ impl DeleteBranchConfirmation {
 
         f.render_widget(Clear, area); //this clears out the background
         f.render_widget(paragraph, area);
+        confirm::render_buttons(f, area)
     }
 }
@@ -0,0 +1,18 @@
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    terminal::Frame,
+    widgets::{Block, Borders, Paragraph, Text},
+};
+
+/// The description of the highlighted branch, from `branch.<name>.description`.
+pub struct Description;
+impl Description {
+    pub fn render<B: Backend>(f: &mut Frame<B>, chunk: &Rect, description: &str) {
+        let text = [Text::raw(description)];
+        let paragraph = Paragraph::new(text.iter())
+            .block(Block::default().borders(Borders::ALL).title("Description"))
+            .wrap(true);
+        f.render_widget(paragraph, *chunk);
+    }
+}
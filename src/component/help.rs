@@ -18,15 +18,65 @@ impl Help {
             Text::raw("\n"),
             // Checkout
             Text::styled("Checkout Branch", Style::default().fg(Color::Green)),
-            Text::raw(": Ctrl+o"),
+            Text::raw(": Ctrl+o (picks the branches in `git ex pick`)"),
             Text::raw("\n"),
             // Delete Branches
             Text::styled("Delete Branches", Style::default().fg(Color::Green)),
-            Text::raw(": Ctrl+d"),
+            Text::raw(": Ctrl+d (r in the confirmation also deletes their remote branches)"),
+            Text::raw("\n"),
+            // Fetch
+            Text::styled("Fetch & Prune  ", Style::default().fg(Color::Green)),
+            Text::raw(": Ctrl+f (branches whose upstream is gone turn red)"),
+            Text::raw("\n"),
+            // Push
+            Text::styled("Push Branches  ", Style::default().fg(Color::Green)),
+            Text::raw(": Ctrl+u (Alt+u to force with lease)"),
+            Text::raw("\n"),
+            // Sync
+            Text::styled("Fast-forward   ", Style::default().fg(Color::Green)),
+            Text::raw(": Ctrl+s (to the upstream, without checking out)"),
+            Text::raw("\n"),
+            // Rebase
+            Text::styled("Rebase Branch  ", Style::default().fg(Color::Green)),
+            Text::raw(": Ctrl+r, then search for the branch to rebase onto"),
+            Text::raw("\n"),
+            // Merge
+            Text::styled("Merge Branch   ", Style::default().fg(Color::Green)),
+            Text::raw(": Ctrl+g (into the current branch)"),
+            Text::raw("\n"),
+            // Description
+            Text::styled("Description    ", Style::default().fg(Color::Green)),
+            Text::raw(": Ctrl+e"),
+            Text::raw("\n"),
+            // Status
+            Text::styled("Status         ", Style::default().fg(Color::Green)),
+            Text::raw(": Alt+s (Space: stage/unstage, Enter: hunks, c: commit)"),
+            Text::raw("\n"),
+            // Recent
+            Text::styled("Recent Branches", Style::default().fg(Color::Green)),
+            Text::raw(": Alt+b (1-9: check out)"),
+            Text::raw("\n"),
+            // Reflog
+            Text::styled("Reflog         ", Style::default().fg(Color::Green)),
+            Text::raw(": Alt+r (Enter: check out, b: branch at entry, Tab: HEAD/branch)"),
+            Text::raw("\n"),
+            // Compare
+            Text::styled("Compare        ", Style::default().fg(Color::Green)),
+            Text::raw(": Alt+c (d: diff)"),
+            Text::raw("\n"),
+            // Issue
+            Text::styled("Open issue     ", Style::default().fg(Color::Green)),
+            Text::raw(": Alt+o (of the branch name)"),
             Text::raw("\n"),
             // Log
             Text::styled("Show log       ", Style::default().fg(Color::Green)),
-            Text::raw(": Ctrl+l"),
+            Text::raw(
+                ": Ctrl+l (Space: mark commits, c: cherry-pick them, r: reword, o: open issue)",
+            ),
+            Text::raw("\n"),
+            // Mouse
+            Text::styled("Mouse          ", Style::default().fg(Color::Green)),
+            Text::raw(": click to select, double-click to checkout, wheel to scroll"),
             Text::raw("\n"),
         ];
         let paragraph = Paragraph::new(text.iter())
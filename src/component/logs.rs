@@ -1,41 +1,105 @@
-use crate::util;
+use crate::{
+    git::{graph, Commit, LogFilter},
+    util,
+};
 
 use tui::{
     backend::Backend,
     layout::Alignment,
-    style::{Color, Style},
+    layout::Rect,
+    style::{Color, Modifier, Style},
     terminal::Frame,
     widgets::{Block, Borders, Clear, Paragraph, Text},
 };
 
 pub struct Logs;
 impl Logs {
-    pub fn render<B: Backend>(f: &mut Frame<B>, commits: Vec<crate::git::Commit>) {
-        let text = commits
-            .into_iter()
-            .flat_map(|log| {
-                vec![
-                    Text::styled(
-                        log.datetime.format("%Y/%m/%d %H:%M:%S").to_string(),
-                        Style::default().fg(Color::Cyan),
-                    ),
-                    Text::raw(" "),
-                    Text::styled(log.id, Style::default().fg(Color::Yellow)),
-                    Text::raw(" "),
-                    Text::raw(log.message),
+    /// `logs` is `None` while the commits are still being loaded. Each commit takes one line
+    /// with its place in the commit graph, the one at `cursor` is highlighted and the `marked`
+    /// ones are starred. The graph is left out when the filter hides commits of the history.
+    pub fn render<B: Backend>(
+        f: &mut Frame<B>,
+        logs: Option<&Result<Vec<Commit>, String>>,
+        filter: &LogFilter,
+        cursor: usize,
+        marked: &[git2::Oid],
+    ) -> Rect {
+        let graph = match logs {
+            Some(Ok(commits)) if filter.hides_commits() => vec![String::new(); commits.len()],
+            Some(Ok(commits)) => graph::graph(commits),
+            _ => Vec::new(),
+        };
+        let text = match logs {
+            None => vec![Text::raw("Loading…")],
+            Some(Err(e)) => vec![Text::styled(e, Style::default().fg(Color::Red))],
+            Some(Ok(commits)) => commits
+                .iter()
+                .zip(&graph)
+                .enumerate()
+                .flat_map(|(i, (log, lanes))| {
+                    let base = if i == cursor {
+                        Style::default().modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default()
+                    };
+                    let mut line = vec![
+                        Text::styled(
+                            if marked.contains(&log.oid) {
+                                "* "
+                            } else {
+                                "  "
+                            },
+                            base.fg(Color::Magenta),
+                        ),
+                        Text::styled(
+                            if lanes.is_empty() {
+                                String::new()
+                            } else {
+                                format!("{} ", lanes)
+                            },
+                            base.fg(Color::Blue),
+                        ),
+                        Text::styled(
+                            log.datetime.format("%Y/%m/%d %H:%M:%S").to_string(),
+                            base.fg(Color::Cyan),
+                        ),
+                        Text::styled(" ", base),
+                        Text::styled(&log.id, base.fg(Color::Yellow)),
+                        Text::styled(" ", base),
+                    ];
+                    if !log.refs.is_empty() {
+                        line.push(Text::styled(
+                            format!("({}) ", log.refs.join(", ")),
+                            base.fg(Color::Green),
+                        ));
+                    }
+                    line.push(Text::styled(
+                        log.message.lines().next().unwrap_or_default(),
+                        base,
+                    ));
+                    line.push(Text::raw("\n"));
+                    line
+                })
+                .collect::<Vec<_>>(),
+        };
 
-                ]
-            })
-            .collect::<Vec<_>>();
+        let title = if filter.is_empty() {
+            "Log".to_owned()
+        } else {
+            format!("Log ({})", filter)
+        };
+        let area = util::centered_rect(80, 70, f.size());
+        // keep the cursor in view
+        let height = area.height.saturating_sub(2) as usize;
+        let scroll = (cursor + 1).saturating_sub(height) as u16;
 
         let paragraph = Paragraph::new(text.iter())
-            .block(Block::default().title("Log").borders(Borders::ALL))
+            .block(Block::default().title(&title).borders(Borders::ALL))
             .alignment(Alignment::Left)
-            .wrap(true);
-
-        let area = util::centered_rect(80, 70, f.size());
+            .scroll(scroll);
 
         f.render_widget(Clear, area); //this clears out the background
         f.render_widget(paragraph, area);
+        area
     }
 }
@@ -0,0 +1,128 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    terminal::Frame,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Text},
+};
+
+use crate::{
+    app::MergeForm,
+    git::{MergeAnalysis, MergeStrategy},
+    util,
+};
+
+/// Keys of the merge form, which takes every other character for the message.
+const HINT: &str = "Enter: merge, Tab: strategy, Esc: cancel";
+
+pub struct MergeConfirmation;
+impl MergeConfirmation {
+    pub fn render<B: Backend>(f: &mut Frame<B>, form: &MergeForm, current_branch: Option<&str>) {
+        let mut text = vec![
+            Text::raw("Would you like to merge "),
+            Text::styled(&form.branch, Style::default().fg(Color::Green)),
+            Text::raw(" into "),
+            Text::styled(
+                current_branch.unwrap_or("HEAD").to_owned(),
+                Style::default().fg(Color::Green),
+            ),
+            Text::raw(" ?\n\n"),
+            Text::styled(
+                form.analysis.to_string(),
+                Style::default().fg(match form.analysis {
+                    MergeAnalysis::UpToDate => Color::DarkGray,
+                    MergeAnalysis::FastForward => Color::Green,
+                    MergeAnalysis::Normal => Color::Yellow,
+                }),
+            ),
+            Text::raw("\n"),
+        ];
+        if form.analysis != MergeAnalysis::UpToDate {
+            text.push(Text::raw("strategy (Tab): "));
+            for strategy in form.strategies() {
+                let style = if strategy == form.strategy {
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                text.push(Text::styled(format!(" {} ", strategy), style));
+            }
+            text.push(Text::raw("\n"));
+            if form.strategy != MergeStrategy::FastForward {
+                text.push(Text::raw("message: "));
+                text.push(Text::styled(
+                    &form.message,
+                    Style::default().fg(Color::Yellow),
+                ));
+                text.push(Text::raw("\n"));
+            }
+        }
+
+        let paragraph = Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .title("Merge Branch")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .alignment(Alignment::Left)
+            .wrap(true);
+
+        let area = util::centered_fix_rect(100, 10, f.size());
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(paragraph, area);
+        // on the last line, where confirmations have their buttons
+        let line = Rect::new(
+            area.x + 1,
+            area.bottom().saturating_sub(2),
+            area.width.saturating_sub(2),
+            1,
+        );
+        let text = [Text::styled(HINT, Style::default().fg(Color::Cyan))];
+        f.render_widget(Paragraph::new(text.iter()), line);
+    }
+}
+
+/// Shows the files to resolve before a stopped merge can be committed.
+pub struct MergeConflicts;
+impl MergeConflicts {
+    pub fn render<B: Backend>(f: &mut Frame<B>, conflicts: &[String]) {
+        let mut text = vec![Text::raw("The merge stopped.\n\n")];
+        if conflicts.is_empty() {
+            text.push(Text::raw("All conflicts are resolved.\n"));
+        } else {
+            text.push(Text::styled(
+                "conflicted files:\n",
+                Style::default().fg(Color::Yellow),
+            ));
+            conflicts.iter().for_each(|path| {
+                text.push(Text::raw("--> "));
+                text.push(Text::styled(path, Style::default().fg(Color::Red)));
+                text.push(Text::raw("\n"));
+            });
+        }
+        text.push(Text::raw(
+            "\nResolve and stage the files, then c: commit the merge, a: abort",
+        ));
+
+        let paragraph = Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .title("Merge Conflicts")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double)
+                    .border_style(Style::default().fg(Color::Red)),
+            )
+            .alignment(Alignment::Left)
+            .wrap(true);
+
+        let area = util::centered_rect(80, 70, f.size());
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(paragraph, area);
+    }
+}
@@ -0,0 +1,73 @@
+use tui::{
+    backend::Backend,
+    layout::Alignment,
+    style::{Color, Modifier, Style},
+    terminal::Frame,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Text},
+};
+
+use super::confirm::{self, ConfirmButtons};
+use crate::util;
+
+pub struct PushConfirmation;
+impl PushConfirmation {
+    pub fn render<B: Backend>(f: &mut Frame<B>, branches: &[String]) -> ConfirmButtons {
+        let mut text = vec![
+            Text::raw("Would you like to "),
+            Text::styled("push", Style::default().fg(Color::Green)),
+            Text::raw(" ?\n"),
+        ];
+        Self::push_branches(&mut text, branches);
+        Self::render_popup(f, "Push Branch", Color::Cyan, text)
+    }
+
+    /// Same as `render`, but for `--force-with-lease`, which can drop commits on the remote.
+    pub fn render_force<B: Backend>(f: &mut Frame<B>, branches: &[String]) -> ConfirmButtons {
+        let mut text = vec![
+            Text::raw("Would you like to "),
+            Text::styled(
+                "force push (with lease)",
+                Style::default().fg(Color::Red).modifier(Modifier::BOLD),
+            ),
+            Text::raw(" ?\n"),
+            Text::styled(
+                "Commits on the remote that are not here will be lost.\n",
+                Style::default().fg(Color::Yellow),
+            ),
+        ];
+        Self::push_branches(&mut text, branches);
+        Self::render_popup(f, "Force Push Branch", Color::Red, text)
+    }
+
+    fn push_branches<'a>(text: &mut Vec<Text<'a>>, branches: &'a [String]) {
+        branches.iter().for_each(|branch_name| {
+            text.push(Text::raw("--> "));
+            text.push(Text::styled(branch_name, Style::default().fg(Color::Cyan)));
+            text.push(Text::raw("\n"));
+        });
+    }
+
+    fn render_popup<B: Backend>(
+        f: &mut Frame<B>,
+        title: &str,
+        color: Color,
+        text: Vec<Text>,
+    ) -> ConfirmButtons {
+        let paragraph = Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double)
+                    .border_style(Style::default().fg(color)),
+            )
+            .alignment(Alignment::Left)
+            .wrap(true);
+
+        let area = util::centered_fix_rect(100, 30, f.size());
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(paragraph, area);
+        confirm::render_buttons(f, area)
+    }
+}
@@ -0,0 +1,87 @@
+use tui::{
+    backend::Backend,
+    layout::Alignment,
+    style::{Color, Style},
+    terminal::Frame,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Text},
+};
+
+use super::confirm::{self, ConfirmButtons};
+use crate::{git::RebaseState, util};
+
+pub struct RebaseConfirmation;
+impl RebaseConfirmation {
+    pub fn render<B: Backend>(f: &mut Frame<B>, branch_name: &str, onto: &str) -> ConfirmButtons {
+        let text = [
+            Text::raw("Would you like to rebase "),
+            Text::styled(branch_name.to_owned(), Style::default().fg(Color::Green)),
+            Text::raw(" onto "),
+            Text::styled(onto.to_owned(), Style::default().fg(Color::Green)),
+            Text::raw(" ?"),
+        ];
+        let paragraph = Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .title("Rebase Branch")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .alignment(Alignment::Left)
+            .wrap(true);
+
+        let area = util::centered_fix_rect(100, 5, f.size());
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(paragraph, area);
+        confirm::render_buttons(f, area)
+    }
+}
+
+/// Shows where a rebase stopped and the files to resolve before continuing.
+pub struct RebaseConflicts;
+impl RebaseConflicts {
+    pub fn render<B: Backend>(f: &mut Frame<B>, state: &RebaseState) {
+        let mut text = vec![
+            Text::raw("Rebasing "),
+            Text::styled(&state.branch, Style::default().fg(Color::Green)),
+            Text::raw(" onto "),
+            Text::styled(&state.onto, Style::default().fg(Color::Green)),
+            Text::raw(format!(" stopped at {}/{}:\n", state.current, state.total)),
+            Text::styled(&state.commit, Style::default().fg(Color::Yellow)),
+            Text::raw("\n\n"),
+        ];
+        if state.conflicts.is_empty() {
+            text.push(Text::raw("All conflicts are resolved.\n"));
+        } else {
+            text.push(Text::styled(
+                "conflicted files:\n",
+                Style::default().fg(Color::Yellow),
+            ));
+            state.conflicts.iter().for_each(|path| {
+                text.push(Text::raw("--> "));
+                text.push(Text::styled(path, Style::default().fg(Color::Red)));
+                text.push(Text::raw("\n"));
+            });
+        }
+        text.push(Text::raw(
+            "\nResolve and stage the files, then c: continue, s: skip this commit, a: abort",
+        ));
+
+        let paragraph = Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .title("Rebase Conflicts")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double)
+                    .border_style(Style::default().fg(Color::Red)),
+            )
+            .alignment(Alignment::Left)
+            .wrap(true);
+
+        let area = util::centered_rect(80, 70, f.size());
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(paragraph, area);
+    }
+}
@@ -0,0 +1,52 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    terminal::Frame,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Text},
+};
+
+use super::WorkingTree;
+use crate::{git::WorkingTreeStatus, util};
+
+/// The branches checked out last, numbered from 1 for the key that switches to them.
+pub struct RecentBranches;
+impl RecentBranches {
+    pub fn render<B: Backend>(
+        f: &mut Frame<B>,
+        branches: &[String],
+        working_tree: Option<&WorkingTreeStatus>,
+    ) -> Rect {
+        let mut text = Vec::new();
+        for (i, branch) in branches.iter().enumerate() {
+            text.push(Text::styled(
+                format!("{} ", i + 1),
+                Style::default().fg(Color::Yellow),
+            ));
+            text.push(Text::styled(branch, Style::default().fg(Color::Green)));
+            text.push(Text::raw("\n"));
+        }
+        let warning = WorkingTree::dirty_warning(working_tree);
+        if let Some(warning) = &warning {
+            text.push(Text::raw("\n"));
+            text.push(Text::styled(warning, Style::default().fg(Color::Red)));
+        }
+        let paragraph = Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .title("Recent Branches")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .alignment(Alignment::Left)
+            .wrap(true);
+
+        let height = branches.len() as u16 + if warning.is_some() { 4 } else { 2 };
+        let area = util::centered_fix_rect(80, height, f.size());
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(paragraph, area);
+        area
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{git::ReflogEntry, util};
+
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    terminal::Frame,
+    widgets::{Block, Borders, Clear, Paragraph, Text},
+};
+
+pub struct Reflog;
+impl Reflog {
+    /// Each entry takes one line with where the ref pointed, when and why it moved there.
+    /// The one at `cursor` is highlighted.
+    pub fn render<B: Backend>(
+        f: &mut Frame<B>,
+        name: &str,
+        entries: &[ReflogEntry],
+        cursor: usize,
+    ) -> Rect {
+        let selector_width = entries
+            .iter()
+            .map(|entry| entry.selector.len())
+            .max()
+            .unwrap_or(0);
+        let text = if entries.is_empty() {
+            vec![Text::raw("No reflog entries.")]
+        } else {
+            entries
+                .iter()
+                .enumerate()
+                .flat_map(|(i, entry)| {
+                    let base = if i == cursor {
+                        Style::default().modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default()
+                    };
+                    vec![
+                        Text::styled(
+                            format!("{:width$} ", entry.selector, width = selector_width),
+                            base.fg(Color::Magenta),
+                        ),
+                        Text::styled(&entry.id, base.fg(Color::Yellow)),
+                        Text::styled(" ", base),
+                        Text::styled(
+                            entry.datetime.format("%Y/%m/%d %H:%M:%S").to_string(),
+                            base.fg(Color::Cyan),
+                        ),
+                        Text::styled(" ", base),
+                        Text::styled(format!("{}:", entry.action), base.fg(Color::Green)),
+                        Text::styled(" ", base),
+                        Text::styled(&entry.message, base),
+                        Text::raw("\n"),
+                    ]
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let title = format!("Reflog of {}", name);
+        let area = util::centered_rect(80, 70, f.size());
+        // keep the cursor in view
+        let height = area.height.saturating_sub(2) as usize;
+        let scroll = (cursor + 1).saturating_sub(height) as u16;
+
+        let paragraph = Paragraph::new(text.iter())
+            .block(Block::default().title(&title).borders(Borders::ALL))
+            .alignment(Alignment::Left)
+            .scroll(scroll);
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(paragraph, area);
+        area
+    }
+}
@@ -8,11 +8,11 @@ use tui::{
 
 pub struct SearchInput;
 impl SearchInput {
-    pub fn render<B: Backend>(f: &mut Frame<B>, chunk: &Rect, input_str: &str) {
+    pub fn render<B: Backend>(f: &mut Frame<B>, chunk: &Rect, input_str: &str, title: &str) {
         let text = [Text::raw(input_str)];
         let input = Paragraph::new(text.iter())
             .style(Style::default().fg(Color::Yellow))
-            .block(Block::default().borders(Borders::ALL).title("Input"));
+            .block(Block::default().borders(Borders::ALL).title(title));
         f.render_widget(input, *chunk);
     }
 }
@@ -0,0 +1,22 @@
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Style},
+    terminal::Frame,
+    widgets::{Paragraph, Text},
+};
+
+use crate::app::Notification;
+
+pub struct StatusBar;
+impl StatusBar {
+    pub fn render<B: Backend>(f: &mut Frame<B>, chunk: &Rect, notification: Option<&Notification>) {
+        let text = match notification {
+            Some(Notification::Info(msg)) => [Text::styled(msg, Style::default().fg(Color::Green))],
+            Some(Notification::Error(msg)) => [Text::styled(msg, Style::default().fg(Color::Red))],
+            None => [Text::raw("")],
+        };
+        let paragraph = Paragraph::new(text.iter());
+        f.render_widget(paragraph, *chunk);
+    }
+}
@@ -0,0 +1,223 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    terminal::Frame,
+    widgets::{Block, Borders, Clear, Paragraph, Text},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    git::{Area, Hunk, WorkingTreeStatus},
+    util,
+};
+
+const STAGED_COLOR: Color = Color::Green;
+const UNSTAGED_COLOR: Color = Color::Yellow;
+const UNTRACKED_COLOR: Color = Color::DarkGray;
+const CONFLICTED_COLOR: Color = Color::Red;
+
+/// The files of the working tree, grouped like `git status` does, with one highlighted.
+pub struct WorkingTree;
+impl WorkingTree {
+    pub fn render<B: Backend>(
+        f: &mut Frame<B>,
+        status: Option<&WorkingTreeStatus>,
+        cursor: usize,
+    ) -> Rect {
+        let area = util::centered_rect(80, 70, f.size());
+        let mut text = Vec::new();
+        let mut cursor_line: usize = 0;
+        match status {
+            None => text.push(Text::raw("Loading…")),
+            Some(status) if status.is_clean() => {
+                text.push(Text::raw("nothing to commit, working tree clean"))
+            }
+            Some(status) => {
+                let files = status.files();
+                let mut line = 0;
+                for (i, file) in files.iter().enumerate() {
+                    if i == 0 || files[i - 1].area != file.area {
+                        if i > 0 {
+                            text.push(Text::raw("\n"));
+                            line += 1;
+                        }
+                        let count = files.iter().filter(|f| f.area == file.area).count();
+                        text.push(Text::styled(
+                            Self::title(file.area),
+                            Style::default().fg(Self::color(file.area)),
+                        ));
+                        text.push(Text::raw(format!(" ({}):\n", count)));
+                        line += 1;
+                    }
+                    let path = match file.old_path {
+                        Some(old_path) => format!("{} -> {}", old_path, file.path),
+                        None => file.path.to_owned(),
+                    };
+                    let style = if i == cursor {
+                        cursor_line = line;
+                        Style::default().modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default()
+                    };
+                    text.push(Text::styled(
+                        format!("  {} ", file.symbol),
+                        style.fg(Self::color(file.area)),
+                    ));
+                    text.push(Text::styled(path, style));
+                    text.push(Text::raw("\n"));
+                    line += 1;
+                }
+            }
+        }
+
+        // keep the highlighted file in view
+        let height = area.height.saturating_sub(2) as usize;
+        let scroll = (cursor_line + 1).saturating_sub(height) as u16;
+        let paragraph = Paragraph::new(text.iter())
+            .block(Block::default().title("Status").borders(Borders::ALL))
+            .alignment(Alignment::Left)
+            .scroll(scroll);
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(paragraph, area);
+        area
+    }
+
+    fn title(area: Area) -> &'static str {
+        match area {
+            Area::Conflicted => "Conflicted",
+            Area::Staged => "Staged",
+            Area::Unstaged => "Unstaged",
+            Area::Untracked => "Untracked",
+        }
+    }
+
+    fn color(area: Area) -> Color {
+        match area {
+            Area::Conflicted => CONFLICTED_COLOR,
+            Area::Staged => STAGED_COLOR,
+            Area::Unstaged => UNSTAGED_COLOR,
+            Area::Untracked => UNTRACKED_COLOR,
+        }
+    }
+
+    /// A warning for confirmations of commands that are better run on a clean tree.
+    pub fn dirty_warning(status: Option<&WorkingTreeStatus>) -> Option<String> {
+        let status = status.filter(|status| status.is_dirty())?;
+        let counts = [
+            (status.staged.len(), "staged"),
+            (status.unstaged.len(), "unstaged"),
+            (status.conflicted.len(), "conflicted"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, label)| format!("{} {}", count, label))
+        .collect::<Vec<_>>();
+        Some(format!(
+            "The working tree has uncommitted changes ({}).",
+            counts.join(", ")
+        ))
+    }
+}
+
+/// The hunks of the staged or unstaged changes of a file, with the highlighted one on top.
+pub struct Hunks;
+impl Hunks {
+    pub fn render<B: Backend>(
+        f: &mut Frame<B>,
+        title: &str,
+        hunks: &[Hunk],
+        cursor: usize,
+    ) -> Rect {
+        let mut text = Vec::new();
+        let mut cursor_line = 0;
+        for (i, hunk) in hunks.iter().enumerate() {
+            let mut style = Style::default().fg(Color::Cyan);
+            if i == cursor {
+                cursor_line = text.len() / 2;
+                style = style.modifier(Modifier::REVERSED);
+            }
+            text.push(Text::styled(&hunk.header, style));
+            text.push(Text::raw("\n"));
+            for line in &hunk.lines {
+                let color = match line.chars().next() {
+                    Some('+') => Color::Green,
+                    Some('-') => Color::Red,
+                    _ => Color::Reset,
+                };
+                text.push(Text::styled(line, Style::default().fg(color)));
+                text.push(Text::raw("\n"));
+            }
+        }
+
+        let paragraph = Paragraph::new(text.iter())
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .alignment(Alignment::Left)
+            .scroll(cursor_line as u16);
+
+        let area = util::centered_rect(90, 80, f.size());
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(paragraph, area);
+        area
+    }
+}
+
+/// The current branch and the number of changed files, e.g. `⚓ master +1 ~2 ?3`.
+pub struct WorkingTreeSummary;
+impl WorkingTreeSummary {
+    pub fn render<B: Backend>(
+        f: &mut Frame<B>,
+        chunk: &Rect,
+        current_branch: Option<&str>,
+        status: Option<&WorkingTreeStatus>,
+    ) {
+        let text = Self::text(current_branch, status);
+        let paragraph = Paragraph::new(text.iter()).alignment(Alignment::Right);
+        f.render_widget(paragraph, *chunk);
+    }
+
+    /// The columns the summary takes.
+    pub fn width(current_branch: Option<&str>, status: Option<&WorkingTreeStatus>) -> u16 {
+        Self::text(current_branch, status)
+            .iter()
+            .map(|text| match text {
+                Text::Raw(s) | Text::Styled(s, _) => s.width(),
+            })
+            .sum::<usize>() as u16
+    }
+
+    fn text<'a>(
+        current_branch: Option<&'a str>,
+        status: Option<&WorkingTreeStatus>,
+    ) -> Vec<Text<'a>> {
+        let mut text = Vec::new();
+        if let Some(branch) = current_branch {
+            text.push(Text::styled(
+                format!("⚓ {}", branch),
+                Style::default().fg(Color::LightCyan),
+            ));
+        }
+        if let Some(status) = status {
+            if status.is_clean() {
+                text.push(Text::styled(" ✔", Style::default().fg(STAGED_COLOR)));
+            }
+            [
+                ('+', status.staged.len(), STAGED_COLOR),
+                ('~', status.unstaged.len(), UNSTAGED_COLOR),
+                ('?', status.untracked.len(), UNTRACKED_COLOR),
+                ('!', status.conflicted.len(), CONFLICTED_COLOR),
+            ]
+            .iter()
+            .filter(|(_, count, _)| *count > 0)
+            .for_each(|(symbol, count, color)| {
+                text.push(Text::styled(
+                    format!(" {}{}", symbol, count),
+                    Style::default().fg(*color),
+                ));
+            });
+        }
+        text
+    }
+}
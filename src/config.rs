@@ -0,0 +1,84 @@
+use crate::{git::optional, issue::DEFAULT_BRANCH_TEMPLATE};
+
+/// Settings for git-ex, read from the `ex.*` keys of git config.
+///
+/// ```text
+/// [ex]
+///     exitAfterCommand = true
+///     branchTemplate = {type}/{issue}-{description}
+///     issueUrl = https://tracker/browse/{issue}
+///     opener = xdg-open
+///     recentBranches = 5
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Quit the TUI after a checkout or delete instead of returning to the branch list.
+    pub exit_after_command: bool,
+    /// How `git ex start` names branches, and where the issue number is found in a branch name.
+    pub branch_template: String,
+    /// The link to an issue, with `{issue}` standing for its number.
+    pub issue_url: Option<String>,
+    /// The command issue links are opened with. Without one, the link is only shown.
+    pub opener: Option<String>,
+    /// How many of the branches checked out last the recent view lists, at most 9.
+    pub recent_branches: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            exit_after_command: false,
+            branch_template: DEFAULT_BRANCH_TEMPLATE.to_owned(),
+            issue_url: None,
+            opener: None,
+            recent_branches: 5,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_git(config: &git2::Config) -> anyhow::Result<Self> {
+        let default = Self::default();
+        Ok(Self {
+            exit_after_command: optional(config.get_bool("ex.exitAfterCommand"))?
+                .unwrap_or(default.exit_after_command),
+            branch_template: optional(config.get_string("ex.branchTemplate"))?
+                .unwrap_or(default.branch_template),
+            issue_url: optional(config.get_string("ex.issueUrl"))?,
+            opener: optional(config.get_string("ex.opener"))?,
+            recent_branches: optional(config.get_i64("ex.recentBranches"))?
+                .map(|n| n.clamp(1, 9) as usize)
+                .unwrap_or(default.recent_branches),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_git() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("config");
+        std::fs::write(&path, "")?;
+        let mut config = git2::Config::open(&path)?;
+
+        assert_eq!(Config::from_git(&config)?, Config::default());
+
+        config.set_bool("ex.exitAfterCommand", true)?;
+        config.set_str("ex.branchTemplate", "{issue}_{description}")?;
+        config.set_str("ex.issueUrl", "https://tracker/browse/{issue}")?;
+        config.set_i64("ex.recentBranches", 20)?;
+        let loaded = Config::from_git(&config)?;
+        assert!(loaded.exit_after_command);
+        assert_eq!(loaded.branch_template, "{issue}_{description}");
+        assert_eq!(
+            loaded.issue_url.as_deref(),
+            Some("https://tracker/browse/{issue}")
+        );
+        assert_eq!(loaded.opener, None);
+        assert_eq!(loaded.recent_branches, 9);
+        Ok(())
+    }
+}
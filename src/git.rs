@@ -1,19 +1,242 @@
-use std::path::Path;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
+use anyhow::anyhow;
 use chrono::TimeZone;
 
+mod commit;
+mod credentials;
+pub mod graph;
+mod log_filter;
+mod reflog;
+mod status;
+
+pub use log_filter::{parse_date, LogFilter};
+pub use reflog::ReflogEntry;
+pub use status::{Area, Change, FileStatus, Hunk, StatusFile, WorkingTreeStatus};
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Signature {
     pub name: String,
     pub email: String,
 }
 
+impl std::fmt::Display for Signature {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} <{}>", self.name, self.email)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Commit {
+    pub oid: git2::Oid,
+    /// The abbreviated id, for display.
     pub id: String,
+    pub parents: Vec<git2::Oid>,
+    /// The refs pointing at the commit, like `git log --decorate` shows them.
+    pub refs: Vec<String>,
     pub author: Signature,
     pub message: String,
     pub datetime: chrono::DateTime<chrono::Local>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BranchStatus {
+    /// Commits (ahead, behind) the upstream branch, if there is one.
+    pub upstream: Option<(usize, usize)>,
+    /// Whether the branch is contained in HEAD, like `git branch --merged`.
+    pub merged: bool,
+    /// Whether the configured upstream branch no longer exists, e.g. after a prune.
+    pub upstream_gone: bool,
+}
+
+/// Progress of a transfer from a remote.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Progress {
+    pub remote: String,
+    pub received_objects: usize,
+    pub total_objects: usize,
+    pub indexed_deltas: usize,
+    pub total_deltas: usize,
+    pub received_bytes: usize,
+}
+
+impl std::fmt::Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let percent = |done: usize, total: usize| done * 100 / total.max(1);
+        if self.received_objects < self.total_objects {
+            write!(
+                f,
+                "{}: Receiving objects: {}% ({}/{}), {:.2} KiB",
+                self.remote,
+                percent(self.received_objects, self.total_objects),
+                self.received_objects,
+                self.total_objects,
+                self.received_bytes as f64 / 1024.0
+            )
+        } else if self.indexed_deltas < self.total_deltas {
+            write!(
+                f,
+                "{}: Resolving deltas: {}% ({}/{})",
+                self.remote,
+                percent(self.indexed_deltas, self.total_deltas),
+                self.indexed_deltas,
+                self.total_deltas
+            )
+        } else {
+            write!(f, "{}: Fetching…", self.remote)
+        }
+    }
+}
+
+/// What `fast_forward` did to a branch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncResult {
+    NoUpstream,
+    UpToDate,
+    /// Only has commits that are not pushed yet.
+    Ahead(usize),
+    /// Moved from the first commit to the second one.
+    FastForwarded(String, String),
+    /// Left alone, since both sides have commits of their own (ahead, behind).
+    Diverged(usize, usize),
+}
+
+impl SyncResult {
+    pub fn is_diverged(&self) -> bool {
+        matches!(self, SyncResult::Diverged(..))
+    }
+}
+
+impl std::fmt::Display for SyncResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SyncResult::NoUpstream => write!(f, "no upstream"),
+            SyncResult::UpToDate => write!(f, "up to date"),
+            SyncResult::Ahead(ahead) => write!(f, "ahead by {}", ahead),
+            SyncResult::FastForwarded(from, to) => write!(f, "fast-forwarded {}..{}", from, to),
+            SyncResult::Diverged(ahead, behind) => write!(f, "diverged (↑{} ↓{})", ahead, behind),
+        }
+    }
+}
+
+/// A rebase that stopped at a commit it could not apply cleanly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RebaseState {
+    pub branch: String,
+    pub onto: String,
+    /// 1-based position of the stopped commit among `total`.
+    pub current: usize,
+    pub total: usize,
+    /// Short id and summary of the stopped commit.
+    pub commit: String,
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RebaseStatus {
+    /// Finished, having applied this many commits. Skipped ones, and the ones whose
+    /// changes were upstream already, are not counted.
+    Done(usize),
+    Stopped(RebaseState),
+}
+
+/// What merging a branch into HEAD would take, from `git2::Repository::merge_analysis`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergeAnalysis {
+    UpToDate,
+    FastForward,
+    /// Both sides have commits of their own, so a merge commit is needed.
+    Normal,
+}
+
+impl std::fmt::Display for MergeAnalysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MergeAnalysis::UpToDate => write!(f, "Already up to date"),
+            MergeAnalysis::FastForward => write!(f, "Can be fast-forwarded"),
+            MergeAnalysis::Normal => write!(f, "Needs a merge commit"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergeStrategy {
+    FastForward,
+    /// A merge commit, even if a fast-forward is possible, like `git merge --no-ff`.
+    Commit,
+    /// A single commit with the changes of the branch, like `git merge --squash && git commit`.
+    Squash,
+}
+
+impl std::fmt::Display for MergeStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MergeStrategy::FastForward => write!(f, "fast-forward"),
+            MergeStrategy::Commit => write!(f, "merge commit"),
+            MergeStrategy::Squash => write!(f, "squash"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeOutcome {
+    UpToDate,
+    FastForwarded,
+    /// The short id of the new commit.
+    Committed(String),
+    /// Stopped with these files unresolved, to be finished with `commit_merge`.
+    Conflicts(Vec<String>),
+}
+
+/// A cherry-pick that stopped at a commit it could not apply cleanly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CherryPickState {
+    /// Short id and summary of the stopped commit.
+    pub commit: String,
+    pub conflicts: Vec<String>,
+    /// The commits after the stopped one, still to apply in this order.
+    pub remaining: Vec<git2::Oid>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CherryPickStatus {
+    /// Finished, having applied this many commits. The ones whose changes were
+    /// there already are not counted.
+    Done(usize),
+    Stopped(CherryPickState),
+}
+
+/// How two branches differ, like `git log --left-right a...b`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub a: String,
+    pub b: String,
+    /// `None` when the branches have no history in common.
+    pub merge_base: Option<Commit>,
+    /// The newest commits only reachable from `a`, at most the limit of `compare`.
+    pub only_in_a: Vec<Commit>,
+    pub only_in_b: Vec<Commit>,
+    /// The number of all the commits only reachable from `a`.
+    pub count_a: usize,
+    pub count_b: usize,
+}
+
+/// A branch on a remote repository, e.g. `origin/feature`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteBranch {
+    pub remote: String,
+    pub name: String,
+}
+
+impl std::fmt::Display for RemoteBranch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}/{}", self.remote, self.name)
+    }
+}
+
 pub struct Repository {
     repo: git2::Repository,
 }
@@ -25,10 +248,28 @@ impl Repository {
         })
     }
 
+    /// The `.git` directory (`.git/worktrees/<name>` in a linked worktree).
+    pub fn git_dir(&self) -> &Path {
+        self.repo.path()
+    }
+
+    /// The `.git` directory shared by all worktrees, holding `refs/`.
+    pub fn common_dir(&self) -> PathBuf {
+        let git_dir = self.git_dir();
+        match std::fs::read_to_string(git_dir.join("commondir")) {
+            Ok(dir) => git_dir.join(dir.trim()),
+            Err(_) => git_dir.to_owned(),
+        }
+    }
+
+    pub fn config(&self) -> anyhow::Result<crate::config::Config> {
+        crate::config::Config::from_git(&self.repo.config()?)
+    }
+
     pub fn current_branch(&self) -> anyhow::Result<Option<String>> {
         self.repo
             .head()
-            .and_then(|reference| Ok(reference.shorthand().map(ToOwned::to_owned)))
+            .map(|reference| reference.shorthand().map(ToOwned::to_owned))
             .map_err(Into::into)
     }
 
@@ -42,35 +283,962 @@ impl Repository {
             .collect()
     }
 
-    pub fn logs(&self, branch_name: &str, limit: usize) -> anyhow::Result<Vec<Commit>> {
+    /// Up to `limit` commits of the branch matching `filter`, children before their parents.
+    pub fn logs(
+        &self,
+        branch_name: &str,
+        limit: usize,
+        filter: &LogFilter,
+    ) -> anyhow::Result<Vec<Commit>> {
         let branch = self
             .repo
             .find_branch(branch_name, git2::BranchType::Local)?;
         let mut revwalk = self.repo.revwalk()?;
-        revwalk.set_sorting(git2::Sort::NONE | git2::Sort::TIME)?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
         revwalk.push_ref(branch.get().name().unwrap())?;
+        if filter.first_parent {
+            revwalk.simplify_first_parent()?;
+        }
+        let mut decorations = self.decorations()?;
 
         Ok(revwalk
             .flat_map(|id| self.repo.find_commit(id.unwrap()))
-            .map(|commit| Commit {
-                id: commit
-                    .as_object()
-                    .short_id()
-                    .unwrap()
-                    .as_str()
-                    .unwrap()
-                    .into(),
-                author: Signature {
-                    name: String::from_utf8_lossy(commit.author().name_bytes()).into(),
-                    email: String::from_utf8_lossy(commit.author().email_bytes()).into(),
-                },
-                message: String::from_utf8_lossy(commit.message_bytes()).into(),
-                datetime: chrono::Local.timestamp(commit.time().seconds(), 0),
+            .filter(|commit| filter.matches(commit))
+            .map(|commit| {
+                let mut info = to_commit(
+                    &commit,
+                    decorations.remove(&commit.id()).unwrap_or_default(),
+                );
+                if filter.first_parent {
+                    info.parents.truncate(1);
+                }
+                info
             })
             .take(limit)
             .collect())
     }
 
+    /// The commits unique to each branch and their merge base, listing at most `limit` commits
+    /// of each side.
+    pub fn compare(&self, a: &str, b: &str, limit: usize) -> anyhow::Result<Comparison> {
+        let tip = |name: &str| -> anyhow::Result<git2::Oid> {
+            self.repo
+                .find_branch(name, git2::BranchType::Local)?
+                .get()
+                .target()
+                .ok_or_else(|| anyhow!("{} has no commit", name))
+        };
+        let (a_tip, b_tip) = (tip(a)?, tip(b)?);
+        let mut decorations = self.decorations()?;
+        let mut only_in = |from: git2::Oid, hidden: git2::Oid| -> anyhow::Result<Vec<Commit>> {
+            let mut revwalk = self.repo.revwalk()?;
+            revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+            revwalk.push(from)?;
+            revwalk.hide(hidden)?;
+            revwalk
+                .take(limit)
+                .map(|id| {
+                    let commit = self.repo.find_commit(id?)?;
+                    let refs = decorations.remove(&commit.id()).unwrap_or_default();
+                    Ok(to_commit(&commit, refs))
+                })
+                .collect()
+        };
+        let only_in_a = only_in(a_tip, b_tip)?;
+        let only_in_b = only_in(b_tip, a_tip)?;
+        let (count_a, count_b) = self.repo.graph_ahead_behind(a_tip, b_tip)?;
+        let merge_base = match optional(self.repo.merge_base(a_tip, b_tip))? {
+            Some(oid) => Some(to_commit(&self.repo.find_commit(oid)?, Vec::new())),
+            None => None,
+        };
+        Ok(Comparison {
+            a: a.to_owned(),
+            b: b.to_owned(),
+            merge_base,
+            only_in_a,
+            only_in_b,
+            count_a,
+            count_b,
+        })
+    }
+
+    /// The changes of branch `to` since it forked from branch `from` as a patch,
+    /// like `git diff from...to`. The changes made on `from` meanwhile are left out.
+    pub fn diff(&self, from: &str, to: &str) -> anyhow::Result<Vec<String>> {
+        let tip = |name: &str| -> anyhow::Result<git2::Commit> {
+            Ok(self
+                .repo
+                .find_branch(name, git2::BranchType::Local)?
+                .get()
+                .peel_to_commit()?)
+        };
+        let (from_tip, to_tip) = (tip(from)?, tip(to)?);
+        let merge_base = match self.repo.merge_base(from_tip.id(), to_tip.id()) {
+            Ok(oid) => self.repo.find_commit(oid)?,
+            Err(e) if e.code() == git2::ErrorCode::NotFound => {
+                return Err(anyhow!("{} and {} have no common commit", from, to))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let diff =
+            self.repo
+                .diff_tree_to_tree(Some(&merge_base.tree()?), Some(&to_tip.tree()?), None)?;
+        let mut patch = String::new();
+        diff.print(git2::DiffFormat::Patch, |_, _, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                patch.push(line.origin());
+            }
+            patch.push_str(&String::from_utf8_lossy(line.content()));
+            true
+        })?;
+        Ok(patch.lines().map(ToOwned::to_owned).collect())
+    }
+
+    /// The names of the refs pointing at each commit: HEAD, branches, remote branches and tags.
+    fn decorations(&self) -> anyhow::Result<HashMap<git2::Oid, Vec<String>>> {
+        let mut decorations: HashMap<git2::Oid, Vec<String>> = HashMap::new();
+        let current_branch = self.current_branch()?;
+        for reference in self.repo.references()? {
+            let reference = reference?;
+            // e.g. origin/HEAD
+            if reference.kind() != Some(git2::ReferenceType::Direct) {
+                continue;
+            }
+            let shorthand = String::from_utf8_lossy(reference.shorthand_bytes()).into_owned();
+            let name = if reference.is_branch() {
+                if current_branch.as_ref() == Some(&shorthand) {
+                    format!("HEAD -> {}", shorthand)
+                } else {
+                    shorthand
+                }
+            } else if reference.is_remote() {
+                shorthand
+            } else if reference.is_tag() {
+                format!("tag: {}", shorthand)
+            } else {
+                continue;
+            };
+            if let Ok(commit) = reference.peel_to_commit() {
+                let names = decorations.entry(commit.id()).or_default();
+                if name.starts_with("HEAD") {
+                    names.insert(0, name);
+                } else {
+                    names.push(name);
+                }
+            }
+        }
+        if self.repo.head_detached()? {
+            if let Some(oid) = self.repo.head()?.target() {
+                decorations.entry(oid).or_default().insert(0, "HEAD".into());
+            }
+        }
+        Ok(decorations)
+    }
+
+    pub fn branch_status(&self, branch_name: &str) -> anyhow::Result<BranchStatus> {
+        let branch = self
+            .repo
+            .find_branch(branch_name, git2::BranchType::Local)?;
+        let tip = match branch.get().target() {
+            Some(oid) => oid,
+            None => return Ok(BranchStatus::default()),
+        };
+        let (upstream, upstream_gone) = match branch.upstream() {
+            Ok(upstream) => (
+                match upstream.get().target() {
+                    Some(oid) => Some(self.repo.graph_ahead_behind(tip, oid)?),
+                    None => None,
+                },
+                false,
+            ),
+            Err(_) => (None, self.has_upstream_config(branch_name)?),
+        };
+        let merged = match self.repo.head().ok().and_then(|head| head.target()) {
+            Some(head) => head == tip || self.repo.graph_descendant_of(head, tip)?,
+            None => false,
+        };
+        Ok(BranchStatus {
+            upstream,
+            merged,
+            upstream_gone,
+        })
+    }
+
+    fn has_upstream_config(&self, branch_name: &str) -> anyhow::Result<bool> {
+        Ok(self
+            .config_string(&format!("branch.{}.merge", branch_name))?
+            .is_some())
+    }
+
+    /// The descriptions of the branches that have one, as `git branch --edit-description`
+    /// stores them in `branch.<name>.description`.
+    pub fn branch_descriptions(&self) -> anyhow::Result<HashMap<String, String>> {
+        let config = self.repo.config()?.snapshot()?;
+        let mut descriptions = HashMap::new();
+        config
+            .entries(Some(r"^branch\..*\.description$"))?
+            .for_each(|entry| {
+                let branch = entry.name().and_then(|name| {
+                    name.strip_prefix("branch.")
+                        .and_then(|name| name.strip_suffix(".description"))
+                });
+                if let (Some(branch), Some(value)) = (branch, entry.value()) {
+                    descriptions.insert(branch.to_owned(), value.trim_end().to_owned());
+                }
+            })?;
+        Ok(descriptions)
+    }
+
+    /// Sets the description of a branch, or removes it when `description` is blank.
+    pub fn set_branch_description(
+        &self,
+        branch_name: &str,
+        description: &str,
+    ) -> anyhow::Result<()> {
+        let key = format!("branch.{}.description", branch_name);
+        let mut config = self.repo.config()?;
+        let description = description.trim();
+        if description.is_empty() {
+            match config.remove(&key) {
+                Err(e) if e.code() != git2::ErrorCode::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        } else {
+            // with a newline at the end, the way git writes it from the editor
+            config.set_str(&key, &format!("{}\n", description))?;
+        }
+        Ok(())
+    }
+
+    fn config_string(&self, key: &str) -> anyhow::Result<Option<String>> {
+        Ok(optional(self.repo.config()?.get_string(key))?)
+    }
+
+    /// Local branches whose upstream branch was deleted, like `[gone]` in `git branch -vv`.
+    pub fn gone_branches(&self) -> anyhow::Result<Vec<String>> {
+        let mut gone = Vec::new();
+        for branch in self.branches()? {
+            if self.branch_status(&branch)?.upstream_gone {
+                gone.push(branch);
+            }
+        }
+        Ok(gone)
+    }
+
+    pub fn remotes(&self) -> anyhow::Result<Vec<String>> {
+        Ok(self
+            .repo
+            .remotes()?
+            .iter()
+            .flatten()
+            .map(ToOwned::to_owned)
+            .collect())
+    }
+
+    /// Fetches `remote`, or every remote if `None`, and prunes remote-tracking branches
+    /// deleted on the remote, like `git fetch --prune [--all]`.
+    /// Returns the names of the fetched remotes.
+    pub fn fetch(
+        &self,
+        remote: Option<&str>,
+        mut progress: impl FnMut(&Progress),
+    ) -> anyhow::Result<Vec<String>> {
+        let remotes = match remote {
+            Some(remote) => vec![remote.to_owned()],
+            None => self.remotes()?,
+        };
+        if remotes.is_empty() {
+            return Err(anyhow!("No remote repository is configured."));
+        }
+        for name in &remotes {
+            let mut remote = self.repo.find_remote(name)?;
+            let mut callbacks = credentials::callbacks(self.repo.config()?);
+            callbacks.transfer_progress(|stats| {
+                progress(&Progress {
+                    remote: name.clone(),
+                    received_objects: stats.received_objects(),
+                    total_objects: stats.total_objects(),
+                    indexed_deltas: stats.indexed_deltas(),
+                    total_deltas: stats.total_deltas(),
+                    received_bytes: stats.received_bytes(),
+                });
+                true
+            });
+            let mut options = git2::FetchOptions::new();
+            options
+                .remote_callbacks(callbacks)
+                .prune(git2::FetchPrune::On)
+                .download_tags(git2::AutotagOption::Auto);
+            remote
+                .fetch::<&str>(&[], Some(&mut options), None)
+                .map_err(|e| anyhow!("{}: {}", name, e.message()))?;
+        }
+        Ok(remotes)
+    }
+
+    /// The remote `git push` would push the branch to. "." is the repository itself,
+    /// e.g. for a branch tracking another local branch, and is passed over.
+    fn push_remote(&self, branch_name: &str) -> anyhow::Result<String> {
+        for key in [
+            format!("branch.{}.pushRemote", branch_name),
+            "remote.pushDefault".to_owned(),
+            format!("branch.{}.remote", branch_name),
+        ] {
+            match self.config_string(&key)? {
+                Some(remote) if remote != "." => return Ok(remote),
+                _ => {}
+            }
+        }
+        let remotes = self.remotes()?;
+        if remotes.iter().any(|remote| remote == "origin") {
+            return Ok("origin".into());
+        }
+        match remotes.as_slice() {
+            [remote] => Ok(remote.clone()),
+            _ => Err(anyhow!("No remote to push {} to.", branch_name)),
+        }
+    }
+
+    /// Pushes a local branch like `git push -u`, or `git push --force-with-lease -u` if `force`.
+    /// The upstream is only set when the branch has none.
+    /// Returns the remote branch pushed to, e.g. `origin/feature`.
+    pub fn push(&self, branch_name: &str, force: bool) -> anyhow::Result<String> {
+        let mut branch = self
+            .repo
+            .find_branch(branch_name, git2::BranchType::Local)?;
+        let local_ref = branch.get().name().unwrap().to_owned();
+        let remote_name = self.push_remote(branch_name)?;
+        let remote_ref = match (
+            self.config_string(&format!("branch.{}.remote", branch_name))?,
+            self.config_string(&format!("branch.{}.merge", branch_name))?,
+        ) {
+            (Some(remote), Some(merge)) if remote == remote_name => merge,
+            _ => local_ref.clone(),
+        };
+        let mut remote = self.repo.find_remote(&remote_name)?;
+        let tracking_ref = tracking_ref(&remote, &remote_ref);
+
+        if force {
+            let expected = match &tracking_ref {
+                Some(name) => self.repo.refname_to_id(name).ok(),
+                None => None,
+            };
+            let mut connection = remote.connect_auth(
+                git2::Direction::Push,
+                Some(credentials::callbacks(self.repo.config()?)),
+                None,
+            )?;
+            let actual = connection
+                .list()?
+                .iter()
+                .find(|head| head.name() == remote_ref)
+                .map(|head| head.oid());
+            if actual != expected {
+                return Err(anyhow!(
+                    "{} on {} changed since the last fetch. Fetch and check it first.",
+                    remote_ref,
+                    remote_name
+                ));
+            }
+            // The push goes over the same connection, and tells the remote to update the
+            // ref from the advertised oid that was just checked. The remote refuses if the
+            // ref moved in between, so the lease holds without a race.
+            self.push_refspec(
+                connection.remote(),
+                &format!("+{}:{}", local_ref, remote_ref),
+            )?;
+        } else {
+            self.push_refspec(&mut remote, &format!("{}:{}", local_ref, remote_ref))?;
+        }
+
+        let upstream = format!(
+            "{}/{}",
+            remote_name,
+            remote_ref
+                .strip_prefix("refs/heads/")
+                .unwrap_or(&remote_ref)
+        );
+        if let Some(tracking_ref) = tracking_ref {
+            if let Some(oid) = branch.get().target() {
+                self.repo.reference(&tracking_ref, oid, true, "push")?;
+            }
+            if branch.upstream().is_err() {
+                branch.set_upstream(Some(&upstream))?;
+            }
+        }
+        Ok(upstream)
+    }
+
+    /// Pushes a single refspec, failing if the remote rejects it.
+    fn push_refspec(&self, remote: &mut git2::Remote, refspec: &str) -> anyhow::Result<()> {
+        let remote_name = remote.name().unwrap_or_default().to_owned();
+        let rejected = std::cell::RefCell::new(None);
+        let mut callbacks = credentials::callbacks(self.repo.config()?);
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(status) = status {
+                *rejected.borrow_mut() = Some(format!("{}: {}", refname, status));
+            }
+            Ok(())
+        });
+        remote
+            .push(
+                &[refspec],
+                Some(git2::PushOptions::new().remote_callbacks(callbacks)),
+            )
+            .map_err(|e| anyhow!("{}: {}", remote_name, e.message()))?;
+        match rejected.into_inner() {
+            Some(rejected) => Err(anyhow!("{} rejected {}", remote_name, rejected)),
+            None => Ok(()),
+        }
+    }
+
+    /// The upstream of a local branch, if it is a branch of a remote that we know of.
+    pub fn remote_branch(&self, branch_name: &str) -> anyhow::Result<Option<RemoteBranch>> {
+        let branch = self
+            .repo
+            .find_branch(branch_name, git2::BranchType::Local)?;
+        let upstream = match branch.upstream() {
+            Ok(upstream) => upstream,
+            Err(_) => return Ok(None),
+        };
+        let remote = self.config_string(&format!("branch.{}.remote", branch_name))?;
+        let merge = self.config_string(&format!("branch.{}.merge", branch_name))?;
+        let (remote, name) = match (remote, merge) {
+            // "." is the repository itself, i.e. the upstream is a local branch
+            (Some(remote), Some(merge)) if remote != "." => {
+                let name = merge
+                    .strip_prefix("refs/heads/")
+                    .unwrap_or(&merge)
+                    .to_owned();
+                (remote, name)
+            }
+            _ => return Ok(None),
+        };
+        // a branch tracking another one, e.g. `origin/main`, must not delete it
+        if name != branch_name {
+            return Ok(None);
+        }
+        // nor the branch the remote's HEAD points at
+        let default_branch = self
+            .repo
+            .find_reference(&format!("refs/remotes/{}/HEAD", remote))
+            .ok()
+            .and_then(|head| head.symbolic_target().map(ToOwned::to_owned));
+        if default_branch.is_some() && default_branch.as_deref() == upstream.get().name() {
+            return Ok(None);
+        }
+        Ok(Some(RemoteBranch { remote, name }))
+    }
+
+    /// Deletes a branch on its remote, like `git push <remote> --delete <branch>`,
+    /// and then its remote-tracking branch.
+    pub fn delete_remote_branch(&self, branch: &RemoteBranch) -> anyhow::Result<()> {
+        let mut remote = self.repo.find_remote(&branch.remote)?;
+        let remote_ref = format!("refs/heads/{}", branch.name);
+        self.push_refspec(&mut remote, &format!(":{}", remote_ref))?;
+        if let Some(tracking_ref) = tracking_ref(&remote, &remote_ref) {
+            if let Ok(mut reference) = self.repo.find_reference(&tracking_ref) {
+                reference.delete()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fast-forwards a local branch to its upstream without checking it out.
+    /// The working tree follows when the branch is HEAD, and a diverged branch is left alone.
+    pub fn fast_forward(&self, branch_name: &str) -> anyhow::Result<SyncResult> {
+        let branch = self
+            .repo
+            .find_branch(branch_name, git2::BranchType::Local)?;
+        let upstream = match branch.upstream() {
+            Ok(upstream) => upstream,
+            Err(_) => return Ok(SyncResult::NoUpstream),
+        };
+        let (tip, target) = match (branch.get().target(), upstream.get().target()) {
+            (Some(tip), Some(target)) => (tip, target),
+            _ => return Ok(SyncResult::NoUpstream),
+        };
+        match self.repo.graph_ahead_behind(tip, target)? {
+            (_, 0) if tip == target => return Ok(SyncResult::UpToDate),
+            (ahead, 0) => return Ok(SyncResult::Ahead(ahead)),
+            (0, _) => {}
+            (ahead, behind) => return Ok(SyncResult::Diverged(ahead, behind)),
+        }
+
+        if let Some(worktree) = self.checked_out_elsewhere(branch_name)? {
+            return Err(anyhow!(
+                "{} is checked out in the worktree {}",
+                branch_name,
+                worktree
+            ));
+        }
+        let message = format!("git-ex: fast-forward to {}", upstream.name()?.unwrap_or(""));
+        if branch.is_head() {
+            // a safe checkout fails instead of overwriting local changes
+            self.repo.checkout_tree(
+                &self.repo.find_object(target, None)?,
+                Some(git2::build::CheckoutBuilder::new().safe()),
+            )?;
+        }
+        branch.into_reference().set_target(target, &message)?;
+
+        let short = |oid: git2::Oid| -> anyhow::Result<String> {
+            Ok(self
+                .repo
+                .find_object(oid, None)?
+                .short_id()?
+                .as_str()
+                .unwrap_or_default()
+                .to_owned())
+        };
+        Ok(SyncResult::FastForwarded(short(tip)?, short(target)?))
+    }
+
+    /// Fast-forwards every local branch that has an upstream, like `fast_forward`.
+    pub fn sync_branches(&self) -> anyhow::Result<Vec<(String, anyhow::Result<SyncResult>)>> {
+        Ok(self
+            .branches()?
+            .into_iter()
+            .map(|branch| {
+                let result = self.fast_forward(&branch);
+                (branch, result)
+            })
+            .filter(|(_, result)| !matches!(result, Ok(SyncResult::NoUpstream)))
+            .collect())
+    }
+
+    /// The other worktree that has the branch checked out, if any: the name of a linked
+    /// worktree, or the path of the main one when run from a linked worktree.
+    fn checked_out_elsewhere(&self, branch_name: &str) -> anyhow::Result<Option<String>> {
+        let refname = format!("refs/heads/{}", branch_name);
+        let git_dir = self.git_dir().canonicalize()?;
+        let checked_out_by = |repo: &git2::Repository| {
+            repo.path().canonicalize().ok().as_ref() != Some(&git_dir)
+                && repo
+                    .head()
+                    .ok()
+                    .and_then(|h| h.name().map(|n| n == refname))
+                    == Some(true)
+        };
+
+        // the main worktree is not one of the linked ones
+        if let Ok(main) = git2::Repository::open(self.common_dir()) {
+            if let Some(workdir) = main.workdir().filter(|_| checked_out_by(&main)) {
+                return Ok(Some(workdir.display().to_string()));
+            }
+        }
+        for name in self.repo.worktrees()?.iter().flatten() {
+            let worktree = self.repo.find_worktree(name)?;
+            if let Ok(repo) = git2::Repository::open_from_worktree(&worktree) {
+                if checked_out_by(&repo) {
+                    return Ok(Some(name.to_owned()));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Rebases a local branch onto another one, like `git rebase <onto> <branch>`,
+    /// so the branch is checked out afterwards. Stops at the first conflict, to be
+    /// resolved and staged before `continue_rebase`, or passed by `skip_rebase`.
+    /// `progress` is called with the 1-based position of each commit and their number.
+    pub fn rebase(
+        &self,
+        branch_name: &str,
+        onto: &str,
+        mut progress: impl FnMut(usize, usize),
+    ) -> anyhow::Result<RebaseStatus> {
+        let branch = self
+            .repo
+            .find_branch(branch_name, git2::BranchType::Local)?;
+        let onto_branch = self.repo.find_branch(onto, git2::BranchType::Local)?;
+        let mut rebase = self.repo.rebase(
+            Some(&self.repo.reference_to_annotated_commit(branch.get())?),
+            Some(&self.repo.reference_to_annotated_commit(onto_branch.get())?),
+            None,
+            None,
+        )?;
+        self.proceed_rebase(&mut rebase, true, &mut progress)
+    }
+
+    /// The rebase in progress, if it stopped at a conflict.
+    pub fn rebase_state(&self) -> anyhow::Result<Option<RebaseState>> {
+        match optional(self.repo.open_rebase(None))? {
+            Some(mut rebase) => Ok(Some(self.stopped_rebase(&mut rebase)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Commits the resolved commit and goes on with the rebase in progress.
+    pub fn continue_rebase(
+        &self,
+        mut progress: impl FnMut(usize, usize),
+    ) -> anyhow::Result<RebaseStatus> {
+        let mut rebase = self.repo.open_rebase(None)?;
+        self.proceed_rebase(&mut rebase, true, &mut progress)
+    }
+
+    /// Drops the stopped commit and goes on with the rebase in progress.
+    pub fn skip_rebase(
+        &self,
+        mut progress: impl FnMut(usize, usize),
+    ) -> anyhow::Result<RebaseStatus> {
+        let mut rebase = self.repo.open_rebase(None)?;
+        self.repo
+            .checkout_head(Some(git2::build::CheckoutBuilder::new().force()))?;
+        self.proceed_rebase(&mut rebase, false, &mut progress)
+    }
+
+    /// Stops the rebase in progress and restores the branch as it was.
+    pub fn abort_rebase(&self) -> anyhow::Result<()> {
+        self.repo.open_rebase(None)?.abort()?;
+        Ok(())
+    }
+
+    fn proceed_rebase(
+        &self,
+        rebase: &mut git2::Rebase,
+        mut commit_current: bool,
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> anyhow::Result<RebaseStatus> {
+        // libgit2 keeps the target in its state directory, but has no API for it
+        let onto = std::fs::read_to_string(self.git_dir().join("rebase-merge/onto"))
+            .map_err(anyhow::Error::from)
+            .and_then(|id| Ok(git2::Oid::from_str(id.trim())?))?;
+        loop {
+            if commit_current {
+                if let Some(index) = rebase.operation_current() {
+                    // the conflicts may have been resolved by another program
+                    let mut repo_index = self.repo.index()?;
+                    repo_index.read(false)?;
+                    if repo_index.has_conflicts() {
+                        return Ok(RebaseStatus::Stopped(self.stopped_rebase(rebase)?));
+                    }
+                    let original = self.repo.find_commit(rebase.nth(index).unwrap().id())?;
+                    let committer = self
+                        .repo
+                        .signature()
+                        .unwrap_or_else(|_| original.committer().to_owned());
+                    match rebase.commit(None, &committer, None) {
+                        // the changes are upstream already, so there is nothing to commit
+                        Err(e) if e.code() == git2::ErrorCode::Applied => {}
+                        result => {
+                            result?;
+                        }
+                    }
+                }
+            }
+            commit_current = true;
+            match rebase.next() {
+                Some(operation) => {
+                    operation?;
+                    if let Some(index) = rebase.operation_current() {
+                        progress(index + 1, rebase.len());
+                    }
+                }
+                None => break,
+            }
+        }
+        // what is on top of the target now, also counting the commits of earlier runs
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push_head()?;
+        revwalk.hide(onto)?;
+        let applied = revwalk.count();
+        rebase.finish(None)?;
+        Ok(RebaseStatus::Done(applied))
+    }
+
+    fn stopped_rebase(&self, rebase: &mut git2::Rebase) -> anyhow::Result<RebaseState> {
+        let branch = rebase
+            .orig_head_name()
+            .map(|name| name.strip_prefix("refs/heads/").unwrap_or(name).to_owned())
+            .unwrap_or_default();
+        // like the target itself, see `proceed_rebase`
+        let onto = std::fs::read_to_string(self.git_dir().join("rebase-merge/onto_name"))
+            .map(|name| name.trim().to_owned())
+            .unwrap_or_default();
+        let current = rebase.operation_current();
+        let commit = match current.and_then(|i| rebase.nth(i)) {
+            Some(operation) => {
+                let commit = self.repo.find_commit(operation.id())?;
+                format!(
+                    "{} {}",
+                    commit.as_object().short_id()?.as_str().unwrap_or_default(),
+                    commit.summary().unwrap_or_default()
+                )
+            }
+            None => String::new(),
+        };
+        Ok(RebaseState {
+            branch,
+            onto,
+            current: current.map(|i| i + 1).unwrap_or(0),
+            total: rebase.len(),
+            commit,
+            conflicts: self.conflicts()?,
+        })
+    }
+
+    /// Paths with unresolved conflicts in the index.
+    pub fn conflicts(&self) -> anyhow::Result<Vec<String>> {
+        let mut index = self.repo.index()?;
+        index.read(false)?;
+        let mut paths = Vec::new();
+        for conflict in index.conflicts()? {
+            let conflict = conflict?;
+            if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+                paths.push(String::from_utf8_lossy(&entry.path).into_owned());
+            }
+        }
+        Ok(paths)
+    }
+
+    pub fn merge_analysis(&self, branch_name: &str) -> anyhow::Result<MergeAnalysis> {
+        let branch = self
+            .repo
+            .find_branch(branch_name, git2::BranchType::Local)?;
+        let theirs = self.repo.reference_to_annotated_commit(branch.get())?;
+        let (analysis, _) = self.repo.merge_analysis(&[&theirs])?;
+        Ok(if analysis.is_up_to_date() {
+            MergeAnalysis::UpToDate
+        } else if analysis.is_fast_forward() {
+            MergeAnalysis::FastForward
+        } else {
+            MergeAnalysis::Normal
+        })
+    }
+
+    /// Merges a local branch into HEAD. Local changes are never overwritten.
+    pub fn merge(
+        &self,
+        branch_name: &str,
+        strategy: MergeStrategy,
+        message: &str,
+    ) -> anyhow::Result<MergeOutcome> {
+        let analysis = self.merge_analysis(branch_name)?;
+        let branch = self
+            .repo
+            .find_branch(branch_name, git2::BranchType::Local)?;
+        let target = branch
+            .get()
+            .target()
+            .ok_or_else(|| anyhow!("{} has no commit", branch_name))?;
+        match (analysis, strategy) {
+            (MergeAnalysis::UpToDate, _) => return Ok(MergeOutcome::UpToDate),
+            (MergeAnalysis::FastForward, MergeStrategy::FastForward) => {
+                self.repo.checkout_tree(
+                    &self.repo.find_object(target, None)?,
+                    Some(git2::build::CheckoutBuilder::new().safe()),
+                )?;
+                self.repo.head()?.set_target(
+                    target,
+                    &format!("git-ex: merge {}: Fast-forward", branch_name),
+                )?;
+                return Ok(MergeOutcome::FastForwarded);
+            }
+            (MergeAnalysis::Normal, MergeStrategy::FastForward) => {
+                return Err(anyhow!(
+                    "{} cannot be fast-forwarded to {}.",
+                    self.current_branch()?.unwrap_or_default(),
+                    branch_name
+                ));
+            }
+            _ => {}
+        }
+
+        let theirs = self.repo.reference_to_annotated_commit(branch.get())?;
+        self.repo.merge(&[&theirs], None, None)?;
+        if strategy == MergeStrategy::Squash {
+            // a squash records no MERGE_HEAD, so the commit gets a single parent
+            self.repo.cleanup_state()?;
+        }
+        std::fs::write(self.merge_message_path(strategy), message)?;
+        self.commit_merge()
+    }
+
+    fn merge_message_path(&self, strategy: MergeStrategy) -> PathBuf {
+        self.git_dir().join(match strategy {
+            MergeStrategy::Squash => "SQUASH_MSG",
+            _ => "MERGE_MSG",
+        })
+    }
+
+    /// The unresolved files of a merge in progress, if there is one.
+    pub fn merge_conflicts(&self) -> anyhow::Result<Option<Vec<String>>> {
+        let squashing = self.merge_message_path(MergeStrategy::Squash).exists();
+        if self.repo.state() == git2::RepositoryState::Merge || squashing {
+            Ok(Some(self.conflicts()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Commits the merge in progress once every conflict is resolved and staged.
+    pub fn commit_merge(&self) -> anyhow::Result<MergeOutcome> {
+        let conflicts = self.conflicts()?;
+        if !conflicts.is_empty() {
+            return Ok(MergeOutcome::Conflicts(conflicts));
+        }
+        let mut parents = vec![self.repo.head()?.peel_to_commit()?];
+        let mut path = self.merge_message_path(MergeStrategy::Squash);
+        if self.repo.state() == git2::RepositoryState::Merge {
+            path = self.merge_message_path(MergeStrategy::Commit);
+            // `mergehead_foreach` needs a mutable repository, and the file is simple enough
+            let merge_heads = std::fs::read_to_string(self.git_dir().join("MERGE_HEAD"))?;
+            for id in merge_heads.lines() {
+                parents.push(self.repo.find_commit(git2::Oid::from_str(id.trim())?)?);
+            }
+        }
+        let message = std::fs::read_to_string(&path)?;
+        let id = self.commit_index(&message, &parents.iter().collect::<Vec<_>>())?;
+        self.repo.cleanup_state()?;
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(MergeOutcome::Committed(
+            self.repo
+                .find_object(id, None)?
+                .short_id()?
+                .as_str()
+                .unwrap_or_default()
+                .to_owned(),
+        ))
+    }
+
+    /// Stops the merge in progress and resets the index and working tree to HEAD,
+    /// like `git merge --abort`.
+    pub fn abort_merge(&self) -> anyhow::Result<()> {
+        self.repo.cleanup_state()?;
+        let squash_message = self.merge_message_path(MergeStrategy::Squash);
+        if squash_message.exists() {
+            std::fs::remove_file(squash_message)?;
+        }
+        self.repo
+            .checkout_head(Some(git2::build::CheckoutBuilder::new().force()))?;
+        Ok(())
+    }
+
+    /// Applies the commits onto HEAD in the given order, like `git cherry-pick`. Merge
+    /// commits are refused before anything is applied. A conflict leaves the cherry-pick
+    /// in progress, to be finished with `continue_cherry_pick` or `abort_cherry_pick`.
+    pub fn cherry_pick(&self, ids: &[git2::Oid]) -> anyhow::Result<CherryPickStatus> {
+        for id in ids {
+            let commit = self.repo.find_commit(*id)?;
+            if commit.parent_count() > 1 {
+                return Err(anyhow!("{} is a merge commit", describe(&commit)?));
+            }
+        }
+        self.pick_commits(ids, 0)
+    }
+
+    /// Commits the resolved commit of the stopped cherry-pick, then applies `remaining`.
+    pub fn continue_cherry_pick(
+        &self,
+        remaining: &[git2::Oid],
+    ) -> anyhow::Result<CherryPickStatus> {
+        if self.repo.state() != git2::RepositoryState::CherryPick {
+            return Err(anyhow!("No cherry-pick is in progress."));
+        }
+        if !self.conflicts()?.is_empty() {
+            return Err(anyhow!("Resolve and stage the conflicted files first."));
+        }
+        let commit = self.cherry_pick_head()?;
+        let applied = usize::from(self.commit_cherry_pick(&commit)?);
+        self.pick_commits(remaining, applied)
+    }
+
+    /// Drops the stopped commit and resets the index and working tree to HEAD. The
+    /// commits applied before it stay.
+    pub fn abort_cherry_pick(&self) -> anyhow::Result<()> {
+        self.repo.cleanup_state()?;
+        self.repo
+            .checkout_head(Some(git2::build::CheckoutBuilder::new().force()))?;
+        Ok(())
+    }
+
+    /// The cherry-pick in progress, if any, with `remaining` as the commits still to apply
+    /// as they are only known to whoever started it.
+    pub fn cherry_pick_state(
+        &self,
+        remaining: Vec<git2::Oid>,
+    ) -> anyhow::Result<Option<CherryPickState>> {
+        if self.repo.state() != git2::RepositoryState::CherryPick {
+            return Ok(None);
+        }
+        Ok(Some(CherryPickState {
+            commit: describe(&self.cherry_pick_head()?)?,
+            conflicts: self.conflicts()?,
+            remaining,
+        }))
+    }
+
+    fn cherry_pick_head(&self) -> anyhow::Result<git2::Commit<'_>> {
+        Ok(self
+            .repo
+            .revparse_single("CHERRY_PICK_HEAD")?
+            .peel_to_commit()?)
+    }
+
+    /// Applies `ids` one after the other, adding the ones committed to `applied`.
+    fn pick_commits(
+        &self,
+        ids: &[git2::Oid],
+        mut applied: usize,
+    ) -> anyhow::Result<CherryPickStatus> {
+        for (i, id) in ids.iter().enumerate() {
+            let commit = self.repo.find_commit(*id)?;
+            self.repo.cherrypick(&commit, None)?;
+            if let Some(state) = self.cherry_pick_state(ids[i + 1..].to_vec())? {
+                if !state.conflicts.is_empty() {
+                    return Ok(CherryPickStatus::Stopped(state));
+                }
+            }
+            if self.commit_cherry_pick(&commit)? {
+                applied += 1;
+            }
+        }
+        Ok(CherryPickStatus::Done(applied))
+    }
+
+    /// Commits the index with the author and message of `commit`, and ends the
+    /// cherry-pick state. Returns whether there was anything to commit.
+    fn commit_cherry_pick(&self, commit: &git2::Commit) -> anyhow::Result<bool> {
+        let mut index = self.repo.index()?;
+        index.read(false)?;
+        let tree = self.repo.find_tree(index.write_tree()?)?;
+        let head = self.repo.head()?.peel_to_commit()?;
+        // the changes are already there, so there is nothing to commit
+        let committed = tree.id() != head.tree_id();
+        if committed {
+            self.repo.commit(
+                Some("HEAD"),
+                &commit.author(),
+                &self.repo.signature()?,
+                &String::from_utf8_lossy(commit.message_bytes()),
+                &tree,
+                &[&head],
+            )?;
+        }
+        self.repo.cleanup_state()?;
+        Ok(committed)
+    }
+
+    /// Commits the index on top of HEAD with the signature from the git config.
+    fn commit_index(&self, message: &str, parents: &[&git2::Commit]) -> anyhow::Result<git2::Oid> {
+        let signature = self.repo.signature()?;
+        let mut index = self.repo.index()?;
+        index.read(false)?;
+        let tree = self.repo.find_tree(index.write_tree()?)?;
+        Ok(self.repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            parents,
+        )?)
+    }
+
     pub fn checkout(&self, branch_name: &str) -> anyhow::Result<()> {
         self.repo
             .find_branch(branch_name, git2::BranchType::Local)
@@ -87,7 +1255,7 @@ impl Repository {
             })
     }
 
-    pub fn checkout_new_branch(&self, branch_name: &str) -> anyhow::Result<git2::Branch> {
+    pub fn checkout_new_branch(&self, branch_name: &str) -> anyhow::Result<git2::Branch<'_>> {
         let oid = self.repo.head()?.target().unwrap();
         self.repo
             .find_commit(oid)
@@ -95,6 +1263,21 @@ impl Repository {
             .map_err(Into::into)
     }
 
+    /// Creates a branch at HEAD and checks it out, with `note` as its description.
+    pub fn start_branch(
+        &self,
+        branch_name: &str,
+        note: Option<&str>,
+    ) -> anyhow::Result<git2::Branch<'_>> {
+        let branch = self.checkout_new_branch(branch_name)?;
+        let name = branch.name()?.unwrap();
+        self.checkout(name)?;
+        if let Some(note) = note {
+            self.set_branch_description(name, note)?;
+        }
+        Ok(branch)
+    }
+
     pub fn delete_branch(&self, branch_name: &str) -> anyhow::Result<()> {
         self.repo
             .find_branch(branch_name, git2::BranchType::Local)
@@ -102,3 +1285,1041 @@ impl Repository {
             .map_err(Into::into)
     }
 }
+
+/// The short id and summary of a commit, e.g. `1a2b3c4 Add a`.
+fn describe(commit: &git2::Commit) -> anyhow::Result<String> {
+    Ok(format!(
+        "{} {}",
+        commit.as_object().short_id()?.as_str().unwrap_or_default(),
+        commit.summary().unwrap_or_default()
+    ))
+}
+
+fn to_commit(commit: &git2::Commit, refs: Vec<String>) -> Commit {
+    Commit {
+        oid: commit.id(),
+        id: commit
+            .as_object()
+            .short_id()
+            .ok()
+            .and_then(|id| id.as_str().map(ToOwned::to_owned))
+            .unwrap_or_default(),
+        parents: commit.parent_ids().collect(),
+        refs,
+        author: Signature {
+            name: String::from_utf8_lossy(commit.author().name_bytes()).into(),
+            email: String::from_utf8_lossy(commit.author().email_bytes()).into(),
+        },
+        message: String::from_utf8_lossy(commit.message_bytes()).into(),
+        datetime: chrono::Local.timestamp(commit.time().seconds(), 0),
+    }
+}
+
+/// Turns the `NotFound` error of a git2 lookup, e.g. of an unset config key, into `None`.
+pub fn optional<T>(result: Result<T, git2::Error>) -> Result<Option<T>, git2::Error> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// The remote-tracking ref that the fetch refspecs of `remote` map `remote_ref` to.
+fn tracking_ref(remote: &git2::Remote, remote_ref: &str) -> Option<String> {
+    remote
+        .refspecs()
+        .filter(|spec| spec.direction() == git2::Direction::Fetch)
+        .find(|spec| spec.src_matches(remote_ref))
+        .and_then(|spec| spec.transform(remote_ref).ok())
+        .and_then(|name| name.as_str().map(ToOwned::to_owned))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{diverged_branches, TempRepo};
+    use std::fs;
+
+    #[test]
+    fn test_current_branch() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("develop").checkout("develop");
+        let repo = Repository::new(temp.path())?;
+
+        assert_eq!(repo.current_branch()?, Some("develop".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_branches() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("develop").branch("feature/1-foo").tag("v1.0.0");
+        let _origin = temp.add_remote("origin");
+        temp.push("origin", "develop");
+        temp.write("dirty.txt", "dirty").stash("wip");
+        let repo = Repository::new(temp.path())?;
+
+        // tags, remote-tracking branches and stashes are not local branches
+        assert_eq!(repo.branches()?, vec!["develop", "feature/1-foo", "master"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_logs() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.commit_file("a.txt", "a", "add a")
+            .branch("develop")
+            .checkout("develop")
+            .commit_file("b.txt", "b", "add b")
+            .checkout("master")
+            .commit_file("c.txt", "c", "add c");
+        temp.merge("develop", "merge develop");
+        let repo = Repository::new(temp.path())?;
+
+        let messages = |branch, limit| -> anyhow::Result<Vec<String>> {
+            Ok(repo
+                .logs(branch, limit, &LogFilter::default())?
+                .into_iter()
+                .map(|c| c.message)
+                .collect())
+        };
+        assert_eq!(
+            messages("master", 10)?,
+            vec!["merge develop", "add c", "add b", "add a", "initial commit"]
+        );
+        assert_eq!(messages("master", 2)?, vec!["merge develop", "add c"]);
+        assert_eq!(
+            messages("develop", 10)?,
+            vec!["add b", "add a", "initial commit"]
+        );
+
+        let commit = repo.logs("develop", 1, &LogFilter::default())?.remove(0);
+        assert_eq!(commit.author.name, "git-ex");
+        assert_eq!(commit.author.email, "git-ex@example.com");
+        assert_eq!(commit.id.len(), 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_logs_filter() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.commit_file("a.txt", "a", "add a")
+            .branch("develop")
+            .checkout("develop")
+            .commit_file("src/b.txt", "b", "add b")
+            .checkout("master")
+            .commit_file("src/c.txt", "c", "fix c");
+        temp.merge("develop", "merge develop");
+        let repo = Repository::new(temp.path())?;
+
+        let messages = |filter: &str| -> anyhow::Result<Vec<String>> {
+            Ok(repo
+                .logs("master", 10, &LogFilter::parse(filter)?)?
+                .into_iter()
+                .map(|c| c.message)
+                .collect())
+        };
+        assert_eq!(messages("path:src")?, vec!["fix c", "add b"]);
+        assert_eq!(messages("path:src/b.txt")?, vec!["add b"]);
+        assert_eq!(messages("path:src first-parent")?, vec!["fix c"]);
+        assert_eq!(
+            messages("no-merges")?,
+            vec!["fix c", "add b", "add a", "initial commit"]
+        );
+        assert_eq!(messages("grep:FIX")?, vec!["fix c"]);
+        assert_eq!(
+            messages("first-parent")?,
+            vec!["merge develop", "fix c", "add a", "initial commit"]
+        );
+        assert_eq!(messages("author:git-ex@example")?.len(), 5);
+        assert!(messages("committer:nobody")?.is_empty());
+        // the commits are made in September 2020
+        assert_eq!(messages("since:2020-09-01")?.len(), 5);
+        assert!(messages("since:1.day")?.is_empty());
+        assert!(messages("until:2020-09-01")?.is_empty());
+
+        let logs = repo.logs("master", 10, &LogFilter::parse("first-parent")?)?;
+        assert_eq!(logs[0].parents.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_logs_filter_merge() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("develop")
+            .checkout("develop")
+            .commit_file("src/b.txt", "b", "add b")
+            .checkout("master")
+            .commit_file("src/c.txt", "c", "add c");
+        // a merge taking both sides
+        temp.write("src/b.txt", "b").add("src/b.txt");
+        temp.merge("develop", "merge develop");
+        let repo = Repository::new(temp.path())?;
+
+        let messages = |filter: &str| -> anyhow::Result<Vec<String>> {
+            Ok(repo
+                .logs("master", 10, &LogFilter::parse(filter)?)?
+                .into_iter()
+                .map(|c| c.message)
+                .collect())
+        };
+        // src/b.txt is as it was on develop, so the merge doesn't change it
+        assert_eq!(messages("path:src/b.txt")?, vec!["add b"]);
+        assert_eq!(messages("path:src/c.txt")?, vec!["add c"]);
+        // while src differs from both parents
+        assert_eq!(
+            messages("path:src")?,
+            vec!["merge develop", "add c", "add b"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_logs_unknown_branch() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        let repo = Repository::new(temp.path())?;
+
+        assert!(repo.logs("nothing", 10, &LogFilter::default()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_branch_status() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("merged")
+            .branch("ahead")
+            .branch("behind")
+            .checkout("ahead")
+            .commit_file("a.txt", "a", "add a")
+            .checkout("master");
+        let _origin = temp.add_remote("origin");
+        temp.push("origin", "ahead")
+            .push("origin", "behind")
+            .checkout("ahead")
+            .commit_file("b.txt", "b", "add b")
+            .checkout("behind")
+            .commit_file("c.txt", "c", "add c")
+            .push("origin", "behind");
+        temp.repo.reference(
+            "refs/heads/behind",
+            temp.repo.revparse_single("master")?.id(),
+            true,
+            "reset",
+        )?;
+        temp.checkout("master");
+        let repo = Repository::new(temp.path())?;
+
+        assert_eq!(
+            repo.branch_status("merged")?,
+            BranchStatus {
+                upstream: None,
+                merged: true,
+                upstream_gone: false
+            }
+        );
+        assert_eq!(
+            repo.branch_status("ahead")?,
+            BranchStatus {
+                upstream: Some((1, 0)),
+                merged: false,
+                upstream_gone: false
+            }
+        );
+        assert_eq!(
+            repo.branch_status("behind")?,
+            BranchStatus {
+                upstream: Some((0, 1)),
+                merged: true,
+                upstream_gone: false
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_prunes() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("develop").branch("feature");
+        let origin = temp.add_remote("origin");
+        temp.push("origin", "develop").push("origin", "feature");
+        let head = temp.repo.revparse_single("master")?.id();
+        origin.repo.reference("refs/heads/new", head, false, "")?;
+        origin
+            .repo
+            .find_branch("feature", git2::BranchType::Local)?
+            .delete()?;
+        let repo = Repository::new(temp.path())?;
+
+        assert_eq!(repo.fetch(None, |_| {})?, vec!["origin"]);
+
+        let remote_branches = |name| temp.repo.find_reference(name).is_ok();
+        assert!(remote_branches("refs/remotes/origin/new"));
+        assert!(remote_branches("refs/remotes/origin/develop"));
+        assert!(!remote_branches("refs/remotes/origin/feature"));
+        assert!(repo.branch_status("feature")?.upstream_gone);
+        assert!(!repo.branch_status("develop")?.upstream_gone);
+        assert!(!repo.branch_status("master")?.upstream_gone);
+        assert_eq!(repo.gone_branches()?, vec!["feature"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_without_remote() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        let repo = Repository::new(temp.path())?;
+
+        assert!(repo.fetch(None, |_| {}).is_err());
+        assert!(repo.fetch(Some("origin"), |_| {}).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_sets_upstream() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("feature")
+            .checkout("feature")
+            .commit_file("a.txt", "a", "add a");
+        let origin = temp.add_remote("origin");
+        let repo = Repository::new(temp.path())?;
+
+        assert_eq!(repo.push("feature", false)?, "origin/feature");
+
+        let local = temp.repo.revparse_single("feature")?.id();
+        assert_eq!(origin.repo.revparse_single("feature")?.id(), local);
+        assert_eq!(repo.branch_status("feature")?.upstream, Some((0, 0)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_force_with_lease() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("feature")
+            .checkout("feature")
+            .commit_file("a.txt", "a", "add a");
+        let origin = temp.add_remote("origin");
+        temp.push("origin", "feature");
+        // rewrite the pushed history
+        let master = temp.repo.revparse_single("master")?.id();
+        temp.repo
+            .reference("refs/heads/feature", master, true, "reset")?;
+        temp.checkout("feature")
+            .commit_file("b.txt", "b", "add b instead");
+        let repo = Repository::new(temp.path())?;
+
+        assert!(repo.push("feature", false).is_err());
+        assert_eq!(repo.push("feature", true)?, "origin/feature");
+        let local = temp.repo.revparse_single("feature")?.id();
+        assert_eq!(origin.repo.revparse_single("feature")?.id(), local);
+
+        // somebody else pushed, so our remote-tracking branch is stale
+        origin
+            .repo
+            .reference("refs/heads/feature", master, true, "push")?;
+        temp.commit_file("c.txt", "c", "add c");
+        let err = repo.push("feature", true).unwrap_err();
+        assert!(err.to_string().contains("changed since the last fetch"));
+        assert_eq!(origin.repo.revparse_single("feature")?.id(), master);
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_remote_branch() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("feature").branch("local");
+        let origin = temp.add_remote("origin");
+        temp.push("origin", "feature");
+        let repo = Repository::new(temp.path())?;
+
+        assert_eq!(repo.remote_branch("local")?, None);
+        let remote_branch = repo.remote_branch("feature")?.unwrap();
+        assert_eq!(remote_branch.to_string(), "origin/feature");
+
+        // only the branch of the same name, and not the default branch of the remote
+        temp.push("origin", "master");
+        let mut config = temp.repo.config()?;
+        config.set_str("branch.local.remote", "origin")?;
+        config.set_str("branch.local.merge", "refs/heads/master")?;
+        assert_eq!(repo.remote_branch("local")?, None);
+        temp.repo.reference_symbolic(
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/master",
+            true,
+            "test",
+        )?;
+        assert_eq!(repo.remote_branch("master")?, None);
+
+        repo.delete_remote_branch(&remote_branch)?;
+        assert!(origin.repo.find_reference("refs/heads/feature").is_err());
+        assert!(temp
+            .repo
+            .find_reference("refs/remotes/origin/feature")
+            .is_err());
+        assert!(temp.repo.find_reference("refs/heads/feature").is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_without_remote() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        let repo = Repository::new(temp.path())?;
+
+        assert!(repo.push("master", false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_with_local_upstream() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("feature");
+        let mut config = temp.repo.config()?;
+        config.set_str("branch.feature.remote", ".")?;
+        config.set_str("branch.feature.merge", "refs/heads/master")?;
+        let repo = Repository::new(temp.path())?;
+
+        let err = repo.push("feature", false).unwrap_err();
+        assert_eq!(err.to_string(), "No remote to push feature to.");
+
+        // the remote git would push to otherwise
+        let origin = temp.add_remote("origin");
+        assert_eq!(repo.push("feature", false)?, "origin/feature");
+        assert!(origin.repo.find_reference("refs/heads/feature").is_ok());
+        // the local upstream stays
+        assert_eq!(
+            temp.repo.config()?.get_string("branch.feature.merge")?,
+            "refs/heads/master"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_progress_display() {
+        let mut progress = Progress {
+            remote: "origin".into(),
+            received_objects: 5,
+            total_objects: 10,
+            received_bytes: 2048,
+            ..Default::default()
+        };
+        assert_eq!(
+            progress.to_string(),
+            "origin: Receiving objects: 50% (5/10), 2.00 KiB"
+        );
+        progress.received_objects = 10;
+        progress.indexed_deltas = 1;
+        progress.total_deltas = 4;
+        assert_eq!(progress.to_string(), "origin: Resolving deltas: 25% (1/4)");
+    }
+
+    #[test]
+    fn test_fast_forward() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("develop")
+            .branch("diverged")
+            .branch("local")
+            .branch("ahead");
+        let _origin = temp.add_remote("origin");
+        temp.push("origin", "master")
+            .push("origin", "develop")
+            .push("origin", "diverged")
+            .push("origin", "ahead");
+        // the remote moves on, as if somebody else pushed and we fetched
+        let old = temp.repo.revparse_single("master")?.id();
+        temp.commit_file("a.txt", "a", "add a");
+        let new = temp.repo.revparse_single("master")?.id();
+        for name in ["master", "develop", "diverged"] {
+            temp.repo
+                .reference(&format!("refs/remotes/origin/{}", name), new, true, "")?;
+        }
+        temp.repo
+            .reference("refs/heads/master", old, true, "reset")?;
+        temp.checkout("diverged").commit_file("b.txt", "b", "add b");
+        temp.checkout("ahead").commit_file("c.txt", "c", "add c");
+        temp.checkout("master");
+        let repo = Repository::new(temp.path())?;
+
+        let short = |oid: git2::Oid| oid.to_string()[..7].to_owned();
+        assert_eq!(
+            repo.fast_forward("develop")?,
+            SyncResult::FastForwarded(short(old), short(new))
+        );
+        assert_eq!(temp.repo.revparse_single("develop")?.id(), new);
+        assert_eq!(repo.fast_forward("develop")?, SyncResult::UpToDate);
+        assert_eq!(repo.fast_forward("diverged")?, SyncResult::Diverged(1, 1));
+        assert_eq!(repo.fast_forward("ahead")?, SyncResult::Ahead(1));
+        assert_eq!(repo.fast_forward("local")?, SyncResult::NoUpstream);
+
+        // HEAD takes the working tree along
+        assert!(matches!(
+            repo.fast_forward("master")?,
+            SyncResult::FastForwarded(..)
+        ));
+        assert_eq!(temp.repo.head()?.target(), Some(new));
+        assert!(temp.path().join("a.txt").exists());
+        assert!(temp.repo.statuses(None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fast_forward_skips_other_worktrees() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("develop");
+        let _origin = temp.add_remote("origin");
+        temp.push("origin", "develop");
+        temp.commit_file("a.txt", "a", "add a");
+        let new = temp.repo.revparse_single("master")?.id();
+        temp.repo
+            .reference("refs/remotes/origin/develop", new, true, "")?;
+        temp.worktree("wt", "develop");
+        let repo = Repository::new(temp.path())?;
+
+        assert!(repo.fast_forward("develop").is_err());
+        let results = repo.sync_branches()?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, "develop");
+        assert!(results[0].1.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fast_forward_from_linked_worktree() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("develop");
+        let _origin = temp.add_remote("origin");
+        temp.push("origin", "develop").push("origin", "master");
+        let old = temp.repo.revparse_single("master")?.id();
+        temp.commit_file("a.txt", "a", "add a");
+        let new = temp.repo.revparse_single("master")?.id();
+        temp.repo
+            .reference("refs/remotes/origin/develop", new, true, "")?;
+        temp.repo
+            .reference("refs/remotes/origin/master", new, true, "")?;
+        temp.repo.reference("refs/heads/master", old, true, "")?;
+        temp.checkout("master");
+        let worktree = temp.worktree("wt", "develop");
+        let repo = Repository::new(&worktree)?;
+
+        // the branch of this worktree is not checked out elsewhere
+        assert!(matches!(
+            repo.fast_forward("develop")?,
+            SyncResult::FastForwarded(..)
+        ));
+        assert!(worktree.join("a.txt").exists());
+        // while the main worktree's is
+        let err = repo.fast_forward("master").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("master is checked out in the worktree"));
+        assert_eq!(temp.repo.revparse_single("master")?.id(), old);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rebase() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("feature")
+            .commit_file("a.txt", "a", "add a")
+            .checkout("feature")
+            .commit_file("b.txt", "b", "add b")
+            .commit_file("c.txt", "c", "add c")
+            .checkout("master");
+        let repo = Repository::new(temp.path())?;
+
+        let mut reported = Vec::new();
+        assert_eq!(
+            repo.rebase("feature", "master", |current, total| reported
+                .push((current, total)))?,
+            RebaseStatus::Done(2)
+        );
+        assert_eq!(reported, vec![(1, 2), (2, 2)]);
+        let messages = repo
+            .logs("feature", 10, &LogFilter::default())?
+            .into_iter()
+            .map(|c| c.message)
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec!["add c", "add b", "add a", "initial commit"]);
+        assert_eq!(repo.current_branch()?, Some("feature".into()));
+        assert_eq!(repo.rebase_state()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rebase_conflict_continue() -> anyhow::Result<()> {
+        let temp = diverged_branches();
+        let repo = Repository::new(temp.path())?;
+
+        let state = match repo.rebase("feature", "master", |_, _| {})? {
+            RebaseStatus::Stopped(state) => state,
+            status => panic!("unexpected status: {:?}", status),
+        };
+        assert_eq!(state.branch, "feature");
+        assert_eq!(state.onto, "master");
+        assert_eq!((state.current, state.total), (1, 2));
+        assert!(state.commit.ends_with(" change a on feature"));
+        assert_eq!(state.conflicts, vec!["a.txt"]);
+        assert_eq!(repo.rebase_state()?, Some(state));
+
+        // still conflicted
+        assert!(matches!(
+            repo.continue_rebase(|_, _| {})?,
+            RebaseStatus::Stopped(_)
+        ));
+
+        temp.write("a.txt", "resolved").add("a.txt");
+        assert_eq!(repo.continue_rebase(|_, _| {})?, RebaseStatus::Done(2));
+        assert_eq!(repo.rebase_state()?, None);
+        assert_eq!(fs::read_to_string(temp.path().join("a.txt"))?, "resolved");
+        let messages = repo
+            .logs("feature", 3, &LogFilter::default())?
+            .into_iter()
+            .map(|c| c.message)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec!["add b", "change a on feature", "change a on master"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rebase_conflict_skip_and_abort() -> anyhow::Result<()> {
+        let temp = diverged_branches();
+        let repo = Repository::new(temp.path())?;
+        let feature = temp.repo.revparse_single("feature")?.id();
+
+        assert!(matches!(
+            repo.rebase("feature", "master", |_, _| {})?,
+            RebaseStatus::Stopped(_)
+        ));
+        repo.abort_rebase()?;
+        assert_eq!(repo.rebase_state()?, None);
+        assert_eq!(temp.repo.revparse_single("feature")?.id(), feature);
+
+        assert!(matches!(
+            repo.rebase("feature", "master", |_, _| {})?,
+            RebaseStatus::Stopped(_)
+        ));
+        // only the commit that was applied counts
+        assert_eq!(repo.skip_rebase(|_, _| {})?, RebaseStatus::Done(1));
+        let messages = repo
+            .logs("feature", 2, &LogFilter::default())?
+            .into_iter()
+            .map(|c| c.message)
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec!["add b", "change a on master"]);
+        assert_eq!(fs::read_to_string(temp.path().join("a.txt"))?, "master");
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_analysis() -> anyhow::Result<()> {
+        let temp = diverged_branches();
+        temp.branch("old");
+        temp.repo.reference(
+            "refs/heads/ahead",
+            temp.repo.revparse_single("feature")?.id(),
+            false,
+            "",
+        )?;
+        temp.checkout("feature");
+        temp.repo.reference(
+            "refs/heads/new",
+            temp.repo.revparse_single("feature~1")?.id(),
+            false,
+            "",
+        )?;
+        temp.checkout("new");
+        let repo = Repository::new(temp.path())?;
+
+        assert_eq!(repo.merge_analysis("ahead")?, MergeAnalysis::FastForward);
+        assert_eq!(repo.merge_analysis("new")?, MergeAnalysis::UpToDate);
+        assert_eq!(repo.merge_analysis("master")?, MergeAnalysis::Normal);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_strategies() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("feature")
+            .checkout("feature")
+            .commit_file("b.txt", "b", "add b")
+            .checkout("master")
+            .branch("old");
+        let repo = Repository::new(temp.path())?;
+        let feature = temp.repo.revparse_single("feature")?.id();
+
+        // a merge commit even though a fast-forward is possible
+        assert!(matches!(
+            repo.merge("feature", MergeStrategy::Commit, "merge it")?,
+            MergeOutcome::Committed(_)
+        ));
+        let head = temp.repo.head()?.peel_to_commit()?;
+        assert_eq!(head.message(), Some("merge it"));
+        assert_eq!(head.parent_count(), 2);
+        assert_eq!(head.parent_id(1)?, feature);
+        assert_eq!(temp.repo.state(), git2::RepositoryState::Clean);
+        assert_eq!(
+            repo.merge("feature", MergeStrategy::Commit, "")?,
+            MergeOutcome::UpToDate
+        );
+
+        temp.checkout("feature")
+            .commit_file("c.txt", "c", "add c")
+            .checkout("master");
+        assert!(matches!(
+            repo.merge("feature", MergeStrategy::Squash, "squashed")?,
+            MergeOutcome::Committed(_)
+        ));
+        let head = temp.repo.head()?.peel_to_commit()?;
+        assert_eq!(head.message(), Some("squashed"));
+        assert_eq!(head.parent_count(), 1);
+        assert!(temp.path().join("c.txt").exists());
+        assert_eq!(repo.merge_conflicts()?, None);
+
+        temp.checkout("old");
+        assert_eq!(
+            repo.merge("master", MergeStrategy::FastForward, "")?,
+            MergeOutcome::FastForwarded
+        );
+        assert_eq!(temp.repo.head()?.target(), Some(head.id()));
+        assert!(temp.repo.statuses(None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_conflicts() -> anyhow::Result<()> {
+        let temp = diverged_branches();
+        let repo = Repository::new(temp.path())?;
+        let master = temp.repo.revparse_single("master")?.id();
+
+        assert!(repo
+            .merge("feature", MergeStrategy::FastForward, "")
+            .is_err());
+        assert_eq!(
+            repo.merge("feature", MergeStrategy::Commit, "merge feature")?,
+            MergeOutcome::Conflicts(vec!["a.txt".into()])
+        );
+        assert_eq!(repo.merge_conflicts()?, Some(vec!["a.txt".into()]));
+        repo.abort_merge()?;
+        assert_eq!(repo.merge_conflicts()?, None);
+        assert_eq!(temp.repo.head()?.target(), Some(master));
+        assert_eq!(fs::read_to_string(temp.path().join("a.txt"))?, "master");
+
+        assert!(matches!(
+            repo.merge("feature", MergeStrategy::Squash, "squash feature")?,
+            MergeOutcome::Conflicts(_)
+        ));
+        assert_eq!(repo.merge_conflicts()?, Some(vec!["a.txt".into()]));
+        temp.write("a.txt", "resolved").add("a.txt");
+        assert!(matches!(repo.commit_merge()?, MergeOutcome::Committed(_)));
+        let head = temp.repo.head()?.peel_to_commit()?;
+        assert_eq!(head.message(), Some("squash feature"));
+        assert_eq!(head.parent_count(), 1);
+        assert_eq!(repo.merge_conflicts()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_logs_graph_order_and_decorations() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.commit_file("a.txt", "a", "add a")
+            .tag("v1")
+            .branch("feature")
+            .checkout("feature")
+            .commit_file("b.txt", "b", "add b")
+            .checkout("master")
+            .commit_file("c.txt", "c", "add c");
+        temp.merge("feature", "merge feature");
+        let repo = Repository::new(temp.path())?;
+
+        let logs = repo.logs("master", 10, &LogFilter::default())?;
+        // every child comes before its parents
+        for (i, commit) in logs.iter().enumerate() {
+            for parent in &commit.parents {
+                assert!(logs.iter().position(|c| c.oid == *parent).unwrap() > i);
+            }
+        }
+        assert_eq!(logs.len(), 5);
+        assert_eq!(logs[0].parents.len(), 2);
+        assert_eq!(logs[0].refs, vec!["HEAD -> master".to_owned()]);
+        let feature = logs
+            .iter()
+            .find(|c| c.message.starts_with("add b"))
+            .unwrap();
+        assert_eq!(feature.refs, vec!["feature".to_owned()]);
+        let tagged = logs
+            .iter()
+            .find(|c| c.message.starts_with("add a"))
+            .unwrap();
+        assert_eq!(tagged.refs, vec!["tag: v1".to_owned()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_branch_descriptions() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("feature/1-foo").branch("develop");
+        let repo = Repository::new(temp.path())?;
+        assert!(repo.branch_descriptions()?.is_empty());
+
+        repo.set_branch_description("feature/1-foo", "  Fix the login\nfor admins  ")?;
+        repo.set_branch_description("develop", "")?;
+        let descriptions = repo.branch_descriptions()?;
+        assert_eq!(descriptions.len(), 1);
+        assert_eq!(descriptions["feature/1-foo"], "Fix the login\nfor admins");
+        assert_eq!(
+            temp.repo
+                .config()?
+                .get_string("branch.feature/1-foo.description")?,
+            "Fix the login\nfor admins\n"
+        );
+
+        repo.set_branch_description("feature/1-foo", " ")?;
+        assert!(repo.branch_descriptions()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare() -> anyhow::Result<()> {
+        let temp = diverged_branches();
+        let repo = Repository::new(temp.path())?;
+
+        let comparison = repo.compare("feature", "master", 1)?;
+        assert_eq!(comparison.count_a, 2);
+        assert_eq!(comparison.count_b, 1);
+        let summaries = |commits: &[Commit]| {
+            commits
+                .iter()
+                .map(|c| c.message.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(summaries(&comparison.only_in_a), vec!["add b"]);
+        assert_eq!(summaries(&comparison.only_in_b), vec!["change a on master"]);
+        assert_eq!(comparison.only_in_b[0].refs, vec!["HEAD -> master"]);
+        assert_eq!(comparison.merge_base.unwrap().message, "add a");
+
+        // only what feature changed since it forked, not what master changed meanwhile
+        let diff = repo.diff("master", "feature")?;
+        assert!(diff.contains(&"+feature".to_owned()));
+        assert!(diff.contains(&"+++ b/b.txt".to_owned()));
+        assert!(!diff.contains(&"-master".to_owned()));
+        let diff = repo.diff("feature", "master")?;
+        assert!(diff.contains(&"+master".to_owned()));
+        assert!(!diff.iter().any(|line| line.contains("b.txt")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cherry_pick() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.commit_file("a.txt", "a", "add a")
+            .branch("feature")
+            .checkout("feature")
+            .commit_file("b.txt", "b", "add b")
+            .commit_file("c.txt", "c", "add c")
+            .commit_file("d.txt", "d", "add d")
+            .checkout("master");
+        let repo = Repository::new(temp.path())?;
+        let logs = repo.logs("feature", 3, &LogFilter::default())?;
+        let (d, b) = (logs[0].oid, logs[2].oid);
+
+        assert_eq!(repo.cherry_pick(&[b, d])?, CherryPickStatus::Done(2));
+        let head = temp.repo.head()?.peel_to_commit()?;
+        assert_eq!(head.summary(), Some("add d"));
+        assert_eq!(head.parent(0)?.summary(), Some("add b"));
+        assert_eq!(head.author().name(), Some("git-ex"));
+        assert!(temp.path().join("b.txt").exists());
+        assert!(!temp.path().join("c.txt").exists());
+        assert_eq!(temp.repo.state(), git2::RepositoryState::Clean);
+
+        // changes that are there already are not committed again, nor counted
+        assert_eq!(repo.cherry_pick(&[b])?, CherryPickStatus::Done(0));
+        assert_eq!(temp.repo.head()?.peel_to_commit()?.summary(), Some("add d"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cherry_pick_merge_commit() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("other")
+            .branch("feature")
+            .checkout("feature")
+            .commit_file("b.txt", "b", "add b");
+        temp.merge("other", "merge other");
+        temp.checkout("master");
+        let repo = Repository::new(temp.path())?;
+        let logs = repo.logs("feature", 2, &LogFilter::default())?;
+        let head = temp.repo.head()?.target();
+
+        // nothing is applied before the merge commit is noticed
+        let error = repo.cherry_pick(&[logs[1].oid, logs[0].oid]).unwrap_err();
+        assert!(error.to_string().ends_with("merge other is a merge commit"));
+        assert_eq!(temp.repo.head()?.target(), head);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cherry_pick_conflicts() -> anyhow::Result<()> {
+        let temp = diverged_branches();
+        let repo = Repository::new(temp.path())?;
+        let logs = repo.logs("feature", 2, &LogFilter::default())?;
+        let head = temp.repo.head()?.target();
+
+        let state = match repo.cherry_pick(&[logs[1].oid, logs[0].oid])? {
+            CherryPickStatus::Stopped(state) => state,
+            status => panic!("unexpected {:?}", status),
+        };
+        assert!(state.commit.ends_with(" change a on feature"));
+        assert_eq!(state.conflicts, vec!["a.txt".to_owned()]);
+        assert_eq!(state.remaining, vec![logs[0].oid]);
+        assert_eq!(temp.repo.head()?.target(), head);
+        assert_eq!(temp.repo.state(), git2::RepositoryState::CherryPick);
+        assert_eq!(
+            repo.cherry_pick_state(state.remaining.clone())?,
+            Some(state.clone())
+        );
+        assert!(repo.continue_cherry_pick(&state.remaining).is_err());
+
+        temp.write("a.txt", "resolved").add("a.txt");
+        assert_eq!(
+            repo.continue_cherry_pick(&state.remaining)?,
+            CherryPickStatus::Done(2)
+        );
+        let head = temp.repo.head()?.peel_to_commit()?;
+        assert_eq!(head.summary(), Some("add b"));
+        assert_eq!(head.parent(0)?.summary(), Some("change a on feature"));
+        assert_eq!(temp.repo.state(), git2::RepositoryState::Clean);
+        assert_eq!(repo.cherry_pick_state(Vec::new())?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_abort_cherry_pick() -> anyhow::Result<()> {
+        let temp = diverged_branches();
+        let repo = Repository::new(temp.path())?;
+        let logs = repo.logs("feature", 2, &LogFilter::default())?;
+        let head = temp.repo.head()?.target();
+
+        assert!(matches!(
+            repo.cherry_pick(&[logs[1].oid])?,
+            CherryPickStatus::Stopped(_)
+        ));
+        repo.abort_cherry_pick()?;
+        assert_eq!(temp.repo.state(), git2::RepositoryState::Clean);
+        assert_eq!(temp.repo.head()?.target(), head);
+        assert_eq!(
+            std::fs::read_to_string(temp.path().join("a.txt"))?,
+            "master"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_common_dir() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("develop");
+        let worktree = temp.worktree("wt", "develop");
+
+        let repo = Repository::new(temp.path())?;
+        assert_eq!(repo.common_dir(), repo.git_dir());
+
+        let linked = Repository::new(worktree)?;
+        assert_ne!(linked.git_dir(), repo.git_dir());
+        assert_eq!(
+            linked.common_dir().canonicalize()?,
+            repo.git_dir().canonicalize()?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkout() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("develop")
+            .checkout("develop")
+            .commit_file("a.txt", "a", "add a")
+            .checkout("master");
+        let repo = Repository::new(temp.path())?;
+        assert!(!temp.path().join("a.txt").exists());
+
+        repo.checkout("develop")?;
+
+        assert_eq!(repo.current_branch()?, Some("develop".into()));
+        assert_eq!(std::fs::read_to_string(temp.path().join("a.txt"))?, "a");
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkout_keeps_conflicting_changes() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.commit_file("a.txt", "a", "add a")
+            .branch("develop")
+            .checkout("develop")
+            .commit_file("a.txt", "b", "change a")
+            .checkout("master")
+            .write("a.txt", "dirty");
+        let repo = Repository::new(temp.path())?;
+
+        assert!(repo.checkout("develop").is_err());
+        assert_eq!(repo.current_branch()?, Some("master".into()));
+        assert_eq!(std::fs::read_to_string(temp.path().join("a.txt"))?, "dirty");
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkout_new_branch() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.commit_file("a.txt", "a", "add a");
+        let repo = Repository::new(temp.path())?;
+
+        let branch = repo.checkout_new_branch("feature/1-foo")?;
+
+        assert_eq!(branch.name()?, Some("feature/1-foo"));
+        assert_eq!(
+            branch.get().target(),
+            temp.repo.head()?.target(),
+            "the new branch starts at HEAD"
+        );
+        // creating a branch does not switch to it
+        assert_eq!(repo.current_branch()?, Some("master".into()));
+        assert!(repo.checkout_new_branch("feature/1-foo").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_start_branch() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        let repo = Repository::new(temp.path())?;
+
+        repo.start_branch("feature/1-foo", Some("Fix the login"))?;
+        assert_eq!(repo.current_branch()?, Some("feature/1-foo".into()));
+        assert_eq!(
+            repo.branch_descriptions()?
+                .get("feature/1-foo")
+                .map(String::as_str),
+            Some("Fix the login")
+        );
+
+        repo.start_branch("feature/2-bar", None)?;
+        assert_eq!(repo.current_branch()?, Some("feature/2-bar".into()));
+        assert!(!repo.branch_descriptions()?.contains_key("feature/2-bar"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_branch() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("develop").branch("in-worktree");
+        temp.worktree("wt", "in-worktree");
+        let repo = Repository::new(temp.path())?;
+
+        repo.delete_branch("develop")?;
+        assert_eq!(repo.branches()?, vec!["in-worktree", "master"]);
+
+        // branches checked out here or in a worktree are kept
+        assert!(repo.delete_branch("master").is_err());
+        assert!(repo.delete_branch("in-worktree").is_err());
+        assert!(repo.delete_branch("nothing").is_err());
+        assert_eq!(repo.branches()?, vec!["in-worktree", "master"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,421 @@
+//! Committing the index and rewording commits, running the hooks git runs for `git commit`.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::anyhow;
+
+use super::{Repository, Signature};
+
+impl Repository {
+    /// Commits the staged changes on top of HEAD with the configured signature.
+    /// The `pre-commit` hook runs first, then `commit-msg`, which may edit the message.
+    /// Comment lines starting with `#` are removed from the message.
+    /// Returns the abbreviated id of the new commit.
+    pub fn commit(&self, message: &str) -> anyhow::Result<String> {
+        self.ensure_clean_state()?;
+        if !self.conflicts()?.is_empty() {
+            return Err(anyhow!("Resolve and stage the conflicted files first."));
+        }
+        let head = self.repo.head().and_then(|head| head.peel_to_commit()).ok();
+        let mut index = self.repo.index()?;
+        let tree = index.write_tree()?;
+        if head.as_ref().map(|head| head.tree_id()) == Some(tree) {
+            return Err(anyhow!("Nothing is staged to commit."));
+        }
+        let message = cleanup_message(message)?;
+
+        self.run_hook("pre-commit", &[])?;
+        let message = self.commit_msg_hook(&message)?;
+
+        let id = self.commit_index(&message, &head.iter().collect::<Vec<_>>())?;
+        self.short_id(id)
+    }
+
+    /// Replaces HEAD with a commit of the index, like `git commit --amend`. The message
+    /// stays unless a new one is given, and the author is kept.
+    pub fn amend(&self, message: Option<&str>) -> anyhow::Result<String> {
+        self.ensure_clean_state()?;
+        if !self.conflicts()?.is_empty() {
+            return Err(anyhow!("Resolve and stage the conflicted files first."));
+        }
+        let head = self.repo.head()?.peel_to_commit()?;
+        let tree = self.repo.find_tree(self.repo.index()?.write_tree()?)?;
+        if tree.id() == head.tree_id() && message.is_none() {
+            return Err(anyhow!("Nothing is staged to amend HEAD with."));
+        }
+        let message = match message {
+            Some(message) => cleanup_message(message)?,
+            None => String::from_utf8_lossy(head.message_bytes()).into_owned(),
+        };
+
+        self.run_hook("pre-commit", &[])?;
+        let message = self.commit_msg_hook(&message)?;
+        // the hook may have staged more
+        let mut index = self.repo.index()?;
+        index.read(false)?;
+        let tree = self.repo.find_tree(index.write_tree()?)?;
+
+        let id = head.amend(
+            Some("HEAD"),
+            None,
+            Some(&self.repo.signature()?),
+            None,
+            Some(&message),
+            Some(&tree),
+        )?;
+        self.short_id(id)
+    }
+
+    /// Changes the message of a commit of a branch, rebuilding the commits on top of it.
+    /// Their trees stay the same, so nothing can conflict. Returns the new id of the commit.
+    pub fn reword(
+        &self,
+        branch_name: &str,
+        id: git2::Oid,
+        message: &str,
+    ) -> anyhow::Result<String> {
+        let mut branch = self
+            .repo
+            .find_branch(branch_name, git2::BranchType::Local)?;
+        let tip = branch
+            .get()
+            .target()
+            .ok_or_else(|| anyhow!("{} has no commit", branch_name))?;
+        self.ensure_clean_state()?;
+        if tip != id && !self.repo.graph_descendant_of(tip, id)? {
+            return Err(anyhow!("{} is not on {}", self.short_id(id)?, branch_name));
+        }
+        let message = self.commit_msg_hook(&cleanup_message(message)?)?;
+        let committer = self.repo.signature()?;
+
+        let commit = self.repo.find_commit(id)?;
+        let reworded = self.repo.commit(
+            None,
+            &commit.author(),
+            &committer,
+            &message,
+            &commit.tree()?,
+            &commit
+                .parents()
+                .collect::<Vec<_>>()
+                .iter()
+                .collect::<Vec<_>>(),
+        )?;
+
+        // rebuild every commit the reworded one is an ancestor of, oldest first
+        let mut rewritten = HashMap::from([(id, reworded)]);
+        let mut walk = self.repo.revwalk()?;
+        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+        walk.push(tip)?;
+        walk.hide(id)?;
+        for oid in walk {
+            let commit = self.repo.find_commit(oid?)?;
+            if !commit
+                .parent_ids()
+                .any(|parent| rewritten.contains_key(&parent))
+            {
+                continue;
+            }
+            let parents = commit
+                .parent_ids()
+                .map(|parent| {
+                    self.repo
+                        .find_commit(rewritten.get(&parent).copied().unwrap_or(parent))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let new_id = self.repo.commit(
+                None,
+                &commit.author(),
+                &committer,
+                &String::from_utf8_lossy(commit.message_bytes()),
+                &commit.tree()?,
+                &parents.iter().collect::<Vec<_>>(),
+            )?;
+            rewritten.insert(commit.id(), new_id);
+        }
+
+        branch.get_mut().set_target(
+            rewritten[&tip],
+            &format!("git-ex: reword {}", self.short_id(id)?),
+        )?;
+        self.short_id(reworded)
+    }
+
+    /// Whether the commit is on the upstream of the branch, so changing it rewrites
+    /// published history.
+    pub fn is_pushed(&self, branch_name: &str, id: git2::Oid) -> anyhow::Result<bool> {
+        let branch = self
+            .repo
+            .find_branch(branch_name, git2::BranchType::Local)?;
+        let upstream = match branch.upstream() {
+            Ok(upstream) => upstream.get().target(),
+            Err(_) => None,
+        };
+        Ok(match upstream {
+            Some(upstream) => upstream == id || self.repo.graph_descendant_of(upstream, id)?,
+            None => false,
+        })
+    }
+
+    pub fn head_id(&self) -> anyhow::Result<git2::Oid> {
+        Ok(self.repo.head()?.peel_to_commit()?.id())
+    }
+
+    /// Runs the `commit-msg` hook on the message, returning the message the hook leaves.
+    fn commit_msg_hook(&self, message: &str) -> anyhow::Result<String> {
+        let message_file = self.git_dir().join("COMMIT_EDITMSG");
+        std::fs::write(&message_file, message)?;
+        self.run_hook("commit-msg", &[&message_file])?;
+        cleanup_message(&std::fs::read_to_string(&message_file)?)
+    }
+
+    /// Fails while a merge, rebase, cherry-pick or the like is in progress, as a new
+    /// commit would get in its way.
+    fn ensure_clean_state(&self) -> anyhow::Result<()> {
+        if self.repo.state() != git2::RepositoryState::Clean {
+            return Err(anyhow!(
+                "A {:?} is in progress, finish or abort it first.",
+                self.repo.state()
+            ));
+        }
+        Ok(())
+    }
+
+    fn short_id(&self, id: git2::Oid) -> anyhow::Result<String> {
+        Ok(self
+            .repo
+            .find_object(id, None)?
+            .short_id()?
+            .as_str()
+            .unwrap_or_default()
+            .to_owned())
+    }
+
+    /// The author and committer of new commits, from `user.name` and `user.email`.
+    pub fn signature(&self) -> anyhow::Result<Signature> {
+        let signature = self.repo.signature()?;
+        Ok(Signature {
+            name: String::from_utf8_lossy(signature.name_bytes()).into(),
+            email: String::from_utf8_lossy(signature.email_bytes()).into(),
+        })
+    }
+
+    /// Runs a hook from `core.hooksPath` or `.git/hooks` if it is there and executable,
+    /// failing with its output if it exits with an error.
+    fn run_hook(&self, name: &str, args: &[&Path]) -> anyhow::Result<()> {
+        let hook = self.hooks_dir()?.join(name);
+        if !is_executable(&hook) {
+            return Ok(());
+        }
+        let output = Command::new(&hook)
+            .args(args)
+            .current_dir(self.repo.workdir().unwrap_or_else(|| self.git_dir()))
+            .env("GIT_INDEX_FILE", self.git_dir().join("index"))
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| anyhow!("{} hook: {}", name, e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            let mut out = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+            if out.is_empty() {
+                out = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+            }
+            Err(anyhow!("{} hook failed: {}", name, out))
+        }
+    }
+
+    fn hooks_dir(&self) -> anyhow::Result<PathBuf> {
+        Ok(match self.config_string("core.hooksPath")? {
+            // relative paths are relative to where hooks run
+            Some(path) => self
+                .repo
+                .workdir()
+                .unwrap_or_else(|| self.git_dir())
+                .join(path),
+            None => self.common_dir().join("hooks"),
+        })
+    }
+}
+
+/// Strips comments and surrounding blank lines, like `git commit --cleanup=strip`.
+fn cleanup_message(message: &str) -> anyhow::Result<String> {
+    let message = git2::message_prettify(message, Some(b'#'))?;
+    if message.trim().is_empty() {
+        Err(anyhow!("Aborting commit due to empty commit message."))
+    } else {
+        Ok(message)
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempRepo;
+
+    #[cfg(unix)]
+    fn write_hook(temp: &TempRepo, name: &str, script: &str) {
+        use std::os::unix::fs::PermissionsExt;
+        let path = temp.repo.path().join("hooks").join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_commit() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        let repo = Repository::new(temp.path())?;
+        assert!(repo.commit("nothing").is_err());
+
+        std::fs::write(temp.path().join("a.txt"), "a")?;
+        repo.stage("a.txt")?;
+        assert!(repo.commit("# only a comment\n").is_err());
+        let id = repo.commit("Add a\n\nThe body.\n# a comment\n")?;
+
+        let head = temp.repo.head()?.peel_to_commit()?;
+        assert!(head.id().to_string().starts_with(&id));
+        assert_eq!(head.message(), Some("Add a\n\nThe body.\n"));
+        assert_eq!(head.author().name(), Some("git-ex"));
+        assert_eq!(head.parent_count(), 1);
+        assert_eq!(repo.signature()?.to_string(), "git-ex <git-ex@example.com>");
+        assert!(repo.working_tree_status()?.is_clean());
+        Ok(())
+    }
+
+    #[test]
+    fn test_amend() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.commit_file("a.txt", "a", "Add a");
+        let repo = Repository::new(temp.path())?;
+        let before = temp.repo.head()?.peel_to_commit()?;
+        assert!(repo.amend(None).is_err());
+
+        std::fs::write(temp.path().join("b.txt"), "b")?;
+        repo.stage("b.txt")?;
+        repo.amend(None)?;
+        let head = temp.repo.head()?.peel_to_commit()?;
+        assert_ne!(head.id(), before.id());
+        assert_eq!(head.message(), Some("Add a\n"));
+        assert_eq!(head.parent_id(0)?, before.parent_id(0)?);
+        assert!(head.tree()?.get_name("b.txt").is_some());
+        assert_eq!(head.author().when(), before.author().when());
+
+        repo.amend(Some("Add a and b"))?;
+        let head = temp.repo.head()?.peel_to_commit()?;
+        assert_eq!(head.message(), Some("Add a and b\n"));
+        assert!(repo.working_tree_status()?.is_clean());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reword() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("feature")
+            .checkout("feature")
+            .commit_file("a.txt", "a", "Add a")
+            .commit_file("b.txt", "b", "Add b")
+            .commit_file("c.txt", "c", "Add c");
+        let repo = Repository::new(temp.path())?;
+        let a = temp.repo.revparse_single("feature~2")?.id();
+        let tree = temp.repo.head()?.peel_to_tree()?.id();
+
+        repo.reword("feature", a, "Add the letter a")?;
+        let messages = |temp: &TempRepo| {
+            let mut walk = temp.repo.revwalk().unwrap();
+            walk.push_head().unwrap();
+            walk.map(|oid| {
+                temp.repo
+                    .find_commit(oid.unwrap())
+                    .unwrap()
+                    .message()
+                    .unwrap()
+                    .to_owned()
+            })
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            messages(&temp),
+            vec!["Add c", "Add b", "Add the letter a\n", "initial commit"]
+        );
+        assert_eq!(temp.repo.head()?.peel_to_tree()?.id(), tree);
+        assert_eq!(temp.repo.head()?.shorthand(), Some("feature"));
+
+        // HEAD itself
+        let head = temp.repo.head()?.peel_to_commit()?.id();
+        repo.reword("feature", head, "Add the letter c")?;
+        assert_eq!(messages(&temp)[0], "Add the letter c\n");
+
+        // not on the branch
+        assert!(repo.reword("master", head, "x").is_err());
+
+        // nor while a merge is in progress
+        std::fs::write(temp.repo.path().join("MERGE_HEAD"), format!("{}\n", a))?;
+        let error = repo.reword("feature", head, "x").unwrap_err();
+        assert!(error.to_string().contains("in progress"));
+        assert_eq!(messages(&temp)[0], "Add the letter c\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_pushed() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        let _origin = temp.add_remote("origin");
+        temp.commit_file("a.txt", "a", "Add a")
+            .push("origin", "master")
+            .commit_file("b.txt", "b", "Add b");
+        let repo = Repository::new(temp.path())?;
+        let head = temp.repo.head()?.peel_to_commit()?;
+
+        assert!(!repo.is_pushed("master", head.id())?);
+        assert!(repo.is_pushed("master", head.parent_id(0)?)?);
+        temp.branch("local");
+        assert!(!repo.is_pushed("local", head.parent_id(0)?)?);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commit_hooks() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        let repo = Repository::new(temp.path())?;
+        std::fs::write(temp.path().join("a.txt"), "a")?;
+        repo.stage("a.txt")?;
+
+        write_hook(
+            &temp,
+            "pre-commit",
+            "#!/bin/sh\necho 'lint failed' >&2\nexit 1\n",
+        );
+        let e = repo.commit("Add a").unwrap_err();
+        assert_eq!(e.to_string(), "pre-commit hook failed: lint failed");
+
+        write_hook(&temp, "pre-commit", "#!/bin/sh\nexit 0\n");
+        write_hook(
+            &temp,
+            "commit-msg",
+            "#!/bin/sh\necho 'Refs: #12' >> \"$1\"\n",
+        );
+        repo.commit("Add a")?;
+        let head = temp.repo.head()?.peel_to_commit()?;
+        assert_eq!(head.message(), Some("Add a\nRefs: #12\n"));
+        Ok(())
+    }
+}
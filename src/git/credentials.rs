@@ -0,0 +1,30 @@
+//! Authentication for talking to remotes, the way the `git` command does it.
+
+use std::cell::Cell;
+
+/// Answers credential requests with the SSH agent for SSH URLs and the configured
+/// credential helpers for HTTPS URLs. Each method is tried once, so a rejected
+/// credential fails the operation instead of asking again forever.
+pub fn callbacks<'a>(config: git2::Config) -> git2::RemoteCallbacks<'a> {
+    let tried_agent = Cell::new(false);
+    let tried_helper = Cell::new(false);
+    let mut callbacks = git2::RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        if allowed.contains(git2::CredentialType::USERNAME) {
+            return git2::Cred::username(username.unwrap_or("git"));
+        }
+        if allowed.contains(git2::CredentialType::SSH_KEY) && !tried_agent.replace(true) {
+            return git2::Cred::ssh_key_from_agent(username.unwrap_or("git"));
+        }
+        if allowed.contains(git2::CredentialType::USER_PASS_PLAINTEXT)
+            && !tried_helper.replace(true)
+        {
+            return git2::Cred::credential_helper(&config, url, username);
+        }
+        Err(git2::Error::from_str(&format!(
+            "authentication failed for {}",
+            url
+        )))
+    });
+    callbacks
+}
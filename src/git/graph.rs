@@ -0,0 +1,164 @@
+//! Lays out commits in lanes for a one-line-per-commit graph, like `git log --graph`.
+
+use super::Commit;
+
+const COMMIT: char = '●';
+const VERTICAL: char = '│';
+const HORIZONTAL: char = '─';
+const CROSSING: char = '┼';
+
+/// Where a lane meets the lane of the commit on its row.
+#[derive(Clone, Copy)]
+enum Junction {
+    /// A child's lane ends in this commit.
+    Close,
+    /// A new lane starts for a parent of this merge commit.
+    Open,
+    /// A parent of this merge commit is already expected in this lane.
+    Join,
+}
+
+impl Junction {
+    fn symbol(self, right: bool) -> char {
+        match (self, right) {
+            (Junction::Close, true) => '╯',
+            (Junction::Close, false) => '╰',
+            (Junction::Open, true) => '╮',
+            (Junction::Open, false) => '╭',
+            (Junction::Join, true) => '┤',
+            (Junction::Join, false) => '├',
+        }
+    }
+}
+
+/// The graph column of each commit, in the same order. `commits` are expected in topological
+/// order, children before their parents. Every lane takes two characters.
+pub fn graph(commits: &[Commit]) -> Vec<String> {
+    // the commit each lane waits for
+    let mut lanes: Vec<Option<git2::Oid>> = Vec::new();
+    commits
+        .iter()
+        .map(|commit| {
+            let column = match lanes.iter().position(|lane| *lane == Some(commit.oid)) {
+                Some(column) => column,
+                None => free_lane(&mut lanes, 0),
+            };
+            let mut junctions = Vec::new();
+            for (i, lane) in lanes.iter_mut().enumerate() {
+                if i != column && *lane == Some(commit.oid) {
+                    *lane = None;
+                    junctions.push((i, Junction::Close));
+                }
+            }
+            // lanes still passing by, before the parents take free ones
+            let passing = lanes.iter().map(Option::is_some).collect::<Vec<_>>();
+
+            lanes[column] = commit.parents.first().copied();
+            for parent in commit.parents.iter().skip(1) {
+                match lanes.iter().position(|lane| *lane == Some(*parent)) {
+                    Some(i) => junctions.push((i, Junction::Join)),
+                    None => {
+                        let i = free_lane(&mut lanes, column + 1);
+                        lanes[i] = Some(*parent);
+                        junctions.push((i, Junction::Open));
+                    }
+                }
+            }
+
+            let width = passing.len().max(lanes.len());
+            let mut row = (0..width)
+                .flat_map(|i| {
+                    let symbol = if passing.get(i).copied().unwrap_or(false) {
+                        VERTICAL
+                    } else {
+                        ' '
+                    };
+                    [symbol, ' ']
+                })
+                .collect::<Vec<_>>();
+            for (i, junction) in junctions {
+                let (from, to) = (column.min(i), column.max(i));
+                for cell in row.iter_mut().take(to * 2).skip(from * 2 + 1) {
+                    *cell = match *cell {
+                        VERTICAL => CROSSING,
+                        ' ' => HORIZONTAL,
+                        other => other,
+                    };
+                }
+                row[i * 2] = junction.symbol(i > column);
+            }
+            row[column * 2] = COMMIT;
+
+            while lanes.last() == Some(&None) {
+                lanes.pop();
+            }
+            row.into_iter().collect::<String>().trim_end().to_owned()
+        })
+        .collect()
+}
+
+/// The first lane from `start` that waits for no commit, added if there is none.
+fn free_lane(lanes: &mut Vec<Option<git2::Oid>>, start: usize) -> usize {
+    match lanes.iter().skip(start).position(Option::is_none) {
+        Some(i) => start + i,
+        None => {
+            lanes.resize(lanes.len().max(start), None);
+            lanes.push(None);
+            lanes.len() - 1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::Signature;
+
+    fn commit(id: u8, parents: &[u8]) -> Commit {
+        let oid = |n: u8| git2::Oid::from_bytes(&[n; 20]).unwrap();
+        Commit {
+            oid: oid(id),
+            id: id.to_string(),
+            parents: parents.iter().map(|n| oid(*n)).collect(),
+            refs: Vec::new(),
+            author: Signature {
+                name: String::new(),
+                email: String::new(),
+            },
+            message: String::new(),
+            datetime: chrono::Local::now(),
+        }
+    }
+
+    #[test]
+    fn test_linear() {
+        let commits = [commit(3, &[2]), commit(2, &[1]), commit(1, &[])];
+        assert_eq!(graph(&commits), vec!["●", "●", "●"]);
+    }
+
+    #[test]
+    fn test_merge() {
+        // 5 merges 4 (feature) into 3 (master), both branched off 2
+        let commits = [
+            commit(5, &[3, 4]),
+            commit(4, &[2]),
+            commit(3, &[2]),
+            commit(2, &[1]),
+            commit(1, &[]),
+        ];
+        assert_eq!(graph(&commits), vec!["●─╮", "│ ●", "● │", "●─╯", "●"]);
+    }
+
+    #[test]
+    fn test_branch_tips() {
+        // 3 and 5 get lanes of their own, which close at their parents 2 and 1
+        let commits = [
+            commit(4, &[2]),
+            commit(3, &[2]),
+            commit(5, &[1]),
+            commit(2, &[1]),
+            commit(1, &[]),
+        ];
+        assert_eq!(graph(&commits), vec!["●", "│ ●", "│ │ ●", "●─╯ │", "●───╯"]);
+    }
+}
@@ -0,0 +1,237 @@
+//! Which commits `Repository::logs` lists, like the limiting options of `git log`.
+
+use std::path::Path;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+/// The format dates are shown in, which `parse_date` reads back.
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+/// Text patterns match case-insensitively anywhere in the text.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LogFilter {
+    /// Matches the name or email of the author.
+    pub author: Option<String>,
+    /// Matches the name or email of the committer.
+    pub committer: Option<String>,
+    pub since: Option<DateTime<Local>>,
+    pub until: Option<DateTime<Local>>,
+    /// Only commits changing this file or directory, compared with their first parent.
+    pub path: Option<String>,
+    /// Matches the commit message.
+    pub grep: Option<String>,
+    /// Follow only the first parent of merge commits.
+    pub first_parent: bool,
+    pub no_merges: bool,
+}
+
+impl LogFilter {
+    /// Parses the filter bar of the log view: `author:`, `committer:`, `since:`, `until:`,
+    /// `path:` and `grep:` followed by a value, the flags `first-parent` and `no-merges`,
+    /// and other words to find in the message.
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let now = Local::now();
+        let mut filter = Self::default();
+        let mut words = Vec::new();
+        for token in s.split_whitespace() {
+            match token.split_once(':') {
+                Some(("author", value)) => filter.author = Some(value.into()),
+                Some(("committer", value)) => filter.committer = Some(value.into()),
+                Some(("since", value)) => filter.since = Some(parse_date(value, now)?),
+                Some(("until", value)) => filter.until = Some(parse_date(value, now)?),
+                Some(("path", value)) => filter.path = Some(value.into()),
+                Some(("grep", value)) => words.push(value),
+                _ => match token.trim_start_matches("--") {
+                    "first-parent" => filter.first_parent = true,
+                    "no-merges" => filter.no_merges = true,
+                    _ => words.push(token),
+                },
+            }
+        }
+        if !words.is_empty() {
+            filter.grep = Some(words.join(" "));
+        }
+        Ok(filter)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether commits of the history are left out, so it cannot be drawn as a graph.
+    pub fn hides_commits(&self) -> bool {
+        Self {
+            first_parent: false,
+            ..self.clone()
+        } != Self::default()
+    }
+
+    pub fn matches(&self, commit: &git2::Commit) -> bool {
+        let signature = |signature: git2::Signature| {
+            format!(
+                "{} <{}>",
+                String::from_utf8_lossy(signature.name_bytes()),
+                String::from_utf8_lossy(signature.email_bytes())
+            )
+        };
+        let time = Local.timestamp(commit.time().seconds(), 0);
+        self.author
+            .as_ref()
+            .is_none_or(|pattern| contains(&signature(commit.author()), pattern))
+            && self
+                .committer
+                .as_ref()
+                .is_none_or(|pattern| contains(&signature(commit.committer()), pattern))
+            && self.since.is_none_or(|since| since <= time)
+            && self.until.is_none_or(|until| time <= until)
+            && self.grep.as_ref().is_none_or(|pattern| {
+                contains(&String::from_utf8_lossy(commit.message_bytes()), pattern)
+            })
+            && !(self.no_merges && commit.parent_count() > 1)
+            && self
+                .path
+                .as_ref()
+                .is_none_or(|path| changes_path(commit, Path::new(path)))
+    }
+}
+
+impl std::fmt::Display for LogFilter {
+    /// The filter the way `parse` reads it.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut tokens = Vec::new();
+        let mut push = |key: &str, value: &Option<String>| {
+            if let Some(value) = value {
+                tokens.push(format!("{}:{}", key, value));
+            }
+        };
+        push("author", &self.author);
+        push("committer", &self.committer);
+        push(
+            "since",
+            &self.since.map(|d| d.format(DATE_FORMAT).to_string()),
+        );
+        push(
+            "until",
+            &self.until.map(|d| d.format(DATE_FORMAT).to_string()),
+        );
+        push("path", &self.path);
+        if let Some(grep) = &self.grep {
+            tokens.extend(grep.split_whitespace().map(|word| format!("grep:{}", word)));
+        }
+        if self.first_parent {
+            tokens.push("first-parent".into());
+        }
+        if self.no_merges {
+            tokens.push("no-merges".into());
+        }
+        write!(f, "{}", tokens.join(" "))
+    }
+}
+
+fn contains(text: &str, pattern: &str) -> bool {
+    text.to_lowercase().contains(&pattern.to_lowercase())
+}
+
+/// Whether the file or directory at `path` differs from every parent, the way git's default
+/// history simplification picks commits for `git log -- <path>`. A merge that took the path
+/// as it was on one of its parents doesn't change it.
+fn changes_path(commit: &git2::Commit, path: &Path) -> bool {
+    let entry_id = |tree: git2::Tree| tree.get_path(path).ok().map(|entry| entry.id());
+    let ours = commit.tree().ok().and_then(entry_id);
+    if commit.parent_count() == 0 {
+        return ours.is_some();
+    }
+    commit
+        .parents()
+        .all(|parent| parent.tree().ok().and_then(entry_id) != ours)
+}
+
+/// Parses dates like `--since` takes them: `2024-01-31`, `2024-01-31T12:00`, `today`,
+/// `yesterday`, or an age like `3.days`, `2 weeks ago`, `1.month`.
+pub fn parse_date(s: &str, now: DateTime<Local>) -> anyhow::Result<DateTime<Local>> {
+    let s = s.trim();
+    let midnight = |date: NaiveDate| {
+        Local
+            .from_local_datetime(&date.and_hms(0, 0, 0))
+            .earliest()
+            .ok_or_else(|| anyhow!("invalid date: {}", s))
+    };
+    match s {
+        "now" => return Ok(now),
+        "today" => return midnight(now.date_naive()),
+        "yesterday" => return midnight(now.date_naive() - Duration::days(1)),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return midnight(date);
+    }
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(s, DATE_FORMAT) {
+        return Local
+            .from_local_datetime(&datetime)
+            .earliest()
+            .ok_or_else(|| anyhow!("invalid date: {}", s));
+    }
+
+    let age = s.trim_end_matches("ago").trim_end().replace('.', " ");
+    let (count, unit) = age
+        .split_once(' ')
+        .ok_or_else(|| anyhow!("invalid date: {}", s))?;
+    let count = count
+        .parse::<i32>()
+        .map_err(|_| anyhow!("invalid date: {}", s))?;
+    let unit = match unit.trim().trim_end_matches('s') {
+        "minute" => Duration::minutes(1),
+        "hour" => Duration::hours(1),
+        "day" => Duration::days(1),
+        "week" => Duration::weeks(1),
+        "month" => Duration::days(30),
+        "year" => Duration::days(365),
+        _ => return Err(anyhow!("invalid date: {}", s)),
+    };
+    Ok(now - unit * count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() -> anyhow::Result<()> {
+        let now = Local.ymd(2024, 3, 10).and_hms(15, 30, 0);
+        let at = |d, h, m| Local.ymd(2024, 3, d).and_hms(h, m, 0);
+
+        assert_eq!(parse_date("2024-03-01", now)?, at(1, 0, 0));
+        assert_eq!(parse_date("2024-03-01T09:15", now)?, at(1, 9, 15));
+        assert_eq!(parse_date("today", now)?, at(10, 0, 0));
+        assert_eq!(parse_date("yesterday", now)?, at(9, 0, 0));
+        assert_eq!(parse_date("1.week", now)?, at(3, 15, 30));
+        assert_eq!(parse_date("2 days ago", now)?, at(8, 15, 30));
+        assert_eq!(parse_date("3.hours", now)?, at(10, 12, 30));
+        assert!(parse_date("last tuesday", now).is_err());
+        assert!(parse_date("soon", now).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse() -> anyhow::Result<()> {
+        let filter = LogFilter::parse("author:alice path:src/git no-merges fix typo")?;
+        assert_eq!(filter.author.as_deref(), Some("alice"));
+        assert_eq!(filter.path.as_deref(), Some("src/git"));
+        assert_eq!(filter.grep.as_deref(), Some("fix typo"));
+        assert!(filter.no_merges);
+        assert!(!filter.first_parent);
+        assert!(filter.hides_commits());
+
+        let filter = LogFilter::parse("--first-parent since:2024-03-01")?;
+        assert!(filter.first_parent);
+        assert!(filter.since.is_some());
+        assert_eq!(LogFilter::parse(&filter.to_string())?, filter);
+
+        let filter = LogFilter::parse("  ")?;
+        assert!(filter.is_empty());
+        assert!(!LogFilter::parse("first-parent")?.hides_commits());
+        assert!(LogFilter::parse("since:someday").is_err());
+        Ok(())
+    }
+}
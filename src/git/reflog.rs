@@ -0,0 +1,212 @@
+//! Reading the reflogs of HEAD and of branches, and going back to where HEAD was.
+
+use anyhow::anyhow;
+use chrono::TimeZone;
+
+use super::{Repository, Signature};
+
+/// One line of a reflog, newest first like `git reflog` lists them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReflogEntry {
+    /// How `git` refers to the entry, e.g. `HEAD@{2}`.
+    pub selector: String,
+    /// Where the ref pointed after the update.
+    pub oid: git2::Oid,
+    /// The abbreviated id, for display.
+    pub id: String,
+    /// What moved the ref, e.g. `checkout` or `commit (amend)`.
+    pub action: String,
+    pub message: String,
+    pub committer: Signature,
+    pub datetime: chrono::DateTime<chrono::Local>,
+}
+
+impl ReflogEntry {
+    fn new(repo: &git2::Repository, name: &str, index: usize, entry: &git2::ReflogEntry) -> Self {
+        let line = entry.message().unwrap_or_default();
+        let (action, message) = match line.split_once(": ") {
+            Some((action, message)) => (action, message),
+            None => ("", line),
+        };
+        let id = repo
+            .find_object(entry.id_new(), None)
+            .ok()
+            .and_then(|object| object.short_id().ok())
+            .and_then(|id| id.as_str().map(ToOwned::to_owned))
+            .unwrap_or_else(|| entry.id_new().to_string()[..7].to_owned());
+        let committer = entry.committer();
+        Self {
+            selector: format!("{}@{{{}}}", name, index),
+            oid: entry.id_new(),
+            id,
+            action: action.to_owned(),
+            message: message.to_owned(),
+            committer: Signature {
+                name: String::from_utf8_lossy(committer.name_bytes()).into(),
+                email: String::from_utf8_lossy(committer.email_bytes()).into(),
+            },
+            datetime: chrono::Local.timestamp(committer.when().seconds(), 0),
+        }
+    }
+
+    /// The branches of a `checkout: moving from X to Y` entry, `(X, Y)`.
+    pub fn checkout(&self) -> Option<(&str, &str)> {
+        if self.action != "checkout" {
+            return None;
+        }
+        self.message
+            .strip_prefix("moving from ")
+            .and_then(|moves| moves.split_once(" to "))
+    }
+}
+
+impl Repository {
+    /// Up to `limit` entries of the reflog of HEAD, or of a local branch.
+    pub fn reflog(&self, name: &str, limit: usize) -> anyhow::Result<Vec<ReflogEntry>> {
+        let refname = if name == "HEAD" {
+            name.to_owned()
+        } else {
+            let branch = self.repo.find_branch(name, git2::BranchType::Local)?;
+            branch.get().name().unwrap().to_owned()
+        };
+        let reflog = self.repo.reflog(&refname)?;
+        Ok(reflog
+            .iter()
+            .take(limit)
+            .enumerate()
+            .map(|(index, entry)| ReflogEntry::new(&self.repo, name, index, &entry))
+            .collect())
+    }
+
+    /// The branches HEAD was on before, most recent first. Unlike `@{-1}`, `@{-2}`, …,
+    /// each branch is listed once, without the current one and the deleted ones.
+    pub fn previous_branches(&self) -> anyhow::Result<Vec<String>> {
+        let current = self.current_branch()?;
+        let branches = self.branches()?;
+        let mut previous: Vec<String> = Vec::new();
+        for entry in self.reflog("HEAD", usize::MAX)? {
+            if let Some((from, _)) = entry.checkout() {
+                if Some(from) != current.as_deref()
+                    && branches.iter().any(|branch| branch == from)
+                    && !previous.iter().any(|branch| branch == from)
+                {
+                    previous.push(from.to_owned());
+                }
+            }
+        }
+        Ok(previous)
+    }
+
+    /// Switches to the `n`-th branch of `previous_branches`, counting from 1.
+    pub fn checkout_previous(&self, n: usize) -> anyhow::Result<String> {
+        let branches = self.previous_branches()?;
+        let branch = n
+            .checked_sub(1)
+            .and_then(|i| branches.get(i))
+            .ok_or_else(|| anyhow!("No branch was checked out {} switch(es) ago.", n))?;
+        self.checkout(branch)?;
+        Ok(branch.to_owned())
+    }
+
+    /// Checks out a commit, detaching HEAD.
+    pub fn checkout_detached(&self, oid: git2::Oid) -> anyhow::Result<()> {
+        let commit = self.repo.find_commit(oid)?;
+        self.repo.checkout_tree(commit.as_object(), None)?;
+        self.repo.set_head_detached(oid)?;
+        Ok(())
+    }
+
+    /// Creates a branch at a commit and checks it out.
+    /// The branch is deleted again if the checkout fails, e.g. on conflicting changes.
+    pub fn checkout_new_branch_at(&self, branch_name: &str, oid: git2::Oid) -> anyhow::Result<()> {
+        let commit = self.repo.find_commit(oid)?;
+        let mut branch = self.repo.branch(branch_name, &commit, false)?;
+        if let Err(e) = self.checkout(branch_name) {
+            branch.delete()?;
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempRepo;
+
+    #[test]
+    fn test_reflog() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("feature")
+            .checkout("feature")
+            .commit_file("a.txt", "a", "Add a");
+        let repo = Repository::new(temp.path())?;
+
+        let reflog = repo.reflog("HEAD", 10)?;
+        assert_eq!(reflog[0].selector, "HEAD@{0}");
+        assert_eq!(reflog[0].action, "commit");
+        assert_eq!(reflog[0].message, "Add a");
+        assert_eq!(reflog[0].oid, repo.head_id()?);
+        assert_eq!(reflog[1].action, "checkout");
+        assert_eq!(reflog[1].checkout(), Some(("master", "feature")));
+        assert_eq!(reflog[0].checkout(), None);
+        assert_eq!(repo.reflog("HEAD", 1)?.len(), 1);
+
+        let reflog = repo.reflog("feature", 10)?;
+        assert_eq!(reflog[0].selector, "feature@{0}");
+        assert_eq!(reflog[0].message, "Add a");
+        assert!(repo.reflog("nothing", 10).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_previous_branches() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("a")
+            .branch("b")
+            .branch("c")
+            .checkout("a")
+            .checkout("b")
+            .checkout("a")
+            .checkout("c");
+        let repo = Repository::new(temp.path())?;
+        assert_eq!(repo.previous_branches()?, vec!["a", "b", "master"]);
+
+        repo.delete_branch("b")?;
+        assert_eq!(repo.previous_branches()?, vec!["a", "master"]);
+
+        assert_eq!(repo.checkout_previous(2)?, "master");
+        assert_eq!(repo.current_branch()?.as_deref(), Some("master"));
+        assert_eq!(repo.previous_branches()?, vec!["c", "a"]);
+        assert!(repo.checkout_previous(3).is_err());
+        assert!(repo.checkout_previous(0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkout_entry() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.commit_file("a.txt", "a", "Add a");
+        let repo = Repository::new(temp.path())?;
+        let before = repo.reflog("HEAD", 10)?[1].oid;
+
+        repo.checkout_detached(before)?;
+        assert_eq!(repo.head_id()?, before);
+        assert!(repo.repo.head_detached()?);
+        assert!(!temp.path().join("a.txt").exists());
+
+        repo.checkout_new_branch_at("restored", before)?;
+        assert_eq!(repo.current_branch()?.as_deref(), Some("restored"));
+        assert_eq!(repo.head_id()?, before);
+
+        // a failed checkout leaves no branch behind
+        repo.checkout("master")?;
+        temp.write("a.txt", "changed");
+        let after = repo.head_id()?;
+        repo.checkout_new_branch_at("another", before).unwrap_err();
+        assert!(!repo.branches()?.contains(&"another".to_owned()));
+        assert_eq!(repo.current_branch()?.as_deref(), Some("master"));
+        assert_eq!(repo.head_id()?, after);
+        Ok(())
+    }
+}
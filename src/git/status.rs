@@ -0,0 +1,480 @@
+//! The state of the index and the working tree, like `git status`, and staging changes.
+
+use std::{cell::Cell, path::Path};
+
+use super::Repository;
+
+/// How a file differs, between HEAD and the index or between the index and the working tree.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Typechange,
+}
+
+impl Change {
+    /// The letter `git status --short` shows.
+    pub fn symbol(&self) -> char {
+        match self {
+            Change::Added => 'A',
+            Change::Modified => 'M',
+            Change::Deleted => 'D',
+            Change::Renamed => 'R',
+            Change::Typechange => 'T',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileStatus {
+    pub path: String,
+    /// The path before a rename.
+    pub old_path: Option<String>,
+    pub change: Change,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WorkingTreeStatus {
+    /// Changes in the index, to be committed.
+    pub staged: Vec<FileStatus>,
+    /// Changes in the working tree, not in the index.
+    pub unstaged: Vec<FileStatus>,
+    pub untracked: Vec<String>,
+    /// Files with unresolved conflicts.
+    pub conflicted: Vec<String>,
+}
+
+/// Where a file of `WorkingTreeStatus` is listed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Area {
+    Conflicted,
+    Staged,
+    Unstaged,
+    Untracked,
+}
+
+/// A row of the status view.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusFile<'a> {
+    pub area: Area,
+    pub path: &'a str,
+    pub old_path: Option<&'a str>,
+    /// `A`, `M`, ... like `git status --short`, `U` for conflicts and `?` when untracked.
+    pub symbol: char,
+}
+
+impl<'a> StatusFile<'a> {
+    fn plain(area: Area, symbol: char, path: &'a str) -> Self {
+        Self {
+            area,
+            path,
+            old_path: None,
+            symbol,
+        }
+    }
+
+    fn changed(area: Area, file: &'a FileStatus) -> Self {
+        Self {
+            area,
+            path: &file.path,
+            old_path: file.old_path.as_deref(),
+            symbol: file.change.symbol(),
+        }
+    }
+}
+
+/// A hunk of the diff of one file, with its lines prefixed by `+`, `-` or a space.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    pub header: String,
+    pub lines: Vec<String>,
+}
+
+impl WorkingTreeStatus {
+    /// Every file, conflicted ones first, then staged, unstaged and untracked ones.
+    pub fn files(&self) -> Vec<StatusFile<'_>> {
+        let mut files = Vec::new();
+        files.extend(
+            self.conflicted
+                .iter()
+                .map(|path| StatusFile::plain(Area::Conflicted, 'U', path)),
+        );
+        files.extend(
+            self.staged
+                .iter()
+                .map(|file| StatusFile::changed(Area::Staged, file)),
+        );
+        files.extend(
+            self.unstaged
+                .iter()
+                .map(|file| StatusFile::changed(Area::Unstaged, file)),
+        );
+        files.extend(
+            self.untracked
+                .iter()
+                .map(|path| StatusFile::plain(Area::Untracked, '?', path)),
+        );
+        files
+    }
+
+    /// Whether tracked files have changes a checkout could carry over or refuse to overwrite.
+    /// Untracked files do not count, like with `git status --untracked-files=no`.
+    pub fn is_dirty(&self) -> bool {
+        !(self.staged.is_empty() && self.unstaged.is_empty() && self.conflicted.is_empty())
+    }
+
+    pub fn is_clean(&self) -> bool {
+        !self.is_dirty() && self.untracked.is_empty()
+    }
+}
+
+impl Repository {
+    pub fn working_tree_status(&self) -> anyhow::Result<WorkingTreeStatus> {
+        let mut options = git2::StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .renames_head_to_index(true);
+        let mut status = WorkingTreeStatus::default();
+        for entry in self.repo.statuses(Some(&mut options))?.iter() {
+            let flags = entry.status();
+            let file_path = |file: git2::DiffFile| file.path().map(|p| p.display().to_string());
+            let path = String::from_utf8_lossy(entry.path_bytes()).into_owned();
+            if flags.is_conflicted() {
+                status.conflicted.push(path);
+                continue;
+            }
+            if flags.is_wt_new() {
+                status.untracked.push(path.clone());
+            }
+            let staged = if flags.is_index_new() {
+                Some(Change::Added)
+            } else if flags.is_index_modified() {
+                Some(Change::Modified)
+            } else if flags.is_index_deleted() {
+                Some(Change::Deleted)
+            } else if flags.is_index_renamed() {
+                Some(Change::Renamed)
+            } else if flags.is_index_typechange() {
+                Some(Change::Typechange)
+            } else {
+                None
+            };
+            if let Some(change) = staged {
+                // the entry of a rename has the old path
+                let delta = entry.head_to_index();
+                let new_path = delta
+                    .as_ref()
+                    .and_then(|delta| file_path(delta.new_file()))
+                    .unwrap_or_else(|| path.clone());
+                let old_path = delta
+                    .as_ref()
+                    .and_then(|delta| file_path(delta.old_file()))
+                    .filter(|old| *old != new_path);
+                status.staged.push(FileStatus {
+                    path: new_path,
+                    old_path,
+                    change,
+                });
+            }
+            let unstaged = if flags.is_wt_modified() {
+                Some(Change::Modified)
+            } else if flags.is_wt_deleted() {
+                Some(Change::Deleted)
+            } else if flags.is_wt_typechange() {
+                Some(Change::Typechange)
+            } else {
+                None
+            };
+            if let Some(change) = unstaged {
+                status.unstaged.push(FileStatus {
+                    path: entry
+                        .index_to_workdir()
+                        .and_then(|delta| file_path(delta.new_file()))
+                        .unwrap_or(path),
+                    old_path: None,
+                    change,
+                });
+            }
+        }
+        Ok(status)
+    }
+
+    /// Adds the file to the index, or removes it from the index if it was deleted.
+    /// A conflicted file is marked as resolved.
+    pub fn stage(&self, path: &str) -> anyhow::Result<()> {
+        let mut index = self.repo.index()?;
+        let exists = self
+            .repo
+            .workdir()
+            .map(|workdir| workdir.join(path).symlink_metadata().is_ok())
+            .unwrap_or(false);
+        if exists {
+            index.add_path(Path::new(path))?;
+        } else {
+            index.remove_path(Path::new(path))?;
+        }
+        index.write()?;
+        Ok(())
+    }
+
+    /// Resets the file in the index to HEAD, like `git restore --staged`.
+    pub fn unstage(&self, path: &str) -> anyhow::Result<()> {
+        match self.repo.head().and_then(|head| head.peel_to_commit()) {
+            Ok(head) => self.repo.reset_default(Some(head.as_object()), [path])?,
+            // nothing is committed yet
+            Err(_) => self.repo.reset_default(None, [path])?,
+        }
+        Ok(())
+    }
+
+    /// The hunks of the staged or unstaged changes of a file.
+    pub fn hunks(&self, path: &str, staged: bool) -> anyhow::Result<Vec<Hunk>> {
+        let diff = self.file_diff(path, staged, false)?;
+        let mut hunks = Vec::new();
+        if let Some(patch) = git2::Patch::from_diff(&diff, 0)? {
+            for i in 0..patch.num_hunks() {
+                let (hunk, line_count) = patch.hunk(i)?;
+                let lines = (0..line_count)
+                    .map(|j| {
+                        patch.line_in_hunk(i, j).map(|line| {
+                            format!(
+                                "{}{}",
+                                line.origin(),
+                                String::from_utf8_lossy(line.content()).trim_end_matches('\n')
+                            )
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                hunks.push(Hunk {
+                    header: String::from_utf8_lossy(hunk.header()).trim_end().to_owned(),
+                    lines,
+                });
+            }
+        }
+        Ok(hunks)
+    }
+
+    /// Adds one of the unstaged hunks of a file to the index, like `git add -p`.
+    pub fn stage_hunk(&self, path: &str, hunk: usize) -> anyhow::Result<()> {
+        self.apply_hunk(&self.file_diff(path, false, false)?, hunk)
+    }
+
+    /// Takes one of the staged hunks of a file out of the index, like `git restore -p --staged`.
+    pub fn unstage_hunk(&self, path: &str, hunk: usize) -> anyhow::Result<()> {
+        self.apply_hunk(&self.file_diff(path, true, true)?, hunk)
+    }
+
+    fn apply_hunk(&self, diff: &git2::Diff, hunk: usize) -> anyhow::Result<()> {
+        let index = Cell::new(0);
+        let mut options = git2::ApplyOptions::new();
+        options.hunk_callback(|_| {
+            let current = index.replace(index.get() + 1);
+            current == hunk
+        });
+        self.repo
+            .apply(diff, git2::ApplyLocation::Index, Some(&mut options))?;
+        Ok(())
+    }
+
+    /// HEAD to the index if `staged`, the index to the working tree otherwise.
+    /// `reverse` swaps the sides, so applying the diff undoes it.
+    fn file_diff(&self, path: &str, staged: bool, reverse: bool) -> anyhow::Result<git2::Diff<'_>> {
+        let mut options = git2::DiffOptions::new();
+        options
+            .pathspec(path)
+            .disable_pathspec_match(true)
+            .include_untracked(true)
+            .show_untracked_content(true)
+            .reverse(reverse);
+        let diff = if staged {
+            let head = self.repo.head().and_then(|head| head.peel_to_tree()).ok();
+            self.repo
+                .diff_tree_to_index(head.as_ref(), None, Some(&mut options))?
+        } else {
+            self.repo.diff_index_to_workdir(None, Some(&mut options))?
+        };
+        Ok(diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempRepo;
+
+    #[test]
+    fn test_working_tree_status() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.commit_file("a.txt", "a", "add a")
+            .commit_file("b.txt", "b", "add b");
+        let repo = Repository::new(temp.path())?;
+        assert!(repo.working_tree_status()?.is_clean());
+
+        std::fs::write(temp.path().join("a.txt"), "changed")?;
+        std::fs::write(temp.path().join("new.txt"), "new")?;
+        std::fs::remove_file(temp.path().join("b.txt"))?;
+        let status = repo.working_tree_status()?;
+        assert!(status.is_dirty());
+        assert!(status.staged.is_empty());
+        assert_eq!(
+            status.unstaged,
+            vec![
+                FileStatus {
+                    path: "a.txt".into(),
+                    old_path: None,
+                    change: Change::Modified
+                },
+                FileStatus {
+                    path: "b.txt".into(),
+                    old_path: None,
+                    change: Change::Deleted
+                },
+            ]
+        );
+        assert_eq!(status.untracked, vec!["new.txt"]);
+
+        let mut index = temp.repo.index()?;
+        index.add_path(std::path::Path::new("new.txt"))?;
+        index.add_path(std::path::Path::new("a.txt"))?;
+        index.write()?;
+        std::fs::write(temp.path().join("a.txt"), "changed again")?;
+        let status = repo.working_tree_status()?;
+        assert_eq!(
+            status.staged,
+            vec![
+                FileStatus {
+                    path: "a.txt".into(),
+                    old_path: None,
+                    change: Change::Modified
+                },
+                FileStatus {
+                    path: "new.txt".into(),
+                    old_path: None,
+                    change: Change::Added
+                },
+            ]
+        );
+        assert_eq!(status.unstaged.len(), 2);
+        assert!(status.untracked.is_empty());
+
+        // a staged rename keeps the old path
+        let temp = TempRepo::new();
+        temp.commit_file("a.txt", "a\nb\nc\n", "add a");
+        std::fs::rename(temp.path().join("a.txt"), temp.path().join("c.txt"))?;
+        let mut index = temp.repo.index()?;
+        index.remove_path(std::path::Path::new("a.txt"))?;
+        index.add_path(std::path::Path::new("c.txt"))?;
+        index.write()?;
+        let status = Repository::new(temp.path())?.working_tree_status()?;
+        assert!(status.staged.contains(&FileStatus {
+            path: "c.txt".into(),
+            old_path: Some("a.txt".into()),
+            change: Change::Renamed
+        }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stage_and_unstage() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.commit_file("a.txt", "a", "add a")
+            .commit_file("b.txt", "b", "add b");
+        let repo = Repository::new(temp.path())?;
+        std::fs::write(temp.path().join("a.txt"), "changed")?;
+        std::fs::write(temp.path().join("new.txt"), "new")?;
+        std::fs::remove_file(temp.path().join("b.txt"))?;
+
+        repo.stage("a.txt")?;
+        repo.stage("new.txt")?;
+        repo.stage("b.txt")?;
+        let status = repo.working_tree_status()?;
+        assert!(status.unstaged.is_empty());
+        assert!(status.untracked.is_empty());
+        assert_eq!(
+            status
+                .files()
+                .iter()
+                .map(|file| (file.area, file.symbol, file.path))
+                .collect::<Vec<_>>(),
+            vec![
+                (Area::Staged, 'M', "a.txt"),
+                (Area::Staged, 'D', "b.txt"),
+                (Area::Staged, 'A', "new.txt"),
+            ]
+        );
+
+        repo.unstage("a.txt")?;
+        repo.unstage("new.txt")?;
+        let status = repo.working_tree_status()?;
+        assert_eq!(status.staged.len(), 1);
+        assert_eq!(status.unstaged[0].path, "a.txt");
+        assert_eq!(status.untracked, vec!["new.txt"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stage_hunks() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        let lines = (1..=20)
+            .map(|i| format!("line {}\n", i))
+            .collect::<String>();
+        temp.commit_file("a.txt", &lines, "add a");
+        let repo = Repository::new(temp.path())?;
+        std::fs::write(
+            temp.path().join("a.txt"),
+            lines
+                .replace("line 2\n", "line two\n")
+                .replace("line 19\n", "line nineteen\n"),
+        )?;
+
+        let hunks = repo.hunks("a.txt", false)?;
+        assert_eq!(hunks.len(), 2);
+        assert!(hunks[0].header.starts_with("@@ -1,5 +1,5 @@"));
+        assert!(hunks[0].lines.contains(&"-line 2".to_owned()));
+        assert!(hunks[0].lines.contains(&"+line two".to_owned()));
+
+        repo.stage_hunk("a.txt", 1)?;
+        let staged = repo.hunks("a.txt", true)?;
+        assert_eq!(staged.len(), 1);
+        assert!(staged[0].lines.contains(&"+line nineteen".to_owned()));
+        let unstaged = repo.hunks("a.txt", false)?;
+        assert_eq!(unstaged.len(), 1);
+        assert!(unstaged[0].lines.contains(&"+line two".to_owned()));
+
+        repo.stage_hunk("a.txt", 0)?;
+        assert_eq!(repo.hunks("a.txt", true)?.len(), 2);
+        repo.unstage_hunk("a.txt", 1)?;
+        let staged = repo.hunks("a.txt", true)?;
+        assert_eq!(staged.len(), 1);
+        assert!(staged[0].lines.contains(&"+line two".to_owned()));
+        assert_eq!(repo.hunks("a.txt", false)?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_working_tree_status_conflicted() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.commit_file("a.txt", "base", "add a")
+            .branch("other")
+            .commit_file("a.txt", "ours", "ours")
+            .checkout("other")
+            .commit_file("a.txt", "theirs", "theirs")
+            .checkout("master");
+        let repo = Repository::new(temp.path())?;
+        repo.merge("other", crate::git::MergeStrategy::Commit, "merge")?;
+
+        let status = repo.working_tree_status()?;
+        assert_eq!(status.conflicted, vec!["a.txt"]);
+        assert!(status.is_dirty());
+
+        std::fs::write(temp.path().join("a.txt"), "resolved")?;
+        repo.stage("a.txt")?;
+        let status = repo.working_tree_status()?;
+        assert!(status.conflicted.is_empty());
+        assert_eq!(status.staged[0].path, "a.txt");
+        Ok(())
+    }
+}
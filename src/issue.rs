@@ -0,0 +1,199 @@
+//! Issue numbers in branch names and commit messages, and links to the issue tracker.
+
+/// The branch name template `git ex start` follows unless `ex.branchTemplate` is set.
+pub const DEFAULT_BRANCH_TEMPLATE: &str = "{type}/{issue}-{description}";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Placeholder(String),
+}
+
+/// A branch name template with `{type}`, `{issue}` and `{description}` placeholders,
+/// e.g. `{type}/{issue}-{description}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BranchTemplate {
+    segments: Vec<Segment>,
+}
+
+impl BranchTemplate {
+    pub fn new(template: &str) -> Self {
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while !rest.is_empty() {
+            match rest
+                .find('{')
+                .and_then(|start| rest[start..].find('}').map(|end| (start, start + end)))
+            {
+                Some((start, end)) => {
+                    if start > 0 {
+                        segments.push(Segment::Literal(rest[..start].into()));
+                    }
+                    segments.push(Segment::Placeholder(rest[start + 1..end].into()));
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    segments.push(Segment::Literal(rest.into()));
+                    rest = "";
+                }
+            }
+        }
+        Self { segments }
+    }
+
+    pub fn format(&self, branch_type: &str, issue: &str, description: &str) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(literal) => literal.as_str(),
+                Segment::Placeholder(name) => match name.as_str() {
+                    "type" => branch_type,
+                    "issue" => issue,
+                    "description" => description,
+                    _ => "",
+                },
+            })
+            .collect()
+    }
+
+    /// The issue number of a branch name made from the template, e.g. `1234` of
+    /// `feature/1234-fix-login`.
+    pub fn issue(&self, branch_name: &str) -> Option<String> {
+        let mut values = Vec::new();
+        if Self::matches(&self.segments, branch_name, &mut values) {
+            values
+                .into_iter()
+                .find(|(name, _)| name == "issue")
+                .map(|(_, value)| value)
+        } else {
+            None
+        }
+    }
+
+    /// Matches the segments against `s`, each placeholder taking as little as it can.
+    /// `{type}` and `{issue}` stay within one path component, and `{issue}` only takes
+    /// an issue number.
+    fn matches(segments: &[Segment], s: &str, values: &mut Vec<(String, String)>) -> bool {
+        match segments.split_first() {
+            None => s.is_empty(),
+            Some((Segment::Literal(literal), rest)) => s
+                .strip_prefix(literal.as_str())
+                .is_some_and(|s| Self::matches(rest, s, values)),
+            Some((Segment::Placeholder(name), rest)) => {
+                for (end, _) in s.char_indices().skip(1).chain([(s.len(), ' ')]) {
+                    let value = &s[..end];
+                    if name != "description" && value.contains('/') {
+                        break;
+                    }
+                    if name == "issue" && !is_issue(value) {
+                        continue;
+                    }
+                    values.push((name.clone(), value.to_owned()));
+                    if Self::matches(rest, &s[end..], values) {
+                        return true;
+                    }
+                    values.pop();
+                }
+                false
+            }
+        }
+    }
+}
+
+impl Default for BranchTemplate {
+    fn default() -> Self {
+        Self::new(DEFAULT_BRANCH_TEMPLATE)
+    }
+}
+
+/// The issues a commit message refers to, like `#1234` or `PROJ-1234`.
+pub fn issues_in_message(message: &str) -> Vec<String> {
+    message
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '#' || c == '-'))
+        .filter_map(|word| {
+            let word = word.trim_matches('-');
+            if let Some(number) = word.strip_prefix('#') {
+                Some(number).filter(|n| is_number(n))
+            } else {
+                Some(word).filter(|word| is_key_issue(word))
+            }
+        })
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// A number like `1234`, or a key and a number like `PROJ-1234`.
+fn is_issue(s: &str) -> bool {
+    is_number(s) || is_key_issue(s)
+}
+
+fn is_number(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_key_issue(s: &str) -> bool {
+    s.rsplit_once('-').is_some_and(|(key, number)| {
+        key.starts_with(|c: char| c.is_ascii_uppercase())
+            && key
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+            && is_number(number)
+    })
+}
+
+/// The link to an issue from a pattern like `https://tracker/browse/{issue}`.
+pub fn issue_url(pattern: &str, issue: &str) -> String {
+    pattern.replace("{issue}", issue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branch_template() {
+        let template = BranchTemplate::default();
+        assert_eq!(
+            template.format("feature", "1234", "fix-login"),
+            "feature/1234-fix-login"
+        );
+        assert_eq!(
+            template.issue("feature/1234-fix-login"),
+            Some("1234".into())
+        );
+        assert_eq!(template.issue("master"), None);
+        assert_eq!(template.issue("feature/-x"), None);
+        // names that do not carry an issue
+        assert_eq!(template.issue("feature/add-login"), None);
+        assert_eq!(template.issue("feature/login/12-x"), None);
+        assert_eq!(
+            template.issue("feature/PROJ-12-fix-login"),
+            Some("PROJ-12".into())
+        );
+
+        let template = BranchTemplate::new("{issue}_{description}");
+        assert_eq!(template.issue("PROJ-12_fix_login"), Some("PROJ-12".into()));
+        let template = BranchTemplate::new("{type}/{description}/{issue}");
+        assert_eq!(
+            template.issue("feature/fix-login/1234"),
+            Some("1234".into())
+        );
+    }
+
+    #[test]
+    fn test_issues_in_message() {
+        assert_eq!(
+            issues_in_message("Fix login (#12), see PROJ-345 and x-y"),
+            vec!["12", "PROJ-345"]
+        );
+        assert!(issues_in_message("No issue # here, just-words").is_empty());
+    }
+
+    #[test]
+    fn test_issue_url() {
+        assert_eq!(
+            issue_url("https://tracker/browse/{issue}", "PROJ-1"),
+            "https://tracker/browse/PROJ-1"
+        );
+    }
+}
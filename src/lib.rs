@@ -1,7 +1,13 @@
 pub mod app;
-pub mod util;
-pub mod git;
-pub mod component;
 pub mod cmd;
+pub mod component;
+pub mod config;
+pub mod git;
+pub mod issue;
+pub mod util;
+pub mod worker;
+
+#[cfg(test)]
+mod test_support;
 
 pub use app::{Command, InputMode};
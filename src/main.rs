@@ -1,5 +1,10 @@
-use git_ex::{app::App, cmd::StartBranchOpts};
-use std::env;
+use git_ex::{
+    app::App,
+    cmd::{AmendOpts, BackOpts, FetchOpts, LogOpts, PickOpts, StartBranchOpts, SyncOpts},
+    git::Repository,
+    issue::BranchTemplate,
+};
+use std::{env, io::Write};
 
 use anyhow::anyhow;
 use clap::{Parser, Subcommand};
@@ -15,6 +20,24 @@ struct Opts {
 enum SubCommand {
     #[command(name = "start")]
     StartBranch(StartBranchOpts),
+    /// Pick branches interactively and print their names.
+    #[command(name = "pick")]
+    Pick(PickOpts),
+    /// Fetch and prune remotes, then list branches whose upstream is gone.
+    #[command(name = "fetch")]
+    Fetch(FetchOpts),
+    /// Fast-forward every branch to its upstream without checking it out.
+    #[command(name = "sync")]
+    Sync(SyncOpts),
+    /// Show the commit graph of a branch, filtered like `git log`.
+    #[command(name = "log")]
+    Log(LogOpts),
+    /// Fold the staged changes into HEAD, or change its message.
+    #[command(name = "amend")]
+    Amend(AmendOpts),
+    /// Switch back to the n-th branch checked out before, read from the reflog of HEAD.
+    #[command(name = "back")]
+    Back(BackOpts),
 }
 
 fn main() -> anyhow::Result<()> {
@@ -25,16 +48,89 @@ fn main() -> anyhow::Result<()> {
         Some(path) => path,
         _ => return Err(anyhow!("Not found git repository.")),
     };
-    let mut app = App::new(dir)?;
+    // only the TUI needs `App`, with its worker and watcher
+    let repo = || Repository::new(&dir);
 
     match opts.subcmd {
-        None => app.start()?,
+        None => App::new(&dir)?.start()?,
         Some(s) => match s {
             SubCommand::StartBranch(opts) => {
-                let branch = app.start_branch(&opts)?;
+                let repo = repo()?;
+                let template = BranchTemplate::new(&repo.config()?.branch_template);
+                let branch =
+                    repo.start_branch(&opts.branch_name(&template), opts.note.as_deref())?;
                 println!("start: {}", branch.name()?.unwrap());
             }
+            SubCommand::Pick(opts) => {
+                let branches = App::new(&dir)?.pick()?;
+                if branches.is_empty() {
+                    return Err(anyhow!("No branch picked."));
+                }
+                print!("{}", opts.format(&branches));
+            }
+            SubCommand::Fetch(opts) => {
+                let repo = repo()?;
+                fetch(&repo, opts.remote.as_deref())?;
+                for branch in repo.gone_branches()? {
+                    println!("upstream gone: {}", branch);
+                }
+            }
+            SubCommand::Sync(opts) => {
+                let repo = repo()?;
+                if opts.fetch {
+                    fetch(&repo, None)?;
+                }
+                let mut failed = false;
+                for (branch, result) in repo.sync_branches()? {
+                    match result {
+                        Ok(result) => {
+                            failed |= result.is_diverged();
+                            println!("{}: {}", branch, result);
+                        }
+                        Err(e) => {
+                            failed = true;
+                            println!("{}: {}", branch, e);
+                        }
+                    }
+                }
+                if failed {
+                    return Err(anyhow!("Some branches could not be fast-forwarded."));
+                }
+            }
+            SubCommand::Log(opts) => {
+                for line in opts.lines(&repo()?)? {
+                    println!("{}", line);
+                }
+            }
+            SubCommand::Amend(opts) => {
+                let repo = repo()?;
+                if let Some(branch) = repo.current_branch()? {
+                    if repo.is_pushed(&branch, repo.head_id()?)? {
+                        eprintln!(
+                            "warning: HEAD is pushed already, {} will need a force push.",
+                            branch
+                        );
+                    }
+                }
+                let id = repo.amend(opts.message.as_deref())?;
+                println!("amended: {}", id);
+            }
+            SubCommand::Back(opts) => {
+                let branch = repo()?.checkout_previous(opts.n)?;
+                println!("Switched to branch '{}'", branch);
+            }
         },
     }
     Ok(())
 }
+
+/// Fetches with the progress on stderr, so only the result goes to stdout.
+fn fetch(repo: &Repository, remote: Option<&str>) -> anyhow::Result<()> {
+    let remotes = repo.fetch(remote, |progress| {
+        eprint!("\r{}\x1b[K", progress);
+        std::io::stderr().flush().ok();
+    })?;
+    eprint!("\r\x1b[K");
+    println!("Fetched {}", remotes.join(", "));
+    Ok(())
+}
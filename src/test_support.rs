@@ -0,0 +1,263 @@
+//! Helpers for tests: throwaway repositories and rendered buffers.
+//!
+//! ```ignore
+//! let repo = TempRepo::new();
+//! repo.commit_file("a.txt", "a", "add a").branch("develop");
+//! let origin = repo.add_remote("origin");
+//! repo.push("origin", "develop");
+//! ```
+
+use std::{
+    cell::Cell,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use tui::buffer::Buffer;
+
+/// Commit times start here and go up by a minute per commit, so history order is stable.
+const EPOCH: i64 = 1_600_000_000;
+
+pub struct TempRepo {
+    dir: tempfile::TempDir,
+    pub repo: git2::Repository,
+    clock: Cell<i64>,
+}
+
+impl TempRepo {
+    /// Creates a repository with one commit on `master`.
+    pub fn new() -> Self {
+        let temp = Self::init(false);
+        temp.commit("initial commit");
+        temp
+    }
+
+    /// Creates an empty bare repository, e.g. to act as a remote.
+    pub fn bare() -> Self {
+        Self::init(true)
+    }
+
+    fn init(bare: bool) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init_opts(
+            dir.path(),
+            git2::RepositoryInitOptions::new()
+                .bare(bare)
+                .initial_head("master"),
+        )
+        .unwrap();
+        // for the commits git-ex makes itself
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "git-ex").unwrap();
+        config.set_str("user.email", "git-ex@example.com").unwrap();
+        Self {
+            dir,
+            repo,
+            clock: Cell::new(EPOCH),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    fn tick(&self) -> git2::Signature<'static> {
+        let time = self.clock.get() + 60;
+        self.clock.set(time);
+        git2::Signature::new("git-ex", "git-ex@example.com", &git2::Time::new(time, 0)).unwrap()
+    }
+
+    fn head_commit(&self) -> Option<git2::Commit<'_>> {
+        self.repo.head().ok().and_then(|h| h.peel_to_commit().ok())
+    }
+
+    /// Writes a file in the working tree without staging it.
+    pub fn write(&self, path: &str, content: &str) -> &Self {
+        let path = self.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+        self
+    }
+
+    /// Stages a file of the working tree.
+    pub fn add(&self, path: &str) -> &Self {
+        let mut index = self.repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+        self
+    }
+
+    /// Commits the current index on top of HEAD.
+    pub fn commit(&self, message: &str) -> git2::Oid {
+        let parents = self.head_commit().into_iter().collect::<Vec<_>>();
+        self.commit_with_parents(message, &parents.iter().collect::<Vec<_>>())
+    }
+
+    fn commit_with_parents(&self, message: &str, parents: &[&git2::Commit]) -> git2::Oid {
+        let signature = self.tick();
+        let tree = {
+            let mut index = self.repo.index().unwrap();
+            let id = index.write_tree().unwrap();
+            self.repo.find_tree(id).unwrap()
+        };
+        self.repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                parents,
+            )
+            .unwrap()
+    }
+
+    /// Writes, stages and commits a file.
+    pub fn commit_file(&self, path: &str, content: &str, message: &str) -> &Self {
+        self.write(path, content).add(path).commit(message);
+        self
+    }
+
+    /// Creates a branch at HEAD.
+    pub fn branch(&self, name: &str) -> &Self {
+        let head = self.head_commit().unwrap();
+        self.repo.branch(name, &head, false).unwrap();
+        self
+    }
+
+    /// Switches HEAD, the index and the working tree to a local branch.
+    pub fn checkout(&self, name: &str) -> &Self {
+        let refname = format!("refs/heads/{}", name);
+        let tree = self.repo.revparse_single(&refname).unwrap();
+        self.repo
+            .checkout_tree(&tree, Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        self.repo.set_head(&refname).unwrap();
+        self
+    }
+
+    /// Creates a merge commit of `branch` into HEAD, taking the tree of HEAD.
+    pub fn merge(&self, branch: &str, message: &str) -> git2::Oid {
+        let head = self.head_commit().unwrap();
+        let other = self
+            .repo
+            .find_branch(branch, git2::BranchType::Local)
+            .unwrap()
+            .get()
+            .peel_to_commit()
+            .unwrap();
+        self.commit_with_parents(message, &[&head, &other])
+    }
+
+    /// Creates a lightweight tag at HEAD.
+    pub fn tag(&self, name: &str) -> &Self {
+        let head = self.head_commit().unwrap();
+        self.repo
+            .tag_lightweight(name, head.as_object(), false)
+            .unwrap();
+        self
+    }
+
+    /// Creates a bare repository and adds it as a remote.
+    /// The remote is deleted when the returned `TempRepo` is dropped.
+    pub fn add_remote(&self, name: &str) -> TempRepo {
+        let remote = TempRepo::bare();
+        self.repo
+            .remote(name, remote.path().to_str().unwrap())
+            .unwrap();
+        remote
+    }
+
+    /// Pushes a local branch and sets it as the upstream, like `git push -u`.
+    pub fn push(&self, remote: &str, branch: &str) -> &Self {
+        let refspec = format!("refs/heads/{0}:refs/heads/{0}", branch);
+        self.repo
+            .find_remote(remote)
+            .unwrap()
+            .push(&[refspec.as_str()], None)
+            .unwrap();
+        let head = self
+            .repo
+            .find_branch(branch, git2::BranchType::Local)
+            .unwrap()
+            .get()
+            .target()
+            .unwrap();
+        self.repo
+            .reference(
+                &format!("refs/remotes/{}/{}", remote, branch),
+                head,
+                true,
+                "push",
+            )
+            .unwrap();
+        self.repo
+            .find_branch(branch, git2::BranchType::Local)
+            .unwrap()
+            .set_upstream(Some(&format!("{}/{}", remote, branch)))
+            .unwrap();
+        self
+    }
+
+    /// Stashes the changes of the working tree, including untracked files.
+    pub fn stash(&self, message: &str) -> &Self {
+        let signature = self.tick();
+        // stashing needs a mutable repository, so use a handle of its own
+        git2::Repository::open(self.path())
+            .unwrap()
+            .stash_save(
+                &signature,
+                message,
+                Some(git2::StashFlags::INCLUDE_UNTRACKED),
+            )
+            .unwrap();
+        self
+    }
+
+    /// Checks out `branch` in a new linked worktree and returns its path.
+    /// Worktrees live in the ignored `.worktrees` directory.
+    pub fn worktree(&self, name: &str, branch: &str) -> PathBuf {
+        let path = self.path().join(".worktrees").join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(self.repo.path().join("info/exclude"), ".worktrees/\n").unwrap();
+        let reference = self
+            .repo
+            .find_branch(branch, git2::BranchType::Local)
+            .unwrap()
+            .into_reference();
+        self.repo
+            .worktree(
+                name,
+                &path,
+                Some(git2::WorktreeAddOptions::new().reference(Some(&reference))),
+            )
+            .unwrap();
+        path
+    }
+}
+
+/// `feature` changes a.txt in the first commit and adds b.txt in the second,
+/// while `master`, which is checked out, changes a.txt too.
+pub fn diverged_branches() -> TempRepo {
+    let temp = TempRepo::new();
+    temp.commit_file("a.txt", "a", "add a")
+        .branch("feature")
+        .commit_file("a.txt", "master", "change a on master")
+        .checkout("feature")
+        .commit_file("a.txt", "feature", "change a on feature")
+        .commit_file("b.txt", "b", "add b")
+        .checkout("master");
+    temp
+}
+
+/// The symbols of a rendered buffer, one string per line.
+pub fn buffer_lines(buffer: &Buffer) -> Vec<String> {
+    let area = buffer.area();
+    (area.top()..area.bottom())
+        .map(|y| {
+            (area.left()..area.right())
+                .map(|x| buffer.get(x, y).symbol.as_str())
+                .collect()
+        })
+        .collect()
+}
@@ -2,8 +2,9 @@ use std::path::{Path, PathBuf};
 
 pub mod event;
 pub mod stateful_list;
+pub mod tty;
 
-pub use event::{Event, Events};
+pub use event::{Event, Events, Key};
 pub use stateful_list::StatefulList;
 
 use tui::layout::{Constraint, Direction, Layout, Rect};
@@ -46,6 +47,10 @@ pub fn centered_fix_rect(width: u16, height: u16, r: Rect) -> Rect {
     )
 }
 
+pub fn contains(r: &Rect, column: u16, row: u16) -> bool {
+    r.left() <= column && column < r.right() && r.top() <= row && row < r.bottom()
+}
+
 pub fn to_refs(name: &str) -> String {
     format!("refs/heads/{}", name)
 }
@@ -73,6 +78,16 @@ pub fn find_git_root_dir<P: AsRef<Path>>(path: &P) -> Option<PathBuf> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_contains() {
+        let r = Rect::new(2, 3, 4, 5);
+        assert!(contains(&r, 2, 3));
+        assert!(contains(&r, 5, 7));
+        assert!(!contains(&r, 6, 7));
+        assert!(!contains(&r, 5, 8));
+        assert!(!contains(&r, 1, 3));
+    }
+
     #[test]
     fn test_to_refs() {
         assert_eq!(to_refs("test"), "refs/heads/test")
@@ -80,16 +95,17 @@ mod tests {
 
     #[test]
     fn test_find_git_root_dir() -> anyhow::Result<()> {
-        let dir = std::env::current_dir()?;
-        assert_eq!(find_git_root_dir(&dir), Some(dir));
+        let temp = crate::test_support::TempRepo::new();
+        temp.commit_file("src/lib.rs", "", "add lib.rs");
+        let dir = temp.path().to_owned();
+
+        assert_eq!(find_git_root_dir(&dir), Some(dir.clone()));
 
-        let dir = std::env::current_dir()?;
-        let file = dir.with_file_name("hoge.txt");
+        let file = dir.join("hoge.txt");
         assert_eq!(find_git_root_dir(&file), None);
 
-        let dir = std::env::current_dir()?;
-        let file = dir.join("src");
-        assert_eq!(find_git_root_dir(&file), Some(dir));
+        let src = dir.join("src");
+        assert_eq!(find_git_root_dir(&src), Some(dir));
 
         Ok(())
     }
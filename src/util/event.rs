@@ -7,96 +7,206 @@ use std::sync::{
 use std::thread;
 use std::time::Duration;
 
-use termion::event::Key;
-use termion::input::TermRead;
+pub mod termion_source;
 
-pub enum Event<I> {
-    Input(I),
+pub use termion_source::{BracketedPasteTerminal, TermionSource};
+
+/// A key press, independent of the terminal library that read it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Backspace,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    BackTab,
+    Delete,
+    Insert,
+    F(u8),
+    Char(char),
+    Alt(char),
+    Ctrl(char),
+    Null,
+    Esc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseKind {
+    Down(MouseButton),
+    Up,
+    Drag,
+    ScrollUp,
+    ScrollDown,
+}
+
+/// A mouse event. `column` and `row` are 0-based, like `tui::layout::Rect`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mouse {
+    pub kind: MouseKind,
+    pub column: u16,
+    pub row: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Input(Key),
+    Mouse(Mouse),
+    /// Text pasted with bracketed paste enabled.
+    Paste(String),
+    /// The terminal was resized to (columns, rows).
+    Resize(u16, u16),
     Tick,
 }
 
-/// A small event handler that wrap termion input and tick events. Each event
-/// type is handled in its own thread and returned to a common `Receiver`
+/// A terminal library that reads input events.
+///
+/// `Events` drives the source from its own thread, so adding another backend
+/// (e.g. crossterm) only needs an implementation of this trait.
+pub trait EventSource: Send + 'static {
+    /// Waits at most `timeout` for input and returns the events read, if any.
+    fn poll(&mut self, timeout: Duration) -> io::Result<Vec<Event>>;
+}
+
+/// A small event handler that reads an `EventSource` on a background thread.
+/// A `Tick` is returned whenever no event arrives within the tick rate.
+/// The thread is stopped and joined when `Events` is dropped.
 pub struct Events {
-    rx: mpsc::Receiver<Event<Key>>,
-    #[allow(dead_code)]
-    input_handle: thread::JoinHandle<()>,
-    #[allow(dead_code)]
-    ignore_exit_key: Arc<AtomicBool>,
-    #[allow(dead_code)]
-    tick_handle: thread::JoinHandle<()>,
+    tx: mpsc::Sender<Event>,
+    rx: mpsc::Receiver<Event>,
+    tick_rate: Duration,
+    shutdown: Arc<AtomicBool>,
+    input_closed: Arc<AtomicBool>,
+    input_handle: Option<thread::JoinHandle<()>>,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct Config {
-    pub exit_key: Key,
     pub tick_rate: Duration,
+    /// How long the input thread waits for input before checking for shutdown.
+    pub poll_timeout: Duration,
 }
 
 impl Default for Config {
     fn default() -> Config {
         Config {
-            exit_key: Key::Char('q'),
             tick_rate: Duration::from_millis(250),
+            poll_timeout: Duration::from_millis(50),
         }
     }
 }
 
 impl Events {
-    pub fn new() -> Events {
-        Events::with_config(Config::default())
+    /// Reads events from the terminal with termion.
+    pub fn new() -> io::Result<Events> {
+        Ok(Events::with_source(
+            TermionSource::new()?,
+            Config::default(),
+        ))
     }
 
-    pub fn with_config(config: Config) -> Events {
+    pub fn with_source<S: EventSource>(mut source: S, config: Config) -> Events {
         let (tx, rx) = mpsc::channel();
-        let ignore_exit_key = Arc::new(AtomicBool::new(true));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let input_closed = Arc::new(AtomicBool::new(false));
         let input_handle = {
             let tx = tx.clone();
-            let ignore_exit_key = ignore_exit_key.clone();
+            let shutdown = shutdown.clone();
+            let input_closed = input_closed.clone();
             thread::spawn(move || {
-                let stdin = io::stdin();
-                for evt in stdin.keys() {
-                    match evt {
-                        Ok(key) => {
-                            if let Err(_) = tx.send(Event::Input(key)) {
-                                return;
-                            }
-                            if !ignore_exit_key.load(Ordering::Relaxed) && key == config.exit_key {
-                                return;
-                            }
-                        }
-                        Err(_) => {}
+                while !shutdown.load(Ordering::Relaxed) {
+                    let events = match source.poll(config.poll_timeout) {
+                        Ok(events) => events,
+                        Err(_) => break,
+                    };
+                    if events.into_iter().any(|event| tx.send(event).is_err()) {
+                        break;
                     }
                 }
-            })
-        };
-        let tick_handle = {
-            let tx = tx.clone();
-            thread::spawn(move || {
-                let tx = tx.clone();
-                loop {
-                    let _ = tx.send(Event::Tick);
-                    thread::sleep(config.tick_rate);
-                }
+                input_closed.store(true, Ordering::Relaxed);
             })
         };
         Events {
+            tx,
             rx,
-            ignore_exit_key,
-            input_handle,
-            tick_handle,
+            tick_rate: config.tick_rate,
+            shutdown,
+            input_closed,
+            input_handle: Some(input_handle),
         }
     }
 
-    pub fn next(&self) -> Result<Event<Key>, mpsc::RecvError> {
-        self.rx.recv()
+    pub fn waker(&self) -> Waker {
+        Waker(self.tx.clone())
     }
 
-    pub fn disable_exit_key(&mut self) {
-        self.ignore_exit_key.store(true, Ordering::Relaxed);
+    /// Returns an error once the input has been closed and every event was read.
+    pub fn next(&self) -> Result<Event, mpsc::RecvError> {
+        match self.rx.recv_timeout(self.tick_rate) {
+            Ok(event) => Ok(event),
+            Err(_) if self.input_closed.load(Ordering::Relaxed) => Err(mpsc::RecvError),
+            Err(_) => Ok(Event::Tick),
+        }
     }
+}
+
+/// Wakes up `Events::next` from another thread, e.g. when background work is done.
+#[derive(Clone)]
+pub struct Waker(mpsc::Sender<Event>);
+
+impl Waker {
+    pub fn wake(&self) {
+        self.0.send(Event::Tick).ok();
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.input_handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct ScriptedSource(Vec<Event>);
+
+    impl EventSource for ScriptedSource {
+        fn poll(&mut self, timeout: Duration) -> io::Result<Vec<Event>> {
+            if self.0.is_empty() {
+                thread::sleep(timeout);
+            }
+            Ok(self.0.drain(..).collect())
+        }
+    }
+
+    #[test]
+    fn test_events() {
+        let config = Config {
+            tick_rate: Duration::from_millis(20),
+            poll_timeout: Duration::from_millis(5),
+        };
+        let events = Events::with_source(
+            ScriptedSource(vec![Event::Input(Key::Char('a')), Event::Resize(80, 24)]),
+            config,
+        );
 
-    pub fn enable_exit_key(&mut self) {
-        self.ignore_exit_key.store(false, Ordering::Relaxed);
+        assert_eq!(events.next(), Ok(Event::Input(Key::Char('a'))));
+        assert_eq!(events.next(), Ok(Event::Resize(80, 24)));
+        assert_eq!(events.next(), Ok(Event::Tick));
     }
 }
@@ -0,0 +1,364 @@
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    ops,
+    os::unix::io::AsRawFd,
+    time::Duration,
+};
+
+use termion::event as te;
+
+use super::{Event, EventSource, Key, Mouse, MouseButton, MouseKind};
+
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+/// How long to wait for the rest of an escape sequence before taking what was read
+/// as keys. Terminals write a sequence at once, so only a split read needs the wait.
+const ESCAPE_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Reads keys, mouse events and bracketed pastes from `/dev/tty`, and reports
+/// terminal resizes by watching the terminal size between reads.
+pub struct TermionSource {
+    tty: File,
+    pending: Vec<u8>,
+    size: Option<(u16, u16)>,
+}
+
+impl TermionSource {
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            tty: termion::get_tty()?,
+            pending: Vec::new(),
+            size: termion::terminal_size().ok(),
+        })
+    }
+
+    fn wait_readable(&self, timeout: Duration) -> io::Result<bool> {
+        let mut fds = libc::pollfd {
+            fd: self.tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ret = unsafe { libc::poll(&mut fds, 1, timeout.as_millis() as libc::c_int) };
+        match ret {
+            -1 => {
+                let e = io::Error::last_os_error();
+                // a signal such as SIGWINCH interrupted the wait
+                if e.kind() == io::ErrorKind::Interrupted {
+                    Ok(false)
+                } else {
+                    Err(e)
+                }
+            }
+            0 => Ok(false),
+            _ => Ok(true),
+        }
+    }
+
+    fn read_pending(&mut self) -> io::Result<()> {
+        let mut buf = [0u8; 1024];
+        let n = self.tty.read(&mut buf)?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.pending.extend_from_slice(&buf[..n]);
+        Ok(())
+    }
+
+    fn check_resize(&mut self, events: &mut Vec<Event>) {
+        if let Ok(size) = termion::terminal_size() {
+            if self.size != Some(size) {
+                self.size = Some(size);
+                events.push(Event::Resize(size.0, size.1));
+            }
+        }
+    }
+}
+
+impl EventSource for TermionSource {
+    fn poll(&mut self, timeout: Duration) -> io::Result<Vec<Event>> {
+        let mut events = Vec::new();
+        if self.wait_readable(timeout)? {
+            self.read_pending()?;
+            events.extend(parse_events(&mut self.pending));
+            while is_incomplete_escape(&self.pending) {
+                if !self.wait_readable(ESCAPE_TIMEOUT)? {
+                    events.extend(flush_escape(&mut self.pending));
+                    break;
+                }
+                self.read_pending()?;
+                events.extend(parse_events(&mut self.pending));
+            }
+        }
+        self.check_resize(&mut events);
+        Ok(events)
+    }
+}
+
+/// Parses as many events as possible out of `pending`, leaving an unfinished paste or
+/// escape sequence in it for the next read.
+fn parse_events(pending: &mut Vec<u8>) -> Vec<Event> {
+    let mut events = Vec::new();
+    while !pending.is_empty() {
+        if pending.starts_with(PASTE_START) {
+            let body = &pending[PASTE_START.len()..];
+            match body.windows(PASTE_END.len()).position(|w| w == PASTE_END) {
+                Some(end) => {
+                    events.push(Event::Paste(String::from_utf8_lossy(&body[..end]).into()));
+                    pending.drain(..PASTE_START.len() + end + PASTE_END.len());
+                    continue;
+                }
+                // the rest of the paste has not been read yet
+                None => break,
+            }
+        }
+
+        // the rest of the sequence has not been read yet
+        if is_incomplete_escape(pending) {
+            break;
+        }
+
+        let consumed = {
+            let mut rest = pending[1..].iter();
+            let event = te::parse_event(pending[0], &mut rest.by_ref().map(|b| Ok(*b)));
+            if let Ok(event) = event {
+                events.extend(convert(event));
+            }
+            pending.len() - rest.len()
+        };
+        pending.drain(..consumed);
+    }
+    events
+}
+
+/// Whether `bytes` is the start of an escape sequence without its end. A lone ESC
+/// counts, as it may be the Esc key or the start of a sequence.
+fn is_incomplete_escape(bytes: &[u8]) -> bool {
+    match bytes {
+        [0x1b] => true,
+        // SS3, e.g. F1 as `ESC O P`
+        [0x1b, b'O'] => true,
+        // the X10 mouse encoding has three bytes after `ESC [ M`
+        [0x1b, b'[', b'M', rest @ ..] => rest.len() < 3,
+        // CSI ends with a byte in `@`..=`~`
+        [0x1b, b'[', rest @ ..] => !rest.iter().any(|b| (0x40..=0x7e).contains(b)),
+        _ => false,
+    }
+}
+
+/// Takes what is left of an escape sequence whose rest never came as keys:
+/// ESC alone is the Esc key, and ESC with one more byte is Alt with it.
+fn flush_escape(pending: &mut Vec<u8>) -> Option<Event> {
+    let event = match pending.as_slice() {
+        [0x1b] => Some(Event::Input(Key::Esc)),
+        [0x1b, b] => Some(Event::Input(Key::Alt(*b as char))),
+        _ => None,
+    };
+    pending.clear();
+    event
+}
+
+fn convert(event: te::Event) -> Option<Event> {
+    match event {
+        te::Event::Key(key) => Some(Event::Input(key.into())),
+        te::Event::Mouse(mouse) => Some(Event::Mouse(mouse.into())),
+        te::Event::Unsupported(_) => None,
+    }
+}
+
+impl From<te::Key> for Key {
+    fn from(key: te::Key) -> Self {
+        match key {
+            te::Key::Backspace => Key::Backspace,
+            te::Key::Left => Key::Left,
+            te::Key::Right => Key::Right,
+            te::Key::Up => Key::Up,
+            te::Key::Down => Key::Down,
+            te::Key::Home => Key::Home,
+            te::Key::End => Key::End,
+            te::Key::PageUp => Key::PageUp,
+            te::Key::PageDown => Key::PageDown,
+            te::Key::BackTab => Key::BackTab,
+            te::Key::Delete => Key::Delete,
+            te::Key::Insert => Key::Insert,
+            te::Key::F(n) => Key::F(n),
+            te::Key::Char(c) => Key::Char(c),
+            te::Key::Alt(c) => Key::Alt(c),
+            te::Key::Ctrl(c) => Key::Ctrl(c),
+            te::Key::Esc => Key::Esc,
+            _ => Key::Null,
+        }
+    }
+}
+
+impl From<te::MouseEvent> for Mouse {
+    fn from(mouse: te::MouseEvent) -> Self {
+        // termion reports 1-based coordinates
+        let (kind, x, y) = match mouse {
+            te::MouseEvent::Press(button, x, y) => {
+                let kind = match button {
+                    te::MouseButton::Left => MouseKind::Down(MouseButton::Left),
+                    te::MouseButton::Right => MouseKind::Down(MouseButton::Right),
+                    te::MouseButton::Middle => MouseKind::Down(MouseButton::Middle),
+                    te::MouseButton::WheelUp => MouseKind::ScrollUp,
+                    te::MouseButton::WheelDown => MouseKind::ScrollDown,
+                };
+                (kind, x, y)
+            }
+            te::MouseEvent::Release(x, y) => (MouseKind::Up, x, y),
+            te::MouseEvent::Hold(x, y) => (MouseKind::Drag, x, y),
+        };
+        Mouse {
+            kind,
+            column: x.saturating_sub(1),
+            row: y.saturating_sub(1),
+        }
+    }
+}
+
+/// A terminal with bracketed paste enabled, disabled again on drop.
+/// Works like `termion::input::MouseTerminal`.
+pub struct BracketedPasteTerminal<W: Write> {
+    term: W,
+}
+
+impl<W: Write> From<W> for BracketedPasteTerminal<W> {
+    fn from(mut from: W) -> Self {
+        from.write_all(b"\x1b[?2004h").ok();
+        BracketedPasteTerminal { term: from }
+    }
+}
+
+impl<W: Write> Drop for BracketedPasteTerminal<W> {
+    fn drop(&mut self) {
+        self.term.write_all(b"\x1b[?2004l").ok();
+    }
+}
+
+impl<W: Write> ops::Deref for BracketedPasteTerminal<W> {
+    type Target = W;
+
+    fn deref(&self) -> &W {
+        &self.term
+    }
+}
+
+impl<W: Write> ops::DerefMut for BracketedPasteTerminal<W> {
+    fn deref_mut(&mut self) -> &mut W {
+        &mut self.term
+    }
+}
+
+impl<W: Write> Write for BracketedPasteTerminal<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.term.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.term.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_events() {
+        let mut pending = b"a\x1b[A\x0f".to_vec();
+        assert_eq!(
+            parse_events(&mut pending),
+            vec![
+                Event::Input(Key::Char('a')),
+                Event::Input(Key::Up),
+                Event::Input(Key::Ctrl('o')),
+            ]
+        );
+        assert!(pending.is_empty());
+
+        // ESC alone waits for what may follow, and is the Esc key if nothing does
+        let mut pending = b"\x1b".to_vec();
+        assert_eq!(parse_events(&mut pending), vec![]);
+        assert_eq!(flush_escape(&mut pending), Some(Event::Input(Key::Esc)));
+        assert!(pending.is_empty());
+
+        let mut pending = b"\x1b[".to_vec();
+        assert_eq!(
+            flush_escape(&mut pending),
+            Some(Event::Input(Key::Alt('[')))
+        );
+    }
+
+    #[test]
+    fn test_parse_events_split() {
+        // an arrow key split between two reads
+        let mut pending = b"a\x1b".to_vec();
+        assert_eq!(
+            parse_events(&mut pending),
+            vec![Event::Input(Key::Char('a'))]
+        );
+        assert_eq!(pending, b"\x1b".to_vec());
+        pending.extend_from_slice(b"[A");
+        assert_eq!(parse_events(&mut pending), vec![Event::Input(Key::Up)]);
+        assert!(pending.is_empty());
+
+        // and a mouse event
+        let mut pending = b"\x1b[<0;1".to_vec();
+        assert_eq!(parse_events(&mut pending), vec![]);
+        pending.extend_from_slice(b"0;5M");
+        assert_eq!(
+            parse_events(&mut pending),
+            vec![Event::Mouse(Mouse {
+                kind: MouseKind::Down(MouseButton::Left),
+                column: 9,
+                row: 4,
+            })]
+        );
+        assert!(pending.is_empty());
+
+        assert!(is_incomplete_escape(b"\x1b[M "));
+        assert!(!is_incomplete_escape(b"\x1b[M !!"));
+        assert!(!is_incomplete_escape(b"\x1ba"));
+        assert!(!is_incomplete_escape(b"a\x1b"));
+    }
+
+    #[test]
+    fn test_parse_events_mouse() {
+        let mut pending = b"\x1b[<0;10;5M\x1b[<64;1;1M".to_vec();
+        assert_eq!(
+            parse_events(&mut pending),
+            vec![
+                Event::Mouse(Mouse {
+                    kind: MouseKind::Down(MouseButton::Left),
+                    column: 9,
+                    row: 4,
+                }),
+                Event::Mouse(Mouse {
+                    kind: MouseKind::ScrollUp,
+                    column: 0,
+                    row: 0,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_events_paste() {
+        let mut pending = b"x\x1b[200~feature/".to_vec();
+        assert_eq!(
+            parse_events(&mut pending),
+            vec![Event::Input(Key::Char('x'))]
+        );
+        assert_eq!(pending, b"\x1b[200~feature/".to_vec());
+
+        pending.extend_from_slice(b"123\x1b[201~y");
+        assert_eq!(
+            parse_events(&mut pending),
+            vec![
+                Event::Paste("feature/123".into()),
+                Event::Input(Key::Char('y')),
+            ]
+        );
+        assert!(pending.is_empty());
+    }
+}
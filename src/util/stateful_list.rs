@@ -4,6 +4,13 @@ use tui::widgets::ListState;
 pub struct StatefulList<T> {
     pub state: ListState,
     pub items: Vec<T>,
+    offset: usize,
+}
+
+impl<T> Default for StatefulList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T> StatefulList<T> {
@@ -11,19 +18,21 @@ impl<T> StatefulList<T> {
         StatefulList {
             state: ListState::default(),
             items: Vec::new(),
+            offset: 0,
         }
     }
 
     pub fn with_items(items: Vec<T>) -> StatefulList<T> {
         StatefulList {
             state: ListState::default(),
-            items: items,
+            items,
+            offset: 0,
         }
     }
 
     pub fn set_items(&mut self, items: Vec<T>) {
         self.items = items;
-        if self.items.len() > 0 {
+        if !self.items.is_empty() {
             self.state.select(Some(0));
         } else {
             self.unselect();
@@ -69,6 +78,33 @@ impl<T> StatefulList<T> {
     pub fn selected(&self) -> Option<&T> {
         self.state.selected().and_then(|i| self.items.get(i))
     }
+
+    /// Scrolls the same way `tui::widgets::List` does when it is drawn `height` rows tall.
+    /// `ListState` keeps its offset private, so the list tracks its own copy for hit-testing.
+    pub fn update_offset(&mut self, height: usize) {
+        self.offset = match self.state.selected() {
+            Some(selected) if height > 0 => {
+                if selected >= height + self.offset - 1 {
+                    selected + 1 - height
+                } else if selected < self.offset {
+                    selected
+                } else {
+                    self.offset
+                }
+            }
+            _ => 0,
+        };
+    }
+
+    /// Index of the item drawn on the `row`-th line of the list.
+    pub fn index_at(&self, row: u16) -> Option<usize> {
+        let i = self.offset + row as usize;
+        if i < self.items.len() {
+            Some(i)
+        } else {
+            None
+        }
+    }
 }
 
 #[cfg(test)]
@@ -89,4 +125,18 @@ mod tests {
 
         assert_eq!(list.selected(), Some(&"foo".to_owned()));
     }
+
+    #[test]
+    fn test_index_at() {
+        let mut list = StatefulList::with_items((0..10).collect());
+        list.update_offset(4);
+        assert_eq!(list.index_at(0), Some(0));
+        assert_eq!(list.index_at(3), Some(3));
+
+        list.state.select(Some(7));
+        list.update_offset(4);
+        assert_eq!(list.index_at(0), Some(4));
+        assert_eq!(list.index_at(3), Some(7));
+        assert_eq!(list.index_at(6), None);
+    }
 }
@@ -0,0 +1,47 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    os::unix::io::AsRawFd,
+};
+
+/// Points the process's stdout at the controlling terminal for as long as it is alive.
+///
+/// termion puts fd 1 into raw mode and asks it for the terminal size, so the TUI can only be
+/// drawn when stdout is a terminal. While picking branches stdout is usually captured
+/// (`git rebase $(git ex pick)`), so the UI goes to `/dev/tty` and the original stdout is
+/// restored on drop, ready to receive the result.
+pub struct StdoutToTty {
+    saved: libc::c_int,
+    // kept open until the original stdout has been restored
+    _tty: File,
+}
+
+impl StdoutToTty {
+    pub fn new() -> io::Result<Self> {
+        let tty = termion::get_tty()?;
+        let saved = cvt(unsafe { libc::dup(libc::STDOUT_FILENO) })?;
+        if let Err(e) = cvt(unsafe { libc::dup2(tty.as_raw_fd(), libc::STDOUT_FILENO) }) {
+            unsafe { libc::close(saved) };
+            return Err(e);
+        }
+        Ok(Self { saved, _tty: tty })
+    }
+}
+
+impl Drop for StdoutToTty {
+    fn drop(&mut self) {
+        io::stdout().flush().ok();
+        unsafe {
+            libc::dup2(self.saved, libc::STDOUT_FILENO);
+            libc::close(self.saved);
+        }
+    }
+}
+
+fn cvt(ret: libc::c_int) -> io::Result<libc::c_int> {
+    if ret == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
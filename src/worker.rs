@@ -0,0 +1,750 @@
+//! Runs git queries on a background thread, so drawing never waits for the repository.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use notify::{RecursiveMode, Watcher};
+
+use crate::{
+    git::{
+        BranchStatus, CherryPickStatus, Commit, Comparison, LogFilter, MergeOutcome, MergeStrategy,
+        Progress, RebaseStatus, RemoteBranch, Repository, SyncResult, WorkingTreeStatus,
+    },
+    util::event::Waker,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    CurrentBranch,
+    Logs {
+        branch: String,
+        limit: usize,
+        filter: LogFilter,
+    },
+    /// Ahead/behind and merged status of each branch, answered one branch at a time.
+    BranchStatuses(Vec<String>),
+    /// Fetches one remote or all of them, reporting progress on the way.
+    Fetch(Option<String>),
+    /// Staged, unstaged, untracked and conflicted files.
+    WorkingTree,
+    /// Rebases a branch onto another one, reporting progress on the way.
+    Rebase {
+        branch: String,
+        onto: String,
+    },
+    /// Goes on with the stopped rebase, committing the resolved commit or skipping it.
+    ContinueRebase {
+        skip: bool,
+    },
+    /// Commits the index with the message, running the pre-commit and commit-msg hooks.
+    Commit(String),
+    /// Pushes each branch, even if one of them fails.
+    Push {
+        branches: Vec<String>,
+        force: bool,
+    },
+    /// Deletes the branches, then the remote branches of the ones that are gone.
+    DeleteBranches {
+        branches: Vec<String>,
+        remote_branches: Vec<RemoteBranch>,
+    },
+    /// How two branches differ, with at most `limit` commits on each side.
+    Compare {
+        a: String,
+        b: String,
+        limit: usize,
+    },
+    /// The patch from the merge base of `from` and `to` to `to`.
+    Diff {
+        from: String,
+        to: String,
+    },
+    /// Fast-forwards each branch to its upstream.
+    Sync(Vec<String>),
+    /// Merges the branch into HEAD, stopping at conflicts.
+    Merge {
+        branch: String,
+        strategy: MergeStrategy,
+        message: String,
+    },
+    /// Cherry-picks the commits onto HEAD, stopping at the first conflict.
+    CherryPick(Vec<git2::Oid>),
+    /// Commits the resolved commit of the stopped cherry-pick, then picks the remaining ones.
+    ContinueCherryPick(Vec<git2::Oid>),
+    /// Changes the message of a commit of the branch, rebuilding the commits after it.
+    Reword {
+        branch: String,
+        oid: git2::Oid,
+        message: String,
+    },
+}
+
+#[derive(Debug)]
+pub enum Response {
+    CurrentBranch(Option<String>),
+    Logs {
+        branch: String,
+        /// The filter of the request, as the answer to an older filter may still arrive.
+        filter: LogFilter,
+        commits: Result<Vec<Commit>, String>,
+    },
+    BranchStatus {
+        branch: String,
+        status: BranchStatus,
+    },
+    /// Every branch of a `BranchStatuses` request has been answered.
+    BranchStatusesDone,
+    FetchProgress(Progress),
+    /// The fetched remotes.
+    Fetched(Result<Vec<String>, String>),
+    WorkingTree(Result<WorkingTreeStatus, String>),
+    /// The 1-based position of the commit being applied, and the number of commits.
+    RebaseProgress {
+        current: usize,
+        total: usize,
+    },
+    Rebased(Result<RebaseStatus, String>),
+    /// The abbreviated id of the new commit.
+    Committed(Result<String, String>),
+    /// Each pushed branch with its upstream, or why it was not pushed.
+    Pushed(Vec<(String, Result<String, String>)>),
+    /// Each branch and remote branch with why it was not deleted, if it was not.
+    Deleted {
+        branches: Vec<(String, Result<(), String>)>,
+        remote_branches: Vec<(String, Result<(), String>)>,
+    },
+    Synced(Vec<(String, Result<SyncResult, String>)>),
+    Comparison(Result<Comparison, String>),
+    Diff {
+        from: String,
+        to: String,
+        diff: Result<Vec<String>, String>,
+    },
+    Merged(Result<MergeOutcome, String>),
+    CherryPicked(Result<CherryPickStatus, String>),
+    /// The abbreviated id of the reworded commit.
+    Reworded(Result<String, String>),
+    Error(String),
+}
+
+impl Response {
+    /// Whether this is the last response to a request.
+    fn is_final(&self) -> bool {
+        !matches!(
+            self,
+            Response::BranchStatus { .. }
+                | Response::FetchProgress(_)
+                | Response::RebaseProgress { .. }
+        )
+    }
+
+    /// Whether this reports a change made to the repository rather than a query,
+    /// which stays relevant after `invalidate`.
+    fn is_outcome(&self) -> bool {
+        matches!(
+            self,
+            Response::FetchProgress(_)
+                | Response::Fetched(_)
+                | Response::RebaseProgress { .. }
+                | Response::Rebased(_)
+                | Response::Committed(_)
+                | Response::Pushed(_)
+                | Response::Deleted { .. }
+                | Response::Synced(_)
+                | Response::Merged(_)
+                | Response::CherryPicked(_)
+                | Response::Reworded(_)
+        )
+    }
+}
+
+/// Progress is reported at most this often, not to redraw for every object.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Handle to the worker thread. Responses to requests made before the last
+/// `invalidate` are dropped, and the thread stops when the handle is dropped.
+pub struct Worker {
+    tx: Option<mpsc::Sender<(u64, Request)>>,
+    rx: mpsc::Receiver<(u64, Response)>,
+    generation: Arc<AtomicU64>,
+    pending: usize,
+    waker: Arc<Mutex<Option<Waker>>>,
+    refs_changed: Arc<AtomicBool>,
+    index_changed: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+    _watcher: Option<notify::RecommendedWatcher>,
+}
+
+impl Worker {
+    pub fn spawn<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let repo = Repository::new(&path)?;
+        let waker: Arc<Mutex<Option<Waker>>> = Arc::new(Mutex::new(None));
+        let refs_changed = Arc::new(AtomicBool::new(false));
+        let index_changed = Arc::new(AtomicBool::new(false));
+        // the UI works without a watcher, it just won't notice changes made elsewhere
+        let watcher = watch_refs(
+            &repo,
+            refs_changed.clone(),
+            index_changed.clone(),
+            waker.clone(),
+        )
+        .ok();
+
+        let (tx, requests) = mpsc::channel::<(u64, Request)>();
+        let (responses, rx) = mpsc::channel();
+        let generation = Arc::new(AtomicU64::new(0));
+        let handle = {
+            let generation = generation.clone();
+            let waker = waker.clone();
+            thread::spawn(move || {
+                let send = |id: u64, response: Response| {
+                    let sent = responses.send((id, response)).is_ok();
+                    if let Some(waker) = waker.lock().unwrap().as_ref() {
+                        waker.wake();
+                    }
+                    sent
+                };
+                for (id, request) in requests {
+                    let stale = || generation.load(Ordering::Relaxed) != id;
+                    let sent = match request {
+                        Request::CurrentBranch => send(
+                            id,
+                            match repo.current_branch() {
+                                Ok(branch) => Response::CurrentBranch(branch),
+                                Err(e) => Response::Error(e.to_string()),
+                            },
+                        ),
+                        Request::Logs {
+                            branch,
+                            limit,
+                            filter,
+                        } => {
+                            let commits = repo
+                                .logs(&branch, limit, &filter)
+                                .map_err(|e| e.to_string());
+                            send(
+                                id,
+                                Response::Logs {
+                                    branch,
+                                    filter,
+                                    commits,
+                                },
+                            )
+                        }
+                        Request::BranchStatuses(branches) => {
+                            for branch in branches {
+                                if stale() {
+                                    break;
+                                }
+                                if let Ok(status) = repo.branch_status(&branch) {
+                                    if !send(id, Response::BranchStatus { branch, status }) {
+                                        return;
+                                    }
+                                }
+                            }
+                            send(id, Response::BranchStatusesDone)
+                        }
+                        Request::Fetch(remote) => {
+                            let mut reported = Instant::now();
+                            let fetched = repo.fetch(remote.as_deref(), |progress| {
+                                if reported.elapsed() >= PROGRESS_INTERVAL {
+                                    reported = Instant::now();
+                                    send(id, Response::FetchProgress(progress.clone()));
+                                }
+                            });
+                            send(id, Response::Fetched(fetched.map_err(|e| e.to_string())))
+                        }
+                        Request::WorkingTree => send(
+                            id,
+                            Response::WorkingTree(
+                                repo.working_tree_status().map_err(|e| e.to_string()),
+                            ),
+                        ),
+                        Request::Rebase { .. } | Request::ContinueRebase { .. } => {
+                            let mut reported: Option<Instant> = None;
+                            let progress = |current: usize, total: usize| {
+                                if reported.is_none_or(|at| at.elapsed() >= PROGRESS_INTERVAL) {
+                                    reported = Some(Instant::now());
+                                    send(id, Response::RebaseProgress { current, total });
+                                }
+                            };
+                            let rebased = match &request {
+                                Request::Rebase { branch, onto } => {
+                                    repo.rebase(branch, onto, progress)
+                                }
+                                Request::ContinueRebase { skip: true } => {
+                                    repo.skip_rebase(progress)
+                                }
+                                _ => repo.continue_rebase(progress),
+                            };
+                            send(id, Response::Rebased(rebased.map_err(|e| e.to_string())))
+                        }
+                        Request::Commit(message) => send(
+                            id,
+                            Response::Committed(repo.commit(&message).map_err(|e| e.to_string())),
+                        ),
+                        Request::Push { branches, force } => {
+                            let pushed = branches
+                                .into_iter()
+                                .map(|branch| {
+                                    let upstream =
+                                        repo.push(&branch, force).map_err(|e| e.to_string());
+                                    (branch, upstream)
+                                })
+                                .collect();
+                            send(id, Response::Pushed(pushed))
+                        }
+                        Request::DeleteBranches {
+                            branches,
+                            remote_branches,
+                        } => {
+                            let branches = branches
+                                .into_iter()
+                                .map(|branch| {
+                                    let deleted =
+                                        repo.delete_branch(&branch).map_err(|e| e.to_string());
+                                    (branch, deleted)
+                                })
+                                .collect::<Vec<_>>();
+                            // a remote branch has the name of its local branch,
+                            // see `Repository::remote_branch`
+                            let remote_branches = remote_branches
+                                .into_iter()
+                                .filter(|remote_branch| {
+                                    branches.iter().any(|(branch, deleted)| {
+                                        *branch == remote_branch.name && deleted.is_ok()
+                                    })
+                                })
+                                .map(|remote_branch| {
+                                    let deleted = repo
+                                        .delete_remote_branch(&remote_branch)
+                                        .map_err(|e| e.to_string());
+                                    (remote_branch.to_string(), deleted)
+                                })
+                                .collect();
+                            send(
+                                id,
+                                Response::Deleted {
+                                    branches,
+                                    remote_branches,
+                                },
+                            )
+                        }
+                        Request::Compare { a, b, limit } => send(
+                            id,
+                            Response::Comparison(
+                                repo.compare(&a, &b, limit).map_err(|e| e.to_string()),
+                            ),
+                        ),
+                        Request::Diff { from, to } => {
+                            let diff = repo.diff(&from, &to).map_err(|e| e.to_string());
+                            send(id, Response::Diff { from, to, diff })
+                        }
+                        Request::Sync(branches) => {
+                            let synced = branches
+                                .into_iter()
+                                .map(|branch| {
+                                    let result =
+                                        repo.fast_forward(&branch).map_err(|e| e.to_string());
+                                    (branch, result)
+                                })
+                                .collect();
+                            send(id, Response::Synced(synced))
+                        }
+                        Request::Merge {
+                            branch,
+                            strategy,
+                            message,
+                        } => send(
+                            id,
+                            Response::Merged(
+                                repo.merge(&branch, strategy, &message)
+                                    .map_err(|e| e.to_string()),
+                            ),
+                        ),
+                        Request::CherryPick(ids) => send(
+                            id,
+                            Response::CherryPicked(
+                                repo.cherry_pick(&ids).map_err(|e| e.to_string()),
+                            ),
+                        ),
+                        Request::ContinueCherryPick(remaining) => send(
+                            id,
+                            Response::CherryPicked(
+                                repo.continue_cherry_pick(&remaining)
+                                    .map_err(|e| e.to_string()),
+                            ),
+                        ),
+                        Request::Reword {
+                            branch,
+                            oid,
+                            message,
+                        } => send(
+                            id,
+                            Response::Reworded(
+                                repo.reword(&branch, oid, &message)
+                                    .map_err(|e| e.to_string()),
+                            ),
+                        ),
+                    };
+                    if !sent {
+                        return;
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            tx: Some(tx),
+            rx,
+            generation,
+            pending: 0,
+            waker,
+            refs_changed,
+            index_changed,
+            handle: Some(handle),
+            _watcher: watcher,
+        })
+    }
+
+    /// Wakes up the event loop whenever a response arrives or refs change.
+    pub fn set_waker(&self, waker: Waker) {
+        *self.waker.lock().unwrap() = Some(waker);
+    }
+
+    pub fn request(&mut self, request: Request) {
+        if let Some(tx) = self.tx.as_ref() {
+            if tx
+                .send((self.generation.load(Ordering::Relaxed), request))
+                .is_ok()
+            {
+                self.pending += 1;
+            }
+        }
+    }
+
+    /// Drops the answers to everything requested so far.
+    pub fn invalidate(&mut self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Whether HEAD or a ref changed on disk since the last call.
+    pub fn refs_changed(&self) -> bool {
+        self.refs_changed.swap(false, Ordering::Relaxed)
+    }
+
+    /// Whether the index changed on disk since the last call, e.g. by `git add`.
+    pub fn index_changed(&self) -> bool {
+        self.index_changed.swap(false, Ordering::Relaxed)
+    }
+
+    /// Whether every request has been answered.
+    pub fn is_idle(&self) -> bool {
+        self.pending == 0
+    }
+
+    /// Returns the next up-to-date response without blocking.
+    pub fn try_recv(&mut self) -> Option<Response> {
+        while let Ok((id, response)) = self.rx.try_recv() {
+            if let Some(response) = self.accept(id, response) {
+                return Some(response);
+            }
+        }
+        None
+    }
+
+    /// Waits up to `timeout` for the next up-to-date response.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Option<Response> {
+        while let Ok((id, response)) = self.rx.recv_timeout(timeout) {
+            if let Some(response) = self.accept(id, response) {
+                return Some(response);
+            }
+        }
+        None
+    }
+
+    fn accept(&mut self, id: u64, response: Response) -> Option<Response> {
+        if response.is_final() {
+            self.pending = self.pending.saturating_sub(1);
+        }
+        if response.is_outcome() || id == self.generation.load(Ordering::Relaxed) {
+            Some(response)
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        // closing the channel ends the thread after the request at hand
+        self.invalidate();
+        self.tx.take();
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+/// Watches `HEAD`, `refs/` and `packed-refs` for changes made by git-ex or anything else,
+/// and the index apart from them.
+fn watch_refs(
+    repo: &Repository,
+    changed: Arc<AtomicBool>,
+    index_changed: Arc<AtomicBool>,
+    waker: Arc<Mutex<Option<Waker>>>,
+) -> notify::Result<notify::RecommendedWatcher> {
+    // events carry resolved paths, while `commondir` is usually relative like `../..`
+    let git_dir = repo.git_dir().canonicalize()?;
+    let common_dir = repo.common_dir().canonicalize()?;
+    let refs_dir = common_dir.join("refs");
+    let index = git_dir.join("index");
+    let watched = {
+        let head = git_dir.join("HEAD");
+        let packed_refs = common_dir.join("packed-refs");
+        let refs_dir = refs_dir.clone();
+        move |path: &PathBuf| {
+            let is_lock = path.extension().map(|e| e == "lock").unwrap_or(false);
+            !is_lock && (path == &head || path == &packed_refs || path.starts_with(&refs_dir))
+        }
+    };
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let event = match event {
+            Ok(event) if !event.kind.is_access() => event,
+            _ => return,
+        };
+        let flags = [
+            (event.paths.iter().any(&watched), &changed),
+            (event.paths.contains(&index), &index_changed),
+        ];
+        for (relevant, flag) in flags {
+            if relevant && !flag.swap(true, Ordering::Relaxed) {
+                if let Some(waker) = waker.lock().unwrap().as_ref() {
+                    waker.wake();
+                }
+            }
+        }
+    })?;
+    // HEAD, packed-refs and the index are replaced by a rename, so watch the directories
+    // holding them
+    watcher.watch(&git_dir, RecursiveMode::NonRecursive)?;
+    if common_dir != git_dir {
+        watcher.watch(&common_dir, RecursiveMode::NonRecursive)?;
+    }
+    watcher.watch(&refs_dir, RecursiveMode::Recursive)?;
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempRepo;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn test_current_branch_and_logs() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("develop").checkout("develop");
+        let mut worker = Worker::spawn(temp.path())?;
+
+        worker.request(Request::CurrentBranch);
+        assert!(matches!(
+            worker.recv_timeout(TIMEOUT),
+            Some(Response::CurrentBranch(Some(branch))) if branch == "develop"
+        ));
+
+        worker.request(Request::Logs {
+            branch: "develop".into(),
+            limit: 10,
+            filter: LogFilter::default(),
+        });
+        match worker.recv_timeout(TIMEOUT) {
+            Some(Response::Logs {
+                branch, commits, ..
+            }) => {
+                assert_eq!(branch, "develop");
+                assert_eq!(commits.unwrap().len(), 1);
+            }
+            r => panic!("unexpected response: {:?}", r),
+        }
+        assert!(worker.is_idle());
+        Ok(())
+    }
+
+    #[test]
+    fn test_branch_statuses_stream() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("develop").branch("feature");
+        let mut worker = Worker::spawn(temp.path())?;
+
+        worker.request(Request::BranchStatuses(vec![
+            "develop".into(),
+            "feature".into(),
+        ]));
+        let mut branches = Vec::new();
+        while let Some(response) = worker.recv_timeout(TIMEOUT) {
+            match response {
+                Response::BranchStatus { branch, status } => {
+                    assert!(status.merged);
+                    branches.push(branch);
+                }
+                Response::BranchStatusesDone => break,
+                r => panic!("unexpected response: {:?}", r),
+            }
+        }
+        assert_eq!(branches, vec!["develop", "feature"]);
+        assert!(worker.is_idle());
+        Ok(())
+    }
+
+    #[test]
+    fn test_working_tree() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        std::fs::write(temp.path().join("new.txt"), "new")?;
+        let mut worker = Worker::spawn(temp.path())?;
+
+        worker.request(Request::WorkingTree);
+        match worker.recv_timeout(TIMEOUT) {
+            Some(Response::WorkingTree(Ok(status))) => {
+                assert_eq!(status.untracked, vec!["new.txt"]);
+                assert!(!status.is_dirty());
+            }
+            r => panic!("unexpected response: {:?}", r),
+        }
+        assert!(worker.is_idle());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalidate_drops_stale_responses() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        let mut worker = Worker::spawn(temp.path())?;
+
+        worker.request(Request::CurrentBranch);
+        worker.invalidate();
+        worker.request(Request::Logs {
+            branch: "master".into(),
+            limit: 1,
+            filter: LogFilter::default(),
+        });
+
+        assert!(matches!(
+            worker.recv_timeout(TIMEOUT),
+            Some(Response::Logs { .. })
+        ));
+        assert!(worker.is_idle());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_outlives_invalidate() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        let _origin = temp.add_remote("origin");
+        temp.push("origin", "master");
+        let mut worker = Worker::spawn(temp.path())?;
+
+        worker.request(Request::Fetch(None));
+        worker.invalidate();
+        loop {
+            match worker.recv_timeout(TIMEOUT) {
+                Some(Response::FetchProgress(_)) => {}
+                Some(Response::Fetched(remotes)) => {
+                    assert_eq!(remotes, Ok(vec!["origin".to_owned()]));
+                    break;
+                }
+                r => panic!("unexpected response: {:?}", r),
+            }
+        }
+        assert!(worker.is_idle());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rebase_outlives_invalidate() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        temp.branch("feature")
+            .commit_file("a.txt", "a", "add a")
+            .checkout("feature")
+            .commit_file("b.txt", "b", "add b")
+            .checkout("master");
+        let mut worker = Worker::spawn(temp.path())?;
+
+        worker.request(Request::Rebase {
+            branch: "feature".into(),
+            onto: "master".into(),
+        });
+        worker.invalidate();
+        loop {
+            match worker.recv_timeout(TIMEOUT) {
+                Some(Response::RebaseProgress { current, total }) => {
+                    assert_eq!((current, total), (1, 1));
+                }
+                Some(Response::Rebased(status)) => {
+                    assert_eq!(status, Ok(RebaseStatus::Done(1)));
+                    break;
+                }
+                r => panic!("unexpected response: {:?}", r),
+            }
+        }
+        assert!(worker.is_idle());
+        Ok(())
+    }
+
+    #[test]
+    fn test_refs_changed() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        let worker = Worker::spawn(temp.path())?;
+        assert!(!worker.refs_changed());
+
+        temp.branch("develop");
+
+        let start = std::time::Instant::now();
+        while !worker.refs_changed() {
+            assert!(start.elapsed() < TIMEOUT, "no change was noticed");
+            thread::sleep(Duration::from_millis(10));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_changed() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        let worker = Worker::spawn(temp.path())?;
+
+        temp.write("a.txt", "a").add("a.txt");
+
+        let start = std::time::Instant::now();
+        while !worker.index_changed() {
+            assert!(start.elapsed() < TIMEOUT, "no change was noticed");
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!worker.refs_changed());
+        Ok(())
+    }
+
+    #[test]
+    fn test_packed_refs_changed() -> anyhow::Result<()> {
+        let temp = TempRepo::new();
+        let worker = Worker::spawn(temp.path())?;
+
+        std::fs::write(
+            temp.repo.path().join("packed-refs"),
+            "# pack-refs with: peeled fully-peeled sorted \n",
+        )?;
+
+        let start = std::time::Instant::now();
+        while !worker.refs_changed() {
+            assert!(start.elapsed() < TIMEOUT, "no change was noticed");
+            thread::sleep(Duration::from_millis(10));
+        }
+        Ok(())
+    }
+}